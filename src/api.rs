@@ -1,7 +1,11 @@
 use async_trait::async_trait;
-use spinners::{Spinner, Spinners};
-use crate::convention::find_and_build_prompt;
+use serde::Serialize;
+use spinners::{Spinner, Spinners, Stream};
+use crate::convention::Convention;
 use crate::config::Config;
+use crate::prompt::{Prompt, PromptContext};
+use std::io::{self, IsTerminal};
+use std::time::Duration;
 
 /// Error type for LLM API operations
 #[derive(Debug)]
@@ -9,6 +13,16 @@ pub enum LlmError {
     ApiError(String),
     ConfigError(String),
     NetworkError(reqwest::Error),
+    /// Still rate limited after retrying; `retry_after` is the provider's last request to wait
+    RateLimited { message: String, retry_after: Option<Duration> },
+    /// No answer within this limit
+    Timeout(Duration),
+    /// The API key was rejected
+    Auth(String),
+    /// The prompt doesn't fit the model's context window
+    ContextLengthExceeded(String),
+    /// The provider kept failing with server errors
+    Unavailable(String),
 }
 
 impl From<reqwest::Error> for LlmError {
@@ -23,12 +37,123 @@ impl std::fmt::Display for LlmError {
             LlmError::ApiError(msg) => write!(f, "API Error: {}", msg),
             LlmError::ConfigError(msg) => write!(f, "Configuration Error: {}", msg),
             LlmError::NetworkError(err) => write!(f, "Network Error: {}", err),
+            LlmError::RateLimited { message, retry_after } => {
+                write!(f, "Rate limited: {}", message)?;
+                match retry_after {
+                    Some(wait) => write!(f, " (retry after {}s)", wait.as_secs()),
+                    None => Ok(()),
+                }
+            }
+            LlmError::Timeout(limit) => write!(f, "Timed out: no response within {}s", limit.as_secs()),
+            LlmError::Auth(msg) => write!(f, "Authentication failed: {}", msg),
+            LlmError::ContextLengthExceeded(msg) => write!(f, "Prompt too long: {}", msg),
+            LlmError::Unavailable(msg) => write!(f, "Provider unavailable: {}", msg),
         }
     }
 }
 
 impl std::error::Error for LlmError {}
 
+impl LlmError {
+    /// Stable machine-readable code for this error
+    pub fn code(&self) -> &'static str {
+        match self {
+            LlmError::ApiError(_) => "api_error",
+            LlmError::ConfigError(_) => "config_error",
+            LlmError::NetworkError(_) => "network_error",
+            LlmError::RateLimited { .. } => "rate_limited",
+            LlmError::Timeout(_) => "timeout",
+            LlmError::Auth(_) => "auth_error",
+            LlmError::ContextLengthExceeded(_) => "context_length_exceeded",
+            LlmError::Unavailable(_) => "provider_unavailable",
+        }
+    }
+
+    /// Whether another attempt, or another provider, might succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            LlmError::NetworkError(_) | LlmError::RateLimited { .. } | LlmError::Timeout(_) | LlmError::Unavailable(_)
+        )
+    }
+
+    /// What the user can do about this error, if there is something
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            LlmError::ApiError(_) | LlmError::ConfigError(_) => None,
+            LlmError::NetworkError(_) => Some("Check your internet connection and proxy settings."),
+            LlmError::RateLimited { .. } => Some(
+                "Wait a minute and try again. If it keeps happening, check your plan's rate limits or lower candidate-count.",
+            ),
+            LlmError::Timeout(_) => {
+                Some("Try again, or allow more time with `committo set request-timeout <seconds>`.")
+            }
+            LlmError::Auth(_) => Some("Check the key with `committo set api-key <key>` and that it can use the model."),
+            LlmError::ContextLengthExceeded(_) => Some(
+                "Commit fewer files at once (`committo -- <paths>`), lower diff-context, or choose a model with a larger context window.",
+            ),
+            LlmError::Unavailable(_) => Some(
+                "Try again later, or list providers to fall back to with `committo set fallback <provider:model>,...`.",
+            ),
+        }
+    }
+}
+
+/// Token usage reported by a provider
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+/// Raw provider response together with its metadata
+#[derive(Debug, Clone, Default)]
+pub struct Completion {
+    pub content: String,
+    pub usage: Option<TokenUsage>,
+}
+
+/// Author of a message in a provider conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+impl Role {
+    /// Role name as used by chat completion APIs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+        }
+    }
+}
+
+/// A single message in a provider conversation
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self { role: Role::System, content: content.into() }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self { role: Role::User, content: content.into() }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self { role: Role::Assistant, content: content.into() }
+    }
+}
+
 /// Configuration for LLM providers
 #[derive(Clone)]
 pub struct LlmConfig {
@@ -45,8 +170,16 @@ pub trait LlmProvider: Send + Sync {
     /// Get provider name for display
     fn get_provider_name(&self) -> String;
     
-    /// Generate commit message using this provider (implementation-specific)
-    async fn generate_commit_message_impl(&self, system_prompt: &str, diff: &str) -> Result<String, LlmError>;
+    /// Generate commit message using this provider (implementation-specific).
+    /// `messages` starts with the system prompt, followed by the diff and any follow-up turns.
+    async fn generate_commit_message_impl(&self, messages: &[ChatMessage]) -> Result<String, LlmError>;
+
+    /// Generate commit message along with response metadata.
+    /// Providers that report token usage should override this.
+    async fn generate_completion_impl(&self, messages: &[ChatMessage]) -> Result<Completion, LlmError> {
+        let content = self.generate_commit_message_impl(messages).await?;
+        Ok(Completion { content, usage: None })
+    }
     
     /// Get API key from internal config
     fn get_api_key(&self) -> Result<String, LlmError>;
@@ -54,11 +187,23 @@ pub trait LlmProvider: Send + Sync {
     /// Get app config reference
     fn get_app_config(&self) -> &Config;
     
-    /// Print dry run information
-    fn print_dry_run_info(&self, system_prompt: &str, diff: &str) {
+    /// Print dry run information; `prompt` supplies the convention and templates in use
+    fn print_dry_run_info(&self, prompt: &Prompt, system_prompt: &str, diff: &str) {
         println!("--- Dry Run ---");
         print!("{}", self.get_app_config().show_masking_config());
 
+        let outline = prompt.convention.outline();
+        if !outline.is_empty() {
+            println!("\n--- Convention ---");
+            print!("{}", outline);
+        }
+        if !prompt.templates.overrides().is_empty() {
+            println!("\n--- Prompt Templates ---");
+            for path in prompt.templates.overrides() {
+                println!("{}", path.display());
+            }
+        }
+
         println!("\n--- Prompt ---");
         println!("{system_prompt}");
         println!("\n--- Git Diff ---");
@@ -69,40 +214,164 @@ pub trait LlmProvider: Send + Sync {
     /// Get candidate count from internal config
     fn get_candidate_count(&self) -> u32;
     
-    /// Main generate commit message method (with dry run support)
+    /// Main generate commit message method (with dry run support), using the built-in
+    /// templates and the conventions that apply to the whole repository
     async fn generate_commit_message(&self, diff: &str, dry_run: bool) -> Result<String, LlmError> {
+        let prompt = Prompt {
+            context: PromptContext { diff: diff.to_string(), ..Default::default() },
+            convention: Convention::for_changes("").unwrap_or_default(),
+            ..Default::default()
+        };
+        self.generate_completion(&prompt, dry_run).await.map(|completion| completion.content)
+    }
+
+    /// Send the system prompt and first user message rendered from `prompt`, keeping the response metadata
+    async fn generate_completion(&self, prompt: &Prompt, dry_run: bool) -> Result<Completion, LlmError> {
         // Always check API key first, even for dry run
         self.get_api_key()?;
-        
-        // Get candidate count from config
-        let candidate_count = self.get_candidate_count();
-        
-        let guideline = "**IMPORTANT PRIORITY RULES:**\n- Numbers indicate priority: 1 = HIGHEST priority, 2, 3, 4, 5... = lower priority\n- When instructions conflict, ALWAYS follow the higher priority (lower number)\n- Apply these rules when analyzing git diff and generating commit messages\n";
-        let custom_conventions = find_and_build_prompt().unwrap_or_default();
-        let default_system_prompt = "You are an AI assistant that helps programmers who struggle with writing commit messages. Based on the following diff, generate a concise and informative commit message.".to_string();
-        let mut system_prompt = if custom_conventions.is_empty() {
-            default_system_prompt
-        } else {
-            format!("{}\n\n{}\n{}",default_system_prompt, guideline, custom_conventions)
-        };
 
-        // Modify prompt for multiple candidates
-        if candidate_count > 1 {
-            system_prompt = format!("{}\n\nGenerate {} different commit message options. Each message should be on a separate line and be concise and informative.", system_prompt, candidate_count);
-        }
+        let system_prompt = self.build_system_prompt(prompt);
+        let diff = prompt.user(self.get_candidate_count()).map_err(|e| LlmError::ConfigError(e.to_string()))?;
 
         if dry_run {
-            self.print_dry_run_info(&system_prompt, diff);
-            return Ok("Dry run complete.".to_string());
+            self.print_dry_run_info(prompt, &system_prompt, &diff);
+            return Ok(Completion { content: "Dry run complete.".to_string(), usage: None });
         }
 
-        let mut sp = Spinner::new(Spinners::BouncingBall, "Generating Commit Message...".into());
-        let response= self.generate_commit_message_impl(&system_prompt, diff).await;
-        sp.stop_and_persist("✔", "How about these? ".into());
+        let messages = [ChatMessage::system(system_prompt), ChatMessage::user(diff)];
+        self.continue_conversation(&messages).await
+    }
+
+    /// Render the system prompt template with the conventions and candidate count
+    fn build_system_prompt(&self, prompt: &Prompt) -> String {
+        prompt.system(self.get_candidate_count())
+    }
+
+    /// Send a conversation (system prompt, diff and any follow-up turns) to the provider
+    async fn continue_conversation(&self, messages: &[ChatMessage]) -> Result<Completion, LlmError> {
+        // On stderr and only for a person watching, so stdout stays clean for `--format json` and `--print`
+        let mut sp = io::stderr()
+            .is_terminal()
+            .then(|| Spinner::with_stream(Spinners::BouncingBall, "Generating Commit Message...".into(), Stream::Stderr));
+        let response = self.generate_completion_impl(messages).await;
+        if let Some(sp) = &mut sp {
+            sp.stop_and_persist("✔", "How about these? ".into());
+        }
         response
     }
 }
 
+/// A conversation with the provider that grows with each retry
+#[derive(Debug, Clone)]
+pub struct Conversation {
+    messages: Vec<ChatMessage>,
+}
+
+impl Conversation {
+    /// Start a conversation from the system prompt and the diff
+    pub fn new(system_prompt: impl Into<String>, diff: impl Into<String>) -> Self {
+        Self {
+            messages: vec![ChatMessage::system(system_prompt), ChatMessage::user(diff)],
+        }
+    }
+
+    /// All messages so far, oldest first
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    /// Record a provider response
+    pub fn push_response(&mut self, content: impl Into<String>) {
+        self.messages.push(ChatMessage::assistant(content));
+    }
+
+    /// Ask for new candidates, taking the user's feedback on the previous ones into account
+    pub fn push_feedback(&mut self, feedback: &str, candidate_count: u32) {
+        let mut content = "None of these commit messages are quite right.".to_string();
+        if feedback.trim().is_empty() {
+            content.push_str(" Try noticeably different wording or focus.");
+        } else {
+            content.push_str(&format!(" What should change: {}", feedback.trim()));
+        }
+        if candidate_count > 1 {
+            content.push_str(&format!("\nGenerate {} different commit message options that take this into account, in the same format as before.", candidate_count));
+        } else {
+            content.push_str("\nWrite a new commit message that takes this into account. Reply with only the commit message.");
+        }
+        self.messages.push(ChatMessage::user(content));
+    }
+
+    /// Ask for a revision of one candidate
+    pub fn push_refinement(&mut self, candidate: &str, instruction: &str) {
+        self.messages.push(ChatMessage::user(format!(
+            "Revise this commit message: \"{}\"\nWhat should change: {}\nReply with only the revised commit message.",
+            candidate, instruction
+        )));
+    }
+
+    /// Ask for a replacement for one candidate
+    pub fn push_regeneration(&mut self, candidate: &str) {
+        self.messages.push(ChatMessage::user(format!(
+            "Write a different commit message to replace \"{}\". It should not repeat any of the messages above.\nReply with only the new commit message.",
+            candidate
+        )));
+    }
+
+    /// Record that the user rewrote a candidate by hand, so later turns start from their version
+    pub fn push_edit(&mut self, original: &str, edited: &str) {
+        self.messages.push(ChatMessage::user(format!(
+            "I rewrote \"{}\" as:\n{}\nUse my version from now on.",
+            original, edited
+        )));
+        self.messages.push(ChatMessage::assistant(edited));
+    }
+}
+
+/// Send the user's feedback on the previous candidates and return the new response.
+/// Both the feedback and the response are appended to `conversation`.
+pub async fn retry_with_feedback(
+    provider: &dyn LlmProvider,
+    conversation: &mut Conversation,
+    feedback: &str,
+) -> Result<String, LlmError> {
+    conversation.push_feedback(feedback, provider.get_candidate_count());
+    let completion = provider.continue_conversation(conversation.messages()).await?;
+    conversation.push_response(completion.content.clone());
+    Ok(completion.content)
+}
+
+/// Revise one candidate according to a user instruction, as a follow-up turn in the conversation.
+/// Both the instruction and the revised message are appended to `conversation`.
+pub async fn refine_candidate(
+    provider: &dyn LlmProvider,
+    conversation: &mut Conversation,
+    candidate: &str,
+    instruction: &str,
+) -> Result<String, LlmError> {
+    conversation.push_refinement(candidate, instruction);
+    continue_with(provider, conversation).await
+}
+
+/// Generate a replacement for the candidate at `index`, keeping the others for context.
+/// Both the request and the new message are appended to `conversation`.
+pub async fn regenerate_candidate(
+    provider: &dyn LlmProvider,
+    conversation: &mut Conversation,
+    candidates: &[String],
+    index: usize,
+) -> Result<String, LlmError> {
+    conversation.push_regeneration(&candidates[index]);
+    continue_with(provider, conversation).await
+}
+
+/// Send `conversation` and append the trimmed reply to it
+async fn continue_with(provider: &dyn LlmProvider, conversation: &mut Conversation) -> Result<String, LlmError> {
+    let completion = provider.continue_conversation(conversation.messages()).await?;
+    let content = completion.content.trim().to_string();
+    conversation.push_response(content.clone());
+    Ok(content)
+}
+
 /// Generate commit message using provided LLM provider
 pub async fn generate_commit_message_with_provider(
     provider: &dyn LlmProvider,
@@ -115,6 +384,6 @@ pub async fn generate_commit_message_with_provider(
 /// Generate commit message using default provider (for backward compatibility)
 pub async fn generate_commit_message(diff: &str, dry_run: bool) -> Result<String, LlmError> {
     let config = Config::default();
-    let provider = crate::providers::ProviderFactory::create_provider(config);
+    let provider = crate::providers::ProviderFactory::create_provider(config).map_err(|e| LlmError::ConfigError(e.to_string()))?;
     generate_commit_message_with_provider(provider.as_ref(), diff, dry_run).await
 }
\ No newline at end of file
@@ -1,4 +1,8 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::io;
+
+use crate::commit::CommitArgs;
+use crate::git::{ChangeScope, DiffAlgorithm, DiffOptions, RenameDetection};
 
 const VERSION: &str = match option_env!("BUILD_VERSION") {
     Some(v) => v,
@@ -15,6 +19,119 @@ pub struct Cli {
     /// Run in dry-run mode to see the generated prompt without calling the API
     #[arg(long, global = true)]
     pub dry_run: bool,
+
+    /// Commit the first candidate without prompting
+    #[arg(short = 'y', long, global = true, conflicts_with_all = ["pick", "print"])]
+    pub yes: bool,
+
+    /// Commit the n-th candidate (starting at 1) without prompting
+    #[arg(long, value_name = "N", global = true, conflicts_with = "print", value_parser = clap::value_parser!(u32).range(1..))]
+    pub pick: Option<u32>,
+
+    /// Print the candidates to stdout, one per line, without committing
+    #[arg(long, global = true)]
+    pub print: bool,
+
+    /// Output format; `json` prints candidates and metadata without prompting or committing
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, global = true)]
+    pub format: OutputFormat,
+
+    /// Also commit modified and deleted tracked files, like `git commit -a`
+    #[arg(short = 'a', long, global = true)]
+    pub all: bool,
+
+    /// Also commit untracked files that aren't ignored
+    #[arg(long, global = true)]
+    pub include_untracked: bool,
+
+    /// Lines of context around each change in the described diff
+    #[arg(short = 'U', long, global = true, value_name = "N")]
+    pub unified: Option<u32>,
+
+    /// Show whole enclosing functions as context
+    #[arg(short = 'W', long, global = true)]
+    pub function_context: bool,
+
+    /// Diff algorithm for the described diff
+    #[arg(long, value_enum, global = true)]
+    pub diff_algorithm: Option<DiffAlgorithm>,
+
+    /// Ignore whitespace-only changes in the described diff (they are still committed)
+    #[arg(short = 'w', long, global = true)]
+    pub ignore_whitespace: bool,
+
+    /// Rename and copy detection for the described diff
+    #[arg(long, value_enum, global = true)]
+    pub renames: Option<RenameDetection>,
+
+    /// Add a `Signed-off-by` trailer with your git identity
+    #[arg(short = 's', long, global = true)]
+    pub signoff: bool,
+
+    /// Add a `Co-authored-by` trailer: `Name <email>`, or part of the name or email of
+    /// someone in `co-authors` or the recent history
+    #[arg(long = "co-author", value_name = "WHO", global = true)]
+    pub co_authors: Vec<String>,
+
+    /// Choose co-authors from `co-authors` and the recent history
+    #[arg(long, global = true)]
+    pub choose_co_authors: bool,
+
+    /// Options for `git commit` (`-S`, `--no-verify`, `--author=<A>`...) and paths to commit;
+    /// with another `--`, everything before it is an option and everything after it a path
+    #[arg(last = true, global = true, value_name = "GIT_ARGS")]
+    pub git_args: Vec<String>,
+}
+
+/// How generated candidates (and errors) are reported
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl Cli {
+    /// The arguments after `--`, sorted into `git commit` options and paths
+    pub fn commit_args(&self) -> io::Result<CommitArgs> {
+        CommitArgs::parse(&self.git_args)
+    }
+
+    /// Which changes to describe and commit
+    pub fn change_scope(&self) -> io::Result<ChangeScope> {
+        let paths = self.commit_args()?.paths;
+        if self.all && !paths.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--all can't be combined with paths; commit either everything or the listed paths",
+            ));
+        }
+        Ok(ChangeScope {
+            all: self.all,
+            include_untracked: self.include_untracked,
+            paths,
+        })
+    }
+
+    /// Diff options from the config, with command-line flags taking precedence
+    pub fn diff_options(&self, configured: DiffOptions) -> DiffOptions {
+        DiffOptions {
+            context_lines: self.unified.unwrap_or(configured.context_lines),
+            function_context: self.function_context || configured.function_context,
+            algorithm: self.diff_algorithm.or(configured.algorithm),
+            ignore_whitespace: self.ignore_whitespace || configured.ignore_whitespace,
+            renames: self.renames.unwrap_or(configured.renames),
+        }
+    }
+
+    /// Index of the candidate to commit without prompting, if one was chosen on the command line
+    pub fn preselected_index(&self) -> Option<usize> {
+        if self.yes {
+            Some(0)
+        } else {
+            self.pick.map(|n| n as usize - 1)
+        }
+    }
 }
 
 #[derive(Subcommand, Debug, PartialEq)]
@@ -30,4 +147,51 @@ pub enum Commands {
     Show,
     /// Generate a commit message
     Generate,
-}
\ No newline at end of file
+    /// Split the staged changes into several commits, one per logical change
+    Split,
+    /// Inspect and maintain the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Create, inspect and test .committoconvention files
+    Convention {
+        #[command(subcommand)]
+        command: ConventionCommands,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum ConfigCommands {
+    /// Report deprecated, invalid or unused settings
+    Doctor,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum ConventionCommands {
+    /// Create a .committoconvention file at the repository root
+    Init {
+        /// Start from this built-in preset
+        #[arg(long, conflicts_with = "from_history")]
+        preset: Option<String>,
+        /// Propose rules based on the existing commit messages
+        #[arg(long)]
+        from_history: bool,
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Report the style of recent commits, used when there is no convention file
+    Analyze {
+        /// How many recent commits to look at
+        #[arg(long, default_value_t = 200)]
+        limit: usize,
+    },
+    /// Show the convention files that apply to the staged changes and the prompt they produce
+    Show,
+    /// Check a commit message against the convention's rules (`-` reads it from stdin)
+    Check {
+        /// The commit message
+        message: String,
+    },
+}
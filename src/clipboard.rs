@@ -0,0 +1,46 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Clipboard tools to try, in order, as (program, args)
+fn clipboard_commands() -> &'static [(&'static str, &'static [&'static str])] {
+    if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else if cfg!(target_os = "windows") {
+        &[("clip", &[])]
+    } else {
+        &[
+            ("wl-copy", &[]),
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ]
+    }
+}
+
+/// Copy text to the system clipboard using the platform's clipboard tool
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    for (program, args) in clipboard_commands() {
+        // A tool that isn't installed just moves us on to the next one
+        let Ok(mut child) = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+
+    let tried: Vec<&str> = clipboard_commands().iter().map(|(program, _)| *program).collect();
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("No working clipboard tool found (tried: {})", tried.join(", ")),
+    ))
+}
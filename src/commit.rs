@@ -1,16 +1,275 @@
+use clap::ValueEnum;
+use std::fmt;
 use std::io::{self, Write};
+use std::path::PathBuf;
 use std::process::{Command, Stdio};
 
+use crate::git::{self, value_name};
+
+/// Developer Certificate of Origin sign-off
+pub const SIGNED_OFF_BY: &str = "Signed-off-by";
+pub const CO_AUTHORED_BY: &str = "Co-authored-by";
+
+/// How many commits are searched for co-authors
+const CO_AUTHOR_HISTORY: usize = 200;
+
+/// Trailers added to the message when committing, never written by the model
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trailers {
+    /// (key, value) in the order they are added
+    pub entries: Vec<(String, String)>,
+}
+
+impl Trailers {
+    pub fn push(&mut self, key: &str, value: &str) {
+        self.entries.push((key.to_string(), value.to_string()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The message with the trailers added, following `git interpret-trailers`
+    pub fn apply(&self, message: &str) -> io::Result<String> {
+        if self.is_empty() {
+            return Ok(message.to_string());
+        }
+        git::interpret_trailers(message, &self.entries)
+    }
+
+    /// The message with the trailers appended as a new paragraph, without asking git.
+    /// Close enough to [`Trailers::apply`] for checking a candidate against the convention.
+    pub fn preview(&self, message: &str) -> String {
+        if self.is_empty() {
+            return message.to_string();
+        }
+        let lines: Vec<String> = self.entries.iter().map(|(key, value)| format!("{}: {}", key, value)).collect();
+        format!("{}\n\n{}", message.trim_end(), lines.join("\n"))
+    }
+
+    /// `message` without trailers the model has no business writing: sign-offs,
+    /// co-authors and any key added here
+    pub fn strip(&self, message: &str) -> String {
+        let managed = |line: &str| {
+            line.split_once(':').is_some_and(|(key, _)| {
+                let key = key.trim();
+                [SIGNED_OFF_BY, CO_AUTHORED_BY].iter().any(|managed| managed.eq_ignore_ascii_case(key))
+                    || self.entries.iter().any(|(managed, _)| managed.eq_ignore_ascii_case(key))
+            })
+        };
+        let lines: Vec<&str> = message.lines().filter(|line| !managed(line)).collect();
+        lines.join("\n").trim_end().to_string()
+    }
+}
+
+/// Split a `Key: value` trailer
+pub fn parse_trailer(trailer: &str) -> Option<(&str, &str)> {
+    let (key, value) = trailer.split_once(':')?;
+    let (key, value) = (key.trim(), value.trim());
+    let valid_key = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    (valid_key && !value.is_empty()).then_some((key, value))
+}
+
+/// Whether `person` looks like `Name <email>`
+pub fn is_identity(person: &str) -> bool {
+    let person = person.trim();
+    person
+        .strip_suffix('>')
+        .and_then(|rest| rest.split_once(" <"))
+        .is_some_and(|(name, email)| !name.trim().is_empty() && email.contains('@'))
+}
+
+/// People who can be added as co-authors: the configured list, then recent
+/// authors of the repository, without the committer
+pub fn known_co_authors(configured: &[String]) -> io::Result<Vec<String>> {
+    let me = git::committer_identity().ok();
+    let mut people: Vec<String> = Vec::new();
+    for person in configured.iter().cloned().chain(git::recent_authors(CO_AUTHOR_HISTORY)?) {
+        if Some(&person) != me.as_ref() && !people.contains(&person) {
+            people.push(person);
+        }
+    }
+    Ok(people)
+}
+
+/// The co-author meant by `who`: either a full `Name <email>`, or part of the
+/// name or email of exactly one of `known`
+pub fn resolve_co_author(who: &str, known: &[String]) -> io::Result<String> {
+    if is_identity(who) {
+        return Ok(who.trim().to_string());
+    }
+    let needle = who.trim().to_lowercase();
+    let matches: Vec<&String> = known.iter().filter(|person| person.to_lowercase().contains(&needle)).collect();
+    match matches.as_slice() {
+        [person] => Ok((*person).clone()),
+        [] => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no known co-author matches `{}`; use the full `Name <email>` form", who),
+        )),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "`{}` matches several co-authors: {}",
+                who,
+                matches.iter().map(|person| person.as_str()).collect::<Vec<_>>().join(", ")
+            ),
+        )),
+    }
+}
+
+/// `git commit` options whose value may be the next argument; without a second `--`
+/// that would be ambiguous with a path, so they must be written with their value
+const VALUE_OPTIONS: &[&str] = &["--author", "--date", "--fixup", "--squash", "--cleanup", "-t", "--template", "--trailer"];
+
+/// `git commit` options that would fight with how committo commits, and what to do instead
+const REJECTED_OPTIONS: &[(&[&str], &str)] = &[
+    (&["-m", "--message", "-F", "--file", "-C", "--reuse-message", "-c", "--reedit-message"], "committo writes the message"),
+    (&["-a", "--all"], "use `committo --all`"),
+    (&["-i", "--include", "-o", "--only", "--pathspec-from-file"], "list the paths after `--` instead"),
+    (&["-p", "--patch", "--interactive"], "stage the hunks with `git add -p` first"),
+    (&["--dry-run"], "use `committo --dry-run`"),
+];
+
+/// Arguments after `--` on the command line: options for `git commit`, then the
+/// paths to commit. Plain paths may also come without options, as in `committo -- src/`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommitArgs {
+    pub options: Vec<String>,
+    pub paths: Vec<String>,
+}
+
+impl CommitArgs {
+    /// Sort `args` into options and paths. With a second `--`, everything before it is an
+    /// option and everything after it a path. Without one, arguments starting with a dash
+    /// are options and must carry their value, as in `--author=<name>` or `-S<key>`.
+    pub fn parse(args: &[String]) -> io::Result<Self> {
+        let parsed = match args.iter().position(|arg| arg == "--") {
+            Some(end) => CommitArgs { options: args[..end].to_vec(), paths: args[end + 1..].to_vec() },
+            None => {
+                let (options, paths): (Vec<String>, Vec<String>) =
+                    args.iter().cloned().partition(|arg| arg.starts_with('-') && arg != "-");
+                if let Some(option) = options.iter().find(|option| VALUE_OPTIONS.contains(&option.as_str())) {
+                    let joined = if option.starts_with("--") { format!("{}=<value>", option) } else { format!("{}<value>", option) };
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("`{}` needs its value in the same argument, as in `{}`, or another `--` before the paths", option, joined),
+                    ));
+                }
+                CommitArgs { options, paths }
+            }
+        };
+
+        for option in parsed.options.iter().filter(|arg| arg.starts_with('-')) {
+            let name = option_name(option);
+            if let Some((_, instead)) = REJECTED_OPTIONS.iter().find(|(names, _)| names.contains(&name)) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("`{}` can't be passed to git commit: {}", option, instead),
+                ));
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Whether git is told to commit even without changes
+    pub fn allows_empty(&self) -> bool {
+        self.options.iter().any(|option| option == "--allow-empty")
+    }
+}
+
+/// `--author` for `--author=x`, `-S` for `-Skey`
+fn option_name(arg: &str) -> &str {
+    if arg.starts_with("--") {
+        arg.split('=').next().unwrap_or(arg)
+    } else {
+        let end = arg.char_indices().nth(2).map_or(arg.len(), |(i, _)| i);
+        &arg[..end]
+    }
+}
+
+/// Value of git's `gpg.format`
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningFormat {
+    Openpgp,
+    Ssh,
+    X509,
+}
+
+/// How commits are signed; anything unset is left to git's own config
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Signing {
+    /// Sign every commit (`--gpg-sign`)
+    pub sign: bool,
+    /// Key to sign with, as for `user.signingKey`
+    pub key: Option<String>,
+    pub format: Option<SigningFormat>,
+}
+
+/// `git commit` exited unsuccessfully, most often because a hook rejected the commit.
+/// Its own output has already gone to the terminal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitFailed {
+    /// Exit code of git, `None` if it was killed by a signal
+    pub code: Option<i32>,
+}
+
+impl fmt::Display for CommitFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "git commit exited with code {}", code)?,
+            None => write!(f, "git commit was killed")?,
+        }
+        write!(f, "; see its output above. Pass `-- --no-verify` to skip the commit hooks.")
+    }
+}
+
+impl std::error::Error for CommitFailed {}
+
+/// Options for running `git commit`
+#[derive(Debug, Clone, Default)]
+pub struct CommitOptions {
+    /// Open the editor before committing (`--edit`)
+    pub run_edit: bool,
+    /// Commit from this index instead of the regular one
+    pub index_file: Option<PathBuf>,
+    /// Added to the message before it is committed (or edited)
+    pub trailers: Trailers,
+    pub signing: Signing,
+    /// Passed to `git commit` as they are, after everything else
+    pub extra_args: Vec<String>,
+}
+
 /// Execute git commit with the message piped to it
 /// This automatically runs: echo "message" | git commit --edit -F - (with --edit)
 /// or echo "message" | git commit -F - (without --edit)
 pub fn execute_git_commit_with_pipe(message: &str, run_edit: bool) -> io::Result<()> {
+    execute_git_commit(message, &CommitOptions { run_edit, ..Default::default() })
+}
+
+/// Execute git commit with the message piped to it, using the given options
+pub fn execute_git_commit(message: &str, options: &CommitOptions) -> io::Result<()> {
+    let message = options.trailers.apply(message)?;
     let mut cmd = Command::new("git");
+    if let Some(key) = &options.signing.key {
+        cmd.arg("-c").arg(format!("user.signingKey={}", key));
+    }
+    if let Some(format) = options.signing.format {
+        cmd.arg("-c").arg(format!("gpg.format={}", value_name(format)));
+    }
     cmd.arg("commit");
     
-    if run_edit {
+    if options.run_edit {
         cmd.arg("--edit");
     }
+    if options.signing.sign {
+        cmd.arg("--gpg-sign");
+    }
+    // Last, so that e.g. `--no-gpg-sign` wins over the config
+    cmd.args(&options.extra_args);
+
+    if let Some(index_file) = &options.index_file {
+        cmd.env("GIT_INDEX_FILE", index_file);
+    }
     
     cmd.arg("-F")
        .arg("-") // Read from stdin
@@ -35,12 +294,97 @@ pub fn execute_git_commit_with_pipe(message: &str, run_edit: bool) -> io::Result
     let status = child.wait()?;
     
     if !status.success() {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("git commit failed with exit code: {}", status.code().unwrap_or(-1))
-        ));
+        return Err(io::Error::other(CommitFailed { code: status.code() }));
     }
     
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trailers(entries: &[(&str, &str)]) -> Trailers {
+        let mut trailers = Trailers::default();
+        for (key, value) in entries {
+            trailers.push(key, value);
+        }
+        trailers
+    }
+
+    #[test]
+    fn test_strip_removes_managed_trailers_only() {
+        let trailers = trailers(&[("Refs", "#12")]);
+        let message = "feat: add login\n\nBody.\n\nSigned-off-by: Model <m@example.com>\nrefs: #99\nReviewed-by: Ann <ann@example.com>";
+        assert_eq!(trailers.strip(message), "feat: add login\n\nBody.\n\nReviewed-by: Ann <ann@example.com>");
+        assert_eq!(Trailers::default().strip("feat: x\n\nCo-authored-by: A <a@b.c>"), "feat: x");
+    }
+
+    #[test]
+    fn test_preview_appends_a_paragraph() {
+        let trailers = trailers(&[(CO_AUTHORED_BY, "Ann <ann@example.com>"), (SIGNED_OFF_BY, "Me <me@example.com>")]);
+        assert_eq!(
+            trailers.preview("feat: x\n"),
+            "feat: x\n\nCo-authored-by: Ann <ann@example.com>\nSigned-off-by: Me <me@example.com>"
+        );
+        assert_eq!(Trailers::default().preview("feat: x"), "feat: x");
+    }
+
+    #[test]
+    fn test_parse_trailer_and_identity() {
+        assert_eq!(parse_trailer("Refs: #12, #13"), Some(("Refs", "#12, #13")));
+        assert_eq!(parse_trailer("Not a trailer"), None);
+        assert_eq!(parse_trailer("Two words: x"), None);
+        assert!(is_identity("Ann Lee <ann@example.com>"));
+        assert!(!is_identity("ann@example.com"));
+        assert!(!is_identity("<ann@example.com>"));
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_commit_args_split_options_and_paths() {
+        let parsed = CommitArgs::parse(&args(&["-S", "--no-verify", "--author", "Ann <a@b.c>", "--date=now", "--", "src/", "-odd"])).unwrap();
+        assert_eq!(parsed.options, args(&["-S", "--no-verify", "--author", "Ann <a@b.c>", "--date=now"]));
+        assert_eq!(parsed.paths, args(&["src/", "-odd"]));
+
+        // Without a second `--`, options carry their values
+        let parsed = CommitArgs::parse(&args(&["--author=Ann <a@b.c>", "-Skey", "src/"])).unwrap();
+        assert_eq!(parsed.options, args(&["--author=Ann <a@b.c>", "-Skey"]));
+        assert_eq!(parsed.paths, args(&["src/"]));
+
+        // Plain paths, as before options could be passed
+        let parsed = CommitArgs::parse(&args(&["src/api.rs", "docs/"])).unwrap();
+        assert!(parsed.options.is_empty());
+        assert_eq!(parsed.paths, args(&["src/api.rs", "docs/"]));
+
+        assert!(CommitArgs::parse(&args(&["--allow-empty"])).unwrap().allows_empty());
+    }
+
+    #[test]
+    fn test_commit_args_reject_conflicting_options() {
+        let error = CommitArgs::parse(&args(&["-m", "message"])).unwrap_err();
+        assert_eq!(error.to_string(), "`-m` can't be passed to git commit: committo writes the message");
+        assert!(CommitArgs::parse(&args(&["--message=x"])).is_err());
+        assert!(CommitArgs::parse(&args(&["--all", "--"])).is_err());
+
+        // The value would be taken for a path
+        let error = CommitArgs::parse(&args(&["--author", "Ann <a@b.c>", "src/"])).unwrap_err();
+        assert!(error.to_string().contains("`--author=<value>`"), "{}", error);
+        let error = CommitArgs::parse(&args(&["-t", "template.txt"])).unwrap_err();
+        assert!(error.to_string().contains("`-t<value>`"), "{}", error);
+    }
+
+    #[test]
+    fn test_resolve_co_author() {
+        let known = vec!["Ann Lee <ann@example.com>".to_string(), "Bob Kim <bob@example.org>".to_string()];
+        assert_eq!(resolve_co_author("ann", &known).unwrap(), "Ann Lee <ann@example.com>");
+        assert_eq!(resolve_co_author("example.org", &known).unwrap(), "Bob Kim <bob@example.org>");
+        assert_eq!(resolve_co_author("Cy <cy@x.io>", &known).unwrap(), "Cy <cy@x.io>");
+        assert!(resolve_co_author("example", &known).unwrap_err().to_string().contains("several"));
+        assert!(resolve_co_author("zed", &known).is_err());
+    }
+}
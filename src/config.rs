@@ -1,8 +1,19 @@
+use clap::ValueEnum;
 use inquire::{Select, Text};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::io;
-use std::path::Path;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::commit::{is_identity, parse_trailer, Signing, SigningFormat};
+use crate::convention::EmojiStyle;
+use crate::git::{value_name, DiffAlgorithm, DiffOptions, RenameDetection};
+use crate::http::{mask_proxy, parse_proxy, HttpOptions};
+use crate::migration;
+use crate::providers::FallbackEntry;
+use crate::retry::{RetryPolicy, DEFAULT_CONNECT_TIMEOUT_SECS, DEFAULT_MAX_RETRIES, DEFAULT_REQUEST_TIMEOUT_SECS};
+use crate::scope::ScopeMode;
 
 /// Configuration file name
 pub const CONFIG_FILE_NAME: &str = ".committo.toml";
@@ -10,6 +21,15 @@ pub const CONFIG_FILE_NAME: &str = ".committo.toml";
 /// Convention file name  
 pub const CONVENTION_FILE_NAME: &str = ".committoconvention";
 
+/// `$XDG_CONFIG_HOME/committo`, or `~/.config/committo`, for files other than the config itself
+pub fn committo_config_dir() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home::home_dir().map(|home| home.join(".config")))?;
+    Some(config_dir.join("committo"))
+}
+
 /// Trait for providing configuration to LLM providers
 pub trait ConfigProvider: Send + Sync {
     /// Get API key
@@ -28,6 +48,9 @@ pub trait ConfigProvider: Send + Sync {
 /// Configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
+    #[serde(rename = "config-version")]
+    pub config_version: Option<u32>,
+
     #[serde(rename = "api-key")]
     pub api_key: Option<String>,
 
@@ -42,6 +65,105 @@ pub struct Config {
 
     #[serde(rename = "run-edit")]
     pub run_edit: Option<bool>,
+
+    #[serde(rename = "diff-context")]
+    pub diff_context: Option<u32>,
+
+    #[serde(rename = "diff-function-context")]
+    pub diff_function_context: Option<bool>,
+
+    #[serde(rename = "diff-algorithm")]
+    pub diff_algorithm: Option<String>,
+
+    #[serde(rename = "diff-ignore-whitespace")]
+    pub diff_ignore_whitespace: Option<bool>,
+
+    #[serde(rename = "diff-renames")]
+    pub diff_renames: Option<String>,
+
+    #[serde(rename = "structural-summary")]
+    pub structural_summary: Option<bool>,
+
+    #[serde(rename = "scope-mode")]
+    pub scope_mode: Option<String>,
+
+    #[serde(rename = "infer-convention")]
+    pub infer_convention: Option<bool>,
+
+    /// Language for commit messages when the convention doesn't name one
+    pub language: Option<String>,
+
+    /// English subject with the body in `language`
+    pub bilingual: Option<bool>,
+
+    /// Gitmoji style when the convention doesn't set one
+    pub emoji: Option<String>,
+
+    /// Add a `Signed-off-by` trailer with the git identity to every commit
+    pub signoff: Option<bool>,
+
+    /// People to offer as co-authors, as `Name <email>`
+    #[serde(rename = "co-authors")]
+    pub co_authors: Option<Vec<String>>,
+
+    /// `Key: value` trailers added to every commit
+    pub trailers: Option<Vec<String>>,
+
+    #[serde(rename = "sign-commits")]
+    pub sign_commits: Option<bool>,
+
+    #[serde(rename = "signing-key")]
+    pub signing_key: Option<String>,
+
+    #[serde(rename = "signing-format")]
+    pub signing_format: Option<String>,
+
+    /// Offer a `fixup!` candidate when the changes touch lines from earlier commits on the branch
+    #[serde(rename = "detect-fixups")]
+    pub detect_fixups: Option<bool>,
+
+    /// Seconds to wait for a whole provider response
+    #[serde(rename = "request-timeout")]
+    pub request_timeout: Option<u64>,
+
+    /// Seconds to wait for the connection to the provider
+    #[serde(rename = "connect-timeout")]
+    pub connect_timeout: Option<u64>,
+
+    /// Retries after a rate limit or server error
+    #[serde(rename = "max-retries")]
+    pub max_retries: Option<u32>,
+
+    /// Providers to try in order when `llm-provider` is unavailable, as `provider` or `provider:model`
+    pub fallback: Option<Vec<String>>,
+
+    /// Seconds all providers in the fallback chain may take together
+    #[serde(rename = "fallback-budget")]
+    pub fallback_budget: Option<u64>,
+
+    /// Proxy for provider requests, overriding `HTTPS_PROXY`
+    #[serde(rename = "https-proxy")]
+    pub https_proxy: Option<String>,
+
+    /// Hosts to reach without `https-proxy`
+    #[serde(rename = "no-proxy")]
+    pub no_proxy: Option<Vec<String>>,
+
+    /// PEM files with extra certificates to trust, e.g. for a TLS-intercepting proxy
+    #[serde(rename = "ca-bundles")]
+    pub ca_bundles: Option<Vec<String>>,
+
+    /// PEM client certificate for gateways that require mutual TLS
+    #[serde(rename = "client-cert")]
+    pub client_cert: Option<String>,
+
+    /// PKCS#8 PEM key for `client-cert`
+    #[serde(rename = "client-key")]
+    pub client_key: Option<String>,
+
+    /// Keys this version doesn't recognise, kept so that saving doesn't drop them
+    #[serde(flatten)]
+    pub extra: toml::Table,
 }
 
 impl ConfigProvider for Config {
@@ -67,12 +189,148 @@ impl Config {
     pub fn get_run_edit(&self) -> bool {
         self.run_edit.unwrap_or(true)
     }
+
+    /// Get structural-summary setting (default to true if not set)
+    pub fn get_structural_summary(&self) -> bool {
+        self.structural_summary.unwrap_or(true)
+    }
+
+    /// Get infer-convention setting (default to true if not set)
+    pub fn get_infer_convention(&self) -> bool {
+        self.infer_convention.unwrap_or(true)
+    }
+
+    /// Gitmoji style for the subject line, if any
+    pub fn get_emoji(&self) -> io::Result<Option<EmojiStyle>> {
+        self.emoji.as_deref().map(|name| parse_choice(EMOJI_CONFIG, name)).transpose()
+    }
+
+    /// Limit for a whole provider request
+    pub fn get_request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECS))
+    }
+
+    /// Limit for connecting to the provider
+    pub fn get_connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS))
+    }
+
+    /// How rate limits and server errors are retried
+    pub fn get_retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: self.max_retries.unwrap_or(DEFAULT_MAX_RETRIES),
+            ..Default::default()
+        }
+    }
+
+    /// Providers to fall back to, in order
+    pub fn get_fallback(&self) -> io::Result<Vec<FallbackEntry>> {
+        self.fallback
+            .iter()
+            .flatten()
+            .map(|entry| FallbackEntry::parse(entry).ok_or_else(|| invalid_fallback(entry)))
+            .collect()
+    }
+
+    /// Limit for the whole fallback chain, if any
+    pub fn get_fallback_budget(&self) -> Option<Duration> {
+        self.fallback_budget.map(Duration::from_secs)
+    }
+
+    /// Timeouts, proxy and certificates for provider requests
+    pub fn get_http_options(&self) -> io::Result<HttpOptions> {
+        let client_identity = match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => Some((PathBuf::from(cert), PathBuf::from(key))),
+            (None, None) => None,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} and {} must be set together", CLIENT_CERT_CONFIG, CLIENT_KEY_CONFIG),
+                ));
+            }
+        };
+        Ok(HttpOptions {
+            request_timeout: self.get_request_timeout(),
+            connect_timeout: self.get_connect_timeout(),
+            https_proxy: self.https_proxy.clone(),
+            no_proxy: self.no_proxy.clone().unwrap_or_default(),
+            ca_bundles: self.ca_bundles.iter().flatten().map(PathBuf::from).collect(),
+            client_identity,
+        })
+    }
+
+    /// Get detect-fixups setting (default to true if not set)
+    pub fn get_detect_fixups(&self) -> bool {
+        self.detect_fixups.unwrap_or(true)
+    }
+
+    /// Get signoff setting (default to false if not set)
+    pub fn get_signoff(&self) -> bool {
+        self.signoff.unwrap_or(false)
+    }
+
+    /// How to sign commits; unset parts are left to git's own config
+    pub fn get_signing(&self) -> io::Result<Signing> {
+        Ok(Signing {
+            sign: self.sign_commits.unwrap_or(false),
+            key: self.signing_key.clone(),
+            format: self.signing_format.as_deref().map(|name| parse_choice(SIGNING_FORMAT_CONFIG, name)).transpose()?,
+        })
+    }
+
+    /// Static trailers as (key, value)
+    pub fn get_trailers(&self) -> io::Result<Vec<(String, String)>> {
+        self.trailers
+            .iter()
+            .flatten()
+            .map(|trailer| {
+                parse_trailer(trailer)
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .ok_or_else(|| invalid_trailer(trailer))
+            })
+            .collect()
+    }
+
+    /// How the package scope inferred in monorepos is used (default to suggest)
+    pub fn get_scope_mode(&self) -> io::Result<ScopeMode> {
+        match &self.scope_mode {
+            Some(name) => parse_choice(SCOPE_MODE_CONFIG, name),
+            None => Ok(ScopeMode::default()),
+        }
+    }
+
+    /// How to produce the diff sent to the provider
+    pub fn get_diff_options(&self) -> io::Result<DiffOptions> {
+        let defaults = DiffOptions::default();
+        Ok(DiffOptions {
+            context_lines: self.diff_context.unwrap_or(defaults.context_lines),
+            function_context: self.diff_function_context.unwrap_or(defaults.function_context),
+            algorithm: match &self.diff_algorithm {
+                Some(name) => Some(parse_choice(DIFF_ALGORITHM_CONFIG, name)?),
+                None => defaults.algorithm,
+            },
+            ignore_whitespace: self.diff_ignore_whitespace.unwrap_or(defaults.ignore_whitespace),
+            renames: match &self.diff_renames {
+                Some(name) => parse_choice(DIFF_RENAMES_CONFIG, name)?,
+                None => defaults.renames,
+            },
+        })
+    }
+
     /// Create new config instance, loading from file or creating interactively if needed
     pub fn new(config_path: &Path) -> io::Result<(Config, std::path::PathBuf)> {
         let config_path_buf = config_path.to_path_buf();
 
         let config = if config_path.exists() {
             Config::load(config_path)?
+        } else if !io::stdin().is_terminal() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "No configuration file found at: {}. Run 'committo set api-key <key>' to create one.",
+                    config_path.display()
+                ),
+            ));
         } else {
             println!("No configuration file found at: {}", config_path.display());
             println!("Let's set up your configuration interactively!");
@@ -83,8 +341,44 @@ impl Config {
         Ok((config, config_path_buf))
     }
 
-    /// Load config from TOML file (assumes file exists)
+    /// Load config from TOML file (assumes file exists), upgrading old schema versions
     fn load(config_path: &Path) -> io::Result<Config> {
+        let mut table = Config::load_table(config_path)?;
+
+        let version = migration::table_version(&table);
+        if version > CURRENT_CONFIG_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Config file version {} is newer than supported version {}. Please upgrade committo.",
+                    version, CURRENT_CONFIG_VERSION
+                ),
+            ));
+        }
+        if version < CURRENT_CONFIG_VERSION {
+            Config::upgrade_file(config_path, &mut table, version)?;
+        }
+
+        let config: Config = toml::Value::Table(table).try_into().map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse TOML: {}", e),
+            )
+        })?;
+
+        for key in config.extra.keys() {
+            eprintln!(
+                "Warning: unknown config key '{}' in {} is ignored (run 'committo config doctor' for details)",
+                key,
+                config_path.display()
+            );
+        }
+
+        Ok(config)
+    }
+
+    /// Read the config file as an untyped TOML table
+    pub(crate) fn load_table(config_path: &Path) -> io::Result<toml::Table> {
         let content = fs::read_to_string(config_path)?;
         toml::from_str(&content).map_err(|e| {
             io::Error::new(
@@ -94,9 +388,46 @@ impl Config {
         })
     }
 
+    /// Migrate the config file to the current schema, keeping a backup of the original
+    fn upgrade_file(config_path: &Path, table: &mut toml::Table, version: u32) -> io::Result<()> {
+        let applied = migration::migrate(table);
+
+        let backup_path = Config::backup_path(config_path, version);
+        fs::copy(config_path, &backup_path)?;
+
+        let toml_string = toml::to_string_pretty(table).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to serialize TOML: {}", e),
+            )
+        })?;
+        fs::write(config_path, toml_string)?;
+
+        eprintln!(
+            "Upgraded config {} from version {} to {} (backup: {})",
+            config_path.display(),
+            version,
+            CURRENT_CONFIG_VERSION,
+            backup_path.display()
+        );
+        for description in applied {
+            eprintln!("  - {}", description);
+        }
+        Ok(())
+    }
+
+    /// Path of the backup written before migrating a config file from `version`
+    pub fn backup_path(config_path: &Path, version: u32) -> PathBuf {
+        let mut file_name = config_path.as_os_str().to_os_string();
+        file_name.push(format!(".v{}.bak", version));
+        PathBuf::from(file_name)
+    }
+
     /// Save config to TOML file
     pub fn save(&self, config_path: &Path) -> io::Result<()> {
-        let toml_string = toml::to_string_pretty(self).map_err(|e| {
+        let mut stamped = self.clone();
+        stamped.config_version = Some(CURRENT_CONFIG_VERSION);
+        let toml_string = toml::to_string_pretty(&stamped).map_err(|e| {
             io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!("Failed to serialize TOML: {}", e),
@@ -131,12 +462,132 @@ impl Config {
                 })?;
                 self.run_edit = Some(run_edit);
             }
+            DIFF_CONTEXT_CONFIG => {
+                let lines: u32 = value.parse().map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "diff-context must be a number",
+                    )
+                })?;
+                self.diff_context = Some(lines);
+            }
+            DIFF_FUNCTION_CONTEXT_CONFIG => {
+                self.diff_function_context = Some(parse_bool(DIFF_FUNCTION_CONTEXT_CONFIG, value)?);
+            }
+            DIFF_ALGORITHM_CONFIG => {
+                let algorithm: DiffAlgorithm = parse_choice(DIFF_ALGORITHM_CONFIG, value)?;
+                self.diff_algorithm = Some(value_name(algorithm));
+            }
+            DIFF_IGNORE_WHITESPACE_CONFIG => {
+                self.diff_ignore_whitespace = Some(parse_bool(DIFF_IGNORE_WHITESPACE_CONFIG, value)?);
+            }
+            DIFF_RENAMES_CONFIG => {
+                let renames: RenameDetection = parse_choice(DIFF_RENAMES_CONFIG, value)?;
+                self.diff_renames = Some(value_name(renames));
+            }
+            STRUCTURAL_SUMMARY_CONFIG => {
+                self.structural_summary = Some(parse_bool(STRUCTURAL_SUMMARY_CONFIG, value)?);
+            }
+            SCOPE_MODE_CONFIG => {
+                let mode: ScopeMode = parse_choice(SCOPE_MODE_CONFIG, value)?;
+                self.scope_mode = Some(value_name(mode));
+            }
+            INFER_CONVENTION_CONFIG => {
+                self.infer_convention = Some(parse_bool(INFER_CONVENTION_CONFIG, value)?);
+            }
+            LANGUAGE_CONFIG => {
+                self.language = Some(parse_language(value)?);
+            }
+            BILINGUAL_CONFIG => {
+                self.bilingual = Some(parse_bool(BILINGUAL_CONFIG, value)?);
+            }
+            EMOJI_CONFIG => {
+                let style: EmojiStyle = parse_choice(EMOJI_CONFIG, value)?;
+                self.emoji = Some(value_name(style));
+            }
+            SIGNOFF_CONFIG => {
+                self.signoff = Some(parse_bool(SIGNOFF_CONFIG, value)?);
+            }
+            CO_AUTHORS_CONFIG => {
+                let people = parse_list(value);
+                if let Some(person) = people.iter().find(|person| !is_identity(person)) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("{} entries must look like `Name <email>`, got `{}`", CO_AUTHORS_CONFIG, person),
+                    ));
+                }
+                self.co_authors = Some(people);
+            }
+            REQUEST_TIMEOUT_CONFIG => {
+                self.request_timeout = Some(parse_seconds(REQUEST_TIMEOUT_CONFIG, value)?);
+            }
+            CONNECT_TIMEOUT_CONFIG => {
+                self.connect_timeout = Some(parse_seconds(CONNECT_TIMEOUT_CONFIG, value)?);
+            }
+            MAX_RETRIES_CONFIG => {
+                let retries: u32 = value.parse().map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "max-retries must be a number",
+                    )
+                })?;
+                self.max_retries = Some(retries);
+            }
+            FALLBACK_CONFIG => {
+                let entries = parse_list(value);
+                if let Some(entry) = entries.iter().find(|entry| FallbackEntry::parse(entry).is_none()) {
+                    return Err(invalid_fallback(entry));
+                }
+                self.fallback = Some(entries);
+            }
+            FALLBACK_BUDGET_CONFIG => {
+                self.fallback_budget = Some(parse_seconds(FALLBACK_BUDGET_CONFIG, value)?);
+            }
+            HTTPS_PROXY_CONFIG => {
+                parse_proxy(value)?;
+                self.https_proxy = Some(value.to_string());
+            }
+            NO_PROXY_CONFIG => self.no_proxy = Some(parse_list(value)),
+            CA_BUNDLES_CONFIG => {
+                let paths = parse_list(value).iter().map(|path| resolve_file(CA_BUNDLES_CONFIG, path)).collect::<io::Result<_>>()?;
+                self.ca_bundles = Some(paths);
+            }
+            CLIENT_CERT_CONFIG => self.client_cert = Some(resolve_file(CLIENT_CERT_CONFIG, value)?),
+            CLIENT_KEY_CONFIG => self.client_key = Some(resolve_file(CLIENT_KEY_CONFIG, value)?),
+            DETECT_FIXUPS_CONFIG => {
+                self.detect_fixups = Some(parse_bool(DETECT_FIXUPS_CONFIG, value)?);
+            }
+            SIGN_COMMITS_CONFIG => {
+                self.sign_commits = Some(parse_bool(SIGN_COMMITS_CONFIG, value)?);
+            }
+            SIGNING_KEY_CONFIG => {
+                let key = value.trim();
+                if key.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("{} must not be empty", SIGNING_KEY_CONFIG),
+                    ));
+                }
+                self.signing_key = Some(key.to_string());
+            }
+            SIGNING_FORMAT_CONFIG => {
+                let format: SigningFormat = parse_choice(SIGNING_FORMAT_CONFIG, value)?;
+                self.signing_format = Some(value_name(format));
+            }
+            TRAILERS_CONFIG => {
+                let trailers = parse_list(value);
+                if let Some(trailer) = trailers.iter().find(|trailer| parse_trailer(trailer).is_none()) {
+                    return Err(invalid_trailer(trailer));
+                }
+                self.trailers = Some(trailers);
+            }
             _ => {
                 return Err(io::Error::new(
                     io::ErrorKind::InvalidInput,
                     format!(
-                        "Invalid config key '{}'. Valid keys are: api-key, candidate-count, llm-provider, llm-model, run-edit",
-                        key
+                        "Invalid config key '{}'. Valid keys are: {}",
+                        key,
+                        SETTABLE_CONFIG_KEYS.join(", ")
                     ),
                 ));
             }
@@ -175,13 +626,13 @@ impl Config {
         // API Key setup
         let api_key = Text::new("Enter your OpenAI API key:")
             .prompt()
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            .map_err(io::Error::other)?;
 
         // Provider selection
         let providers = vec!["openai"];
         let provider_selection = Select::new("Select LLM provider:", providers.clone())
             .prompt()
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            .map_err(io::Error::other)?;
 
         // Model selection
         let models = match provider_selection {
@@ -191,13 +642,13 @@ impl Config {
 
         let model_selection = Select::new("Select model:", models.clone())
             .prompt()
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            .map_err(io::Error::other)?;
 
         // Candidate count
         let candidate_count_str = Text::new("Number of commit message candidates:")
             .with_default("5")
             .prompt()
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            .map_err(io::Error::other)?;
         let candidate_count: u32 = candidate_count_str.parse().map_err(|_| {
             io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -210,15 +661,17 @@ impl Config {
         let run_edit_selection = Select::new("Open editor before committing:", run_edit_options.clone())
             .with_starting_cursor(0) // Default to true
             .prompt()
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            .map_err(io::Error::other)?;
         let run_edit = run_edit_selection == "true";
 
         let config = Config {
+            config_version: Some(CURRENT_CONFIG_VERSION),
             api_key: Some(api_key),
             llm_provider: Some(provider_selection.to_string()),
             llm_model: Some(model_selection.to_string()),
             candidate_count: Some(candidate_count),
             run_edit: Some(run_edit),
+            ..Default::default()
         };
 
         config.save(config_path)?;
@@ -263,16 +716,169 @@ impl Config {
         if let Some(run_edit) = self.run_edit {
             output.push_str(&format!("Run Edit : {}\n", run_edit));
         }
+        if let Some(lines) = self.diff_context {
+            output.push_str(&format!("Diff Context : {}\n", lines));
+        }
+        if let Some(function_context) = self.diff_function_context {
+            output.push_str(&format!("Diff Function Context : {}\n", function_context));
+        }
+        if let Some(algorithm) = &self.diff_algorithm {
+            output.push_str(&format!("Diff Algorithm : \"{}\"\n", algorithm));
+        }
+        if let Some(ignore_whitespace) = self.diff_ignore_whitespace {
+            output.push_str(&format!("Diff Ignore Whitespace : {}\n", ignore_whitespace));
+        }
+        if let Some(renames) = &self.diff_renames {
+            output.push_str(&format!("Diff Renames : \"{}\"\n", renames));
+        }
+        if let Some(structural_summary) = self.structural_summary {
+            output.push_str(&format!("Structural Summary : {}\n", structural_summary));
+        }
+        if let Some(scope_mode) = &self.scope_mode {
+            output.push_str(&format!("Scope Mode : \"{}\"\n", scope_mode));
+        }
+        if let Some(infer_convention) = self.infer_convention {
+            output.push_str(&format!("Infer Convention : {}\n", infer_convention));
+        }
+        if let Some(language) = &self.language {
+            output.push_str(&format!("Language : \"{}\"\n", language));
+        }
+        if let Some(bilingual) = self.bilingual {
+            output.push_str(&format!("Bilingual : {}\n", bilingual));
+        }
+        if let Some(emoji) = &self.emoji {
+            output.push_str(&format!("Emoji : \"{}\"\n", emoji));
+        }
+        if let Some(signoff) = self.signoff {
+            output.push_str(&format!("Signoff : {}\n", signoff));
+        }
+        if let Some(co_authors) = &self.co_authors {
+            output.push_str(&format!("Co-authors : {:?}\n", co_authors));
+        }
+        if let Some(trailers) = &self.trailers {
+            output.push_str(&format!("Trailers : {:?}\n", trailers));
+        }
+        if let Some(sign_commits) = self.sign_commits {
+            output.push_str(&format!("Sign Commits : {}\n", sign_commits));
+        }
+        if let Some(signing_key) = &self.signing_key {
+            output.push_str(&format!("Signing Key : \"{}\"\n", signing_key));
+        }
+        if let Some(signing_format) = &self.signing_format {
+            output.push_str(&format!("Signing Format : \"{}\"\n", signing_format));
+        }
+        if let Some(detect_fixups) = self.detect_fixups {
+            output.push_str(&format!("Detect Fixups : {}\n", detect_fixups));
+        }
+        if let Some(request_timeout) = self.request_timeout {
+            output.push_str(&format!("Request Timeout : {}s\n", request_timeout));
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            output.push_str(&format!("Connect Timeout : {}s\n", connect_timeout));
+        }
+        if let Some(max_retries) = self.max_retries {
+            output.push_str(&format!("Max Retries : {}\n", max_retries));
+        }
+        if let Some(fallback) = &self.fallback {
+            output.push_str(&format!("Fallback : {:?}\n", fallback));
+        }
+        if let Some(fallback_budget) = self.fallback_budget {
+            output.push_str(&format!("Fallback Budget : {}s\n", fallback_budget));
+        }
+        if let Some(https_proxy) = &self.https_proxy {
+            output.push_str(&format!("HTTPS Proxy : \"{}\"\n", mask_proxy(https_proxy)));
+        }
+        if let Some(no_proxy) = &self.no_proxy {
+            output.push_str(&format!("No Proxy : {:?}\n", no_proxy));
+        }
+        if let Some(ca_bundles) = &self.ca_bundles {
+            output.push_str(&format!("CA Bundles : {:?}\n", ca_bundles));
+        }
+        if let Some(client_cert) = &self.client_cert {
+            output.push_str(&format!("Client Cert : \"{}\"\n", client_cert));
+        }
+        if let Some(client_key) = &self.client_key {
+            output.push_str(&format!("Client Key : \"{}\"\n", client_key));
+        }
         output
     }
 }
 
 /// Config keys for TOML file
+pub const CONFIG_VERSION_CONFIG: &str = "config-version";
 pub const API_KEY_CONFIG: &str = "api-key";
 pub const LLM_PROVIDER_CONFIG: &str = "llm-provider";
 pub const LLM_MODEL_CONFIG: &str = "llm-model";
 pub const CANDIDATE_COUNT_CONFIG: &str = "candidate-count";
 pub const RUN_EDIT_CONFIG: &str = "run-edit";
+pub const DIFF_CONTEXT_CONFIG: &str = "diff-context";
+pub const DIFF_FUNCTION_CONTEXT_CONFIG: &str = "diff-function-context";
+pub const DIFF_ALGORITHM_CONFIG: &str = "diff-algorithm";
+pub const DIFF_IGNORE_WHITESPACE_CONFIG: &str = "diff-ignore-whitespace";
+pub const DIFF_RENAMES_CONFIG: &str = "diff-renames";
+pub const STRUCTURAL_SUMMARY_CONFIG: &str = "structural-summary";
+pub const SCOPE_MODE_CONFIG: &str = "scope-mode";
+pub const INFER_CONVENTION_CONFIG: &str = "infer-convention";
+pub const LANGUAGE_CONFIG: &str = "language";
+pub const BILINGUAL_CONFIG: &str = "bilingual";
+pub const EMOJI_CONFIG: &str = "emoji";
+pub const SIGNOFF_CONFIG: &str = "signoff";
+pub const CO_AUTHORS_CONFIG: &str = "co-authors";
+pub const TRAILERS_CONFIG: &str = "trailers";
+pub const SIGN_COMMITS_CONFIG: &str = "sign-commits";
+pub const SIGNING_KEY_CONFIG: &str = "signing-key";
+pub const SIGNING_FORMAT_CONFIG: &str = "signing-format";
+pub const DETECT_FIXUPS_CONFIG: &str = "detect-fixups";
+pub const REQUEST_TIMEOUT_CONFIG: &str = "request-timeout";
+pub const CONNECT_TIMEOUT_CONFIG: &str = "connect-timeout";
+pub const MAX_RETRIES_CONFIG: &str = "max-retries";
+pub const FALLBACK_CONFIG: &str = "fallback";
+pub const FALLBACK_BUDGET_CONFIG: &str = "fallback-budget";
+pub const HTTPS_PROXY_CONFIG: &str = "https-proxy";
+pub const NO_PROXY_CONFIG: &str = "no-proxy";
+pub const CA_BUNDLES_CONFIG: &str = "ca-bundles";
+pub const CLIENT_CERT_CONFIG: &str = "client-cert";
+pub const CLIENT_KEY_CONFIG: &str = "client-key";
+
+/// Keys that can be changed with `committo set`
+pub const SETTABLE_CONFIG_KEYS: &[&str] = &[
+    API_KEY_CONFIG,
+    CANDIDATE_COUNT_CONFIG,
+    LLM_PROVIDER_CONFIG,
+    LLM_MODEL_CONFIG,
+    RUN_EDIT_CONFIG,
+    DIFF_CONTEXT_CONFIG,
+    DIFF_FUNCTION_CONTEXT_CONFIG,
+    DIFF_ALGORITHM_CONFIG,
+    DIFF_IGNORE_WHITESPACE_CONFIG,
+    DIFF_RENAMES_CONFIG,
+    STRUCTURAL_SUMMARY_CONFIG,
+    SCOPE_MODE_CONFIG,
+    INFER_CONVENTION_CONFIG,
+    LANGUAGE_CONFIG,
+    BILINGUAL_CONFIG,
+    EMOJI_CONFIG,
+    SIGNOFF_CONFIG,
+    CO_AUTHORS_CONFIG,
+    TRAILERS_CONFIG,
+    SIGN_COMMITS_CONFIG,
+    SIGNING_KEY_CONFIG,
+    SIGNING_FORMAT_CONFIG,
+    DETECT_FIXUPS_CONFIG,
+    REQUEST_TIMEOUT_CONFIG,
+    CONNECT_TIMEOUT_CONFIG,
+    MAX_RETRIES_CONFIG,
+    FALLBACK_CONFIG,
+    FALLBACK_BUDGET_CONFIG,
+    HTTPS_PROXY_CONFIG,
+    NO_PROXY_CONFIG,
+    CA_BUNDLES_CONFIG,
+    CLIENT_CERT_CONFIG,
+    CLIENT_KEY_CONFIG,
+];
+
+/// Current config schema version, bumped whenever a migration is added
+pub const CURRENT_CONFIG_VERSION: u32 = 1;
 
 /// Default OpenAI models
 pub const DEFAULT_OPENAI_MODEL: &str = "gpt-3.5-turbo";
@@ -281,6 +887,90 @@ pub const GPT4_MODEL: &str = "gpt-4";
 /// Provider identifiers for LLM_PROVIDER environment variable
 pub const PROVIDER_OPENAI: &str = "openai";
 
+/// Providers accepted for `llm-provider`
+pub const SUPPORTED_PROVIDERS: &[&str] = &[PROVIDER_OPENAI];
+
+/// Parse a `true`/`false` setting
+fn parse_bool(key: &str, value: &str) -> io::Result<bool> {
+    value.parse().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} must be true or false", key),
+        )
+    })
+}
+
+/// Parse a positive number of seconds
+fn parse_seconds(key: &str, value: &str) -> io::Result<u64> {
+    match value.parse::<u64>() {
+        Ok(seconds) if seconds > 0 => Ok(seconds),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} must be a positive number of seconds", key),
+        )),
+    }
+}
+
+/// Split a comma-separated list, dropping empty entries
+fn parse_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(str::to_string).collect()
+}
+
+fn invalid_trailer(trailer: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{} entries must look like `Key: value`, got `{}`", TRAILERS_CONFIG, trailer),
+    )
+}
+
+/// Absolute path of an existing file, so the setting works from any directory
+fn resolve_file(key: &str, path: &str) -> io::Result<String> {
+    let path = match (path.strip_prefix("~/"), home::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    };
+    let resolved = fs::canonicalize(&path)
+        .ok()
+        .filter(|resolved| resolved.is_file())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: no file at {}", key, path.display())))?;
+    Ok(resolved.to_string_lossy().into_owned())
+}
+
+fn invalid_fallback(entry: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "{} entries must look like `provider` or `provider:model` (supported providers: {}), got `{}`",
+            FALLBACK_CONFIG,
+            SUPPORTED_PROVIDERS.join(", "),
+            entry
+        ),
+    )
+}
+
+/// Parse a language name such as "Korean"
+fn parse_language(value: &str) -> io::Result<String> {
+    let language = value.trim();
+    if language.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} must not be empty", LANGUAGE_CONFIG),
+        ));
+    }
+    Ok(language.to_string())
+}
+
+/// Parse a setting that takes one of a fixed set of names
+pub fn parse_choice<T: ValueEnum>(key: &str, value: &str) -> io::Result<T> {
+    T::from_str(value, true).map_err(|_| {
+        let names: Vec<String> = T::value_variants().iter().map(|v| value_name(v.clone())).collect();
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} must be one of: {}", key, names.join(", ")),
+        )
+    })
+}
+
 /// Get specific config value
 pub fn get_config_value(config_path: &Path, key: &str) -> io::Result<Option<String>> {
     let config = Config::load(config_path)?;
@@ -291,6 +981,35 @@ pub fn get_config_value(config_path: &Path, key: &str) -> io::Result<Option<Stri
         LLM_PROVIDER_CONFIG => config.llm_provider,
         LLM_MODEL_CONFIG => config.llm_model,
         RUN_EDIT_CONFIG => config.run_edit.map(|v| v.to_string()),
+        DIFF_CONTEXT_CONFIG => config.diff_context.map(|v| v.to_string()),
+        DIFF_FUNCTION_CONTEXT_CONFIG => config.diff_function_context.map(|v| v.to_string()),
+        DIFF_ALGORITHM_CONFIG => config.diff_algorithm,
+        DIFF_IGNORE_WHITESPACE_CONFIG => config.diff_ignore_whitespace.map(|v| v.to_string()),
+        DIFF_RENAMES_CONFIG => config.diff_renames,
+        STRUCTURAL_SUMMARY_CONFIG => config.structural_summary.map(|v| v.to_string()),
+        SCOPE_MODE_CONFIG => config.scope_mode,
+        INFER_CONVENTION_CONFIG => config.infer_convention.map(|v| v.to_string()),
+        LANGUAGE_CONFIG => config.language,
+        BILINGUAL_CONFIG => config.bilingual.map(|v| v.to_string()),
+        EMOJI_CONFIG => config.emoji,
+        SIGNOFF_CONFIG => config.signoff.map(|v| v.to_string()),
+        CO_AUTHORS_CONFIG => config.co_authors.map(|v| v.join(", ")),
+        TRAILERS_CONFIG => config.trailers.map(|v| v.join(", ")),
+        SIGN_COMMITS_CONFIG => config.sign_commits.map(|v| v.to_string()),
+        SIGNING_KEY_CONFIG => config.signing_key,
+        SIGNING_FORMAT_CONFIG => config.signing_format,
+        DETECT_FIXUPS_CONFIG => config.detect_fixups.map(|v| v.to_string()),
+        REQUEST_TIMEOUT_CONFIG => config.request_timeout.map(|v| v.to_string()),
+        CONNECT_TIMEOUT_CONFIG => config.connect_timeout.map(|v| v.to_string()),
+        MAX_RETRIES_CONFIG => config.max_retries.map(|v| v.to_string()),
+        FALLBACK_CONFIG => config.fallback.map(|v| v.join(", ")),
+        FALLBACK_BUDGET_CONFIG => config.fallback_budget.map(|v| v.to_string()),
+        HTTPS_PROXY_CONFIG => config.https_proxy,
+        NO_PROXY_CONFIG => config.no_proxy.map(|v| v.join(", ")),
+        CA_BUNDLES_CONFIG => config.ca_bundles.map(|v| v.join(", ")),
+        CLIENT_CERT_CONFIG => config.client_cert,
+        CLIENT_KEY_CONFIG => config.client_key,
+        CONFIG_VERSION_CONFIG => config.config_version.map(|v| v.to_string()),
         _ => None,
     };
 
@@ -1,30 +1,789 @@
+//! `.committoconvention` files
+//!
+//! A convention file is free text for the model, optionally preceded by
+//! frontmatter with settings committo understands itself: TOML between `+++`
+//! lines or YAML between `---` lines. Files are collected inside the
+//! repository, from the directory of each changed file up to the repository
+//! root, plus an optional global file in the config directory, and merged
+//! general to specific, so the more specific file wins. A file can `extends`
+//! built-in presets or other local files, which apply before the file itself.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::env;
 use std::fs;
 use std::io;
-use crate::config::CONVENTION_FILE_NAME;
-
-/// Find and build hierarchical prompt from .committoconvention files
-pub fn find_and_build_prompt() -> io::Result<String> {
-    let mut prompt_parts = Vec::new();
-    let current_dir = env::current_dir()?;
-
-    for ancestor in current_dir.ancestors() {
-        let convention_path = ancestor.join(CONVENTION_FILE_NAME);
-        if convention_path.exists() {
-            let content = fs::read_to_string(convention_path)?;
-            prompt_parts.push(content);
-        }
-    }
-
-    // The prompts are found from child to parent, so we reverse to get parent-to-child order.
-    prompt_parts.reverse();
-    
-    // Add priority numbers (1 = highest, 2, 3, 4... = lower priority)
-    let numbered_prompts: Vec<String> = prompt_parts
-        .into_iter()
-        .enumerate()
-        .map(|(i, content)| format!("{}. {}", i + 1, content.trim()))
-        .collect();
-    
-    Ok(numbered_prompts.join("\n\n"))
-}
\ No newline at end of file
+use std::path::{Path, PathBuf};
+
+use crate::config::{committo_config_dir, Config, CONVENTION_FILE_NAME};
+use crate::diff::parse_diff;
+use crate::git;
+use crate::gitmoji::{self, split_emoji};
+use crate::history::{self, InferredConvention, HISTORY_LIMIT};
+use crate::language;
+
+/// How gitmoji are written in the subject line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum EmojiStyle {
+    /// No emoji at all
+    None,
+    /// The emoji character itself, e.g. ✨
+    Unicode,
+    /// A gitmoji shortcode, e.g. `:sparkles:`
+    Shortcode,
+}
+
+/// Settings from the frontmatter of a convention file
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ConventionSettings {
+    /// Allowed Conventional Commits types
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub types: Option<Vec<String>>,
+    /// Allowed scopes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<String>>,
+    /// Language the message is written in, e.g. "English" or "Korean"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// English subject with the body in `language`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bilingual: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_subject_length: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<EmojiStyle>,
+    /// Trailers every message must carry, e.g. "Signed-off-by"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trailers: Option<Vec<String>>,
+    /// `false` stops the walk up to parent directories at this file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inherit: Option<bool>,
+    /// Presets or files (relative to this one) that apply before this file
+    #[serde(default, alias = "include", deserialize_with = "one_or_many")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<Vec<String>>,
+}
+
+/// Accept `extends = "conventional"` as well as a list
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<String>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(Some(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(reference) => vec![reference],
+        OneOrMany::Many(references) => references,
+    }))
+}
+
+impl ConventionSettings {
+    /// Let the more specific `child` override these settings
+    fn merge(&mut self, child: &ConventionSettings) {
+        macro_rules! take {
+            ($($field:ident),*) => {
+                $(if child.$field.is_some() {
+                    self.$field = child.$field.clone();
+                })*
+            };
+        }
+        take!(types, scopes, language, bilingual, max_subject_length, emoji, trailers);
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == ConventionSettings { inherit: self.inherit, extends: self.extends.clone(), ..Default::default() }
+    }
+}
+
+/// Built-in conventions that can be named in `extends`
+const PRESETS: &[(&str, &str)] = &[
+    (
+        "conventional",
+        "+++\ntypes = [\"feat\", \"fix\", \"docs\", \"style\", \"refactor\", \"perf\", \"test\", \"build\", \"ci\", \"chore\", \"revert\"]\n+++\n\
+         Follow Conventional Commits: `<type>(<scope>): <description>` in the imperative mood. \
+         Mark breaking changes with `!` after the type or scope and a `BREAKING CHANGE:` footer.\n",
+    ),
+    (
+        "angular",
+        "+++\ntypes = [\"build\", \"ci\", \"docs\", \"feat\", \"fix\", \"perf\", \"refactor\", \"test\"]\nmax-subject-length = 100\n+++\n\
+         Follow the Angular commit format: `<type>(<scope>): <summary>`. Write the summary in the present tense, \
+         lowercase, without a period at the end. Explain the motivation for the change in the body.\n",
+    ),
+    (
+        "gitmoji",
+        "+++\nemoji = \"unicode\"\n+++\n\
+         Start the subject with the gitmoji that fits the change best, such as ✨ for a new feature, \
+         🐛 for a bug fix, 📝 for documentation, ♻️ for a refactor or 🔥 for removed code.\n",
+    ),
+    (
+        "kernel",
+        "+++\nmax-subject-length = 72\ntrailers = [\"Signed-off-by\"]\n+++\n\
+         Use Linux kernel style: `<subsystem>: <summary>` in the imperative mood, without a trailing period. \
+         The body explains the problem and why this change solves it, wrapped at 72 columns.\n",
+    ),
+    (
+        "korean",
+        "+++\nextends = \"conventional\"\nlanguage = \"Korean\"\n+++\n\
+         Keep the type prefix in English and write the description in Korean, e.g. `feat: 로그인 화면 추가`.\n",
+    ),
+];
+
+/// Contents of a new convention file that extends `preset`
+pub fn preset_file(preset: &str) -> String {
+    format!("+++\nextends = \"{}\"\n+++\n", preset)
+}
+
+/// Names of the built-in presets
+pub fn preset_names() -> Vec<&'static str> {
+    PRESETS.iter().map(|(name, _)| *name).collect()
+}
+
+/// One convention file, split into settings and free text
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConventionFile {
+    pub path: PathBuf,
+    /// Name of the built-in preset this came from, if any
+    pub preset: Option<String>,
+    pub settings: ConventionSettings,
+    pub body: String,
+    /// Resolved `extends`, in order
+    pub includes: Vec<ConventionFile>,
+}
+
+impl ConventionFile {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::resolve(path.to_path_buf(), None, &mut Vec::new())
+    }
+
+    /// Load a file or preset and everything it extends; `chain` holds the files being loaded
+    fn resolve(path: PathBuf, preset: Option<&str>, chain: &mut Vec<String>) -> io::Result<Self> {
+        let label = match preset {
+            Some(name) => format!("preset `{}`", name),
+            None => path.display().to_string(),
+        };
+        if chain.contains(&label) {
+            chain.push(label);
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("convention include cycle: {}", chain.join(" -> ")),
+            ));
+        }
+
+        let content = match preset {
+            Some(name) => PRESETS.iter().find(|(preset, _)| *preset == name).map(|(_, content)| content.to_string()).unwrap_or_default(),
+            None => fs::read_to_string(&path)?,
+        };
+        let (settings, body) = parse_convention(&content)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", label, e)))?;
+
+        chain.push(label.clone());
+        let mut includes = Vec::new();
+        for reference in settings.extends.iter().flatten() {
+            let include = if preset_names().contains(&reference.as_str()) {
+                Self::resolve(PathBuf::new(), Some(reference), chain)?
+            } else {
+                let target = path.parent().unwrap_or(Path::new("")).join(reference);
+                let target = fs::canonicalize(&target).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!(
+                            "{}: can't extend `{}`: no such file, and not a preset ({})",
+                            label,
+                            reference,
+                            preset_names().join(", ")
+                        ),
+                    )
+                })?;
+                Self::resolve(target, None, chain)?
+            };
+            includes.push(include);
+        }
+        chain.pop();
+
+        Ok(ConventionFile { path, preset: preset.map(str::to_string), settings, body, includes })
+    }
+
+    /// The file path, or the preset name
+    pub fn label(&self) -> String {
+        match &self.preset {
+            Some(name) => format!("preset `{}`", name),
+            None => self.path.display().to_string(),
+        }
+    }
+
+    /// This file after everything it extends, so it overrides them, skipping anything already in `out`
+    fn flatten<'a>(&'a self, out: &mut Vec<&'a ConventionFile>) {
+        for include in &self.includes {
+            include.flatten(out);
+        }
+        if !out.iter().any(|file| file.label() == self.label()) {
+            out.push(self);
+        }
+    }
+}
+
+/// Split a convention file into frontmatter settings and the free text after it.
+/// A file whose opening fence is never closed has no frontmatter; it is all text.
+pub fn parse_convention(content: &str) -> Result<(ConventionSettings, String), String> {
+    let first_line = content.lines().next().unwrap_or_default().trim_end();
+    let fence = match first_line {
+        "+++" | "---" => first_line,
+        _ => return Ok((ConventionSettings::default(), content.to_string())),
+    };
+
+    let after_open = &content[content.find('\n').map_or(content.len(), |i| i + 1)..];
+    let mut offset = 0;
+    for line in after_open.split_inclusive('\n') {
+        if line.trim_end() == fence {
+            let frontmatter = &after_open[..offset];
+            let body = after_open[offset + line.len()..].to_string();
+            let settings = if fence == "+++" {
+                toml::from_str(frontmatter).map_err(|e| format!("invalid TOML frontmatter: {}", e))?
+            } else if frontmatter.trim().is_empty() {
+                ConventionSettings::default()
+            } else {
+                serde_yaml_ng::from_str(frontmatter).map_err(|e| format!("invalid YAML frontmatter: {}", e))?
+            };
+            return Ok((settings, body));
+        }
+        offset += line.len();
+    }
+    // e.g. markdown that starts with a `---` rule
+    Ok((ConventionSettings::default(), content.to_string()))
+}
+
+/// The merged conventions that apply in a directory
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Convention {
+    /// Files in parent-to-child order, each with what it extends
+    pub files: Vec<ConventionFile>,
+    /// Settings of all files, children overriding parents
+    pub settings: ConventionSettings,
+    /// Habits from the commit history, used only when there are no files
+    pub inferred: Option<InferredConvention>,
+}
+
+impl Convention {
+    /// Conventions for the files changed in `diff`, in the current repository.
+    /// Outside a repository the current directory is treated as its root.
+    pub fn for_changes(diff: &str) -> io::Result<Self> {
+        let root = match git::repo_root() {
+            Ok(root) => root,
+            Err(_) => env::current_dir()?,
+        };
+        let paths: Vec<String> = parse_diff(diff)
+            .into_iter()
+            .flat_map(|file| [file.old_path, file.new_path])
+            .flatten()
+            .collect();
+        Self::for_paths(&root, &paths, global_convention_path().as_deref())
+    }
+
+    /// Conventions for whatever is staged right now
+    pub fn for_staged() -> io::Result<Self> {
+        let diff = git::get_staged_diff(&git::DiffOptions::default()).unwrap_or_default();
+        Self::for_changes(&diff)
+    }
+
+    /// Conventions for changes to `paths` (relative to `root`). Each path picks up the
+    /// files from its own directory up to `root`, stopping early at `inherit = false`;
+    /// `global` applies when at least one path's walk reaches the top.
+    pub fn for_paths(root: &Path, paths: &[String], global: Option<&Path>) -> io::Result<Self> {
+        let mut dirs: Vec<PathBuf> = paths
+            .iter()
+            .map(|path| root.join(path).parent().map_or_else(|| root.to_path_buf(), Path::to_path_buf))
+            .collect();
+        if dirs.is_empty() {
+            dirs.push(root.to_path_buf());
+        }
+        dirs.sort();
+        dirs.dedup();
+
+        let mut files: Vec<ConventionFile> = Vec::new();
+        let mut use_global = false;
+        for dir in &dirs {
+            let mut reached_top = true;
+            for ancestor in dir.ancestors().take_while(|ancestor| ancestor.starts_with(root)) {
+                let convention_path = ancestor.join(CONVENTION_FILE_NAME);
+                if !convention_path.is_file() {
+                    continue;
+                }
+                let stop = match files.iter().find(|file| file.path == convention_path) {
+                    Some(file) => file.settings.inherit == Some(false),
+                    None => {
+                        let file = ConventionFile::load(&convention_path)?;
+                        let stop = file.settings.inherit == Some(false);
+                        files.push(file);
+                        stop
+                    }
+                };
+                if stop {
+                    reached_top = false;
+                    break;
+                }
+            }
+            use_global |= reached_top;
+        }
+
+        // General to specific: the global file, then shallower directories, so deeper ones win
+        files.sort_by(|a, b| {
+            (a.path.components().count(), &a.path).cmp(&(b.path.components().count(), &b.path))
+        });
+        if let Some(global) = global.filter(|path| use_global && path.is_file()) {
+            files.insert(0, ConventionFile::load(global)?);
+        }
+
+        let mut convention = Convention { files, ..Default::default() };
+        let mut settings = ConventionSettings::default();
+        for file in convention.resolved() {
+            settings.merge(&file.settings);
+        }
+        convention.settings = settings;
+        Ok(convention)
+    }
+
+    /// Without any convention files, fall back to the habits of the recent commits
+    pub fn or_inferred(mut self) -> io::Result<Self> {
+        if self.files.is_empty() {
+            self.inferred = history::infer(&git::recent_messages(HISTORY_LIMIT)?);
+        }
+        Ok(self)
+    }
+
+    /// Fill in what the convention leaves open from the user's config
+    pub fn with_config(mut self, config: &Config) -> io::Result<Self> {
+        if config.get_infer_convention() {
+            self = self.or_inferred()?;
+        }
+        if self.settings.language.is_none() {
+            self.settings.language = config.language.clone();
+        }
+        if self.settings.bilingual.is_none() {
+            self.settings.bilingual = config.bilingual;
+        }
+        if self.settings.emoji.is_none() {
+            self.settings.emoji = config.get_emoji()?;
+        }
+        Ok(self)
+    }
+
+    /// Language candidates must be written in, from the settings or else the history
+    pub fn language(&self) -> Option<&str> {
+        self.settings
+            .language
+            .as_deref()
+            .or_else(|| self.inferred.as_ref().and_then(|inferred| inferred.settings.language.as_deref()))
+    }
+
+    /// Whether the subject is English and only the body is in [`Self::language`]
+    pub fn bilingual(&self) -> bool {
+        self.settings.bilingual == Some(true)
+    }
+
+    /// Problems with the language of `message` alone. Only an explicit `language`
+    /// counts; one inferred from the history is a suggestion.
+    pub fn language_problems(&self, message: &str) -> Vec<String> {
+        match &self.settings.language {
+            Some(language) => language::problems(message, language, self.bilingual()),
+            None => Vec::new(),
+        }
+    }
+
+    /// Every file with the presets and files it extends, in the order they apply
+    fn resolved(&self) -> Vec<&ConventionFile> {
+        let mut out = Vec::new();
+        for file in &self.files {
+            file.flatten(&mut out);
+        }
+        out
+    }
+
+    /// Where the convention comes from, one file per line with what it extends indented below it
+    pub fn outline(&self) -> String {
+        fn walk(file: &ConventionFile, depth: usize, out: &mut String) {
+            out.push_str(&format!("{}{}\n", "  ".repeat(depth), file.label()));
+            for include in &file.includes {
+                walk(include, depth + 1, out);
+            }
+        }
+        let mut out = String::new();
+        for file in &self.files {
+            walk(file, 0, &mut out);
+        }
+        if let Some(inferred) = self.inferred.as_ref().filter(|_| self.files.is_empty()) {
+            out.push_str(&format!("inferred from the last {} commits\n", inferred.sample_size));
+        }
+        out
+    }
+
+    /// Prompt text: numbered file bodies followed by the rules from the frontmatter
+    pub fn prompt(&self) -> String {
+        let mut parts: Vec<String> = match self.inferred.as_ref().filter(|_| self.files.is_empty()) {
+            Some(inferred) => {
+                let habits = Convention { settings: inferred.settings.clone(), ..Default::default() };
+                let lines: Vec<String> = habits.rules().into_iter().chain(inferred.guidance.iter().cloned()).map(|line| format!("- {}", line)).collect();
+                vec![format!("1. Match the style of this repository's last {} commits:\n{}", inferred.sample_size, lines.join("\n"))]
+            }
+            // Add priority numbers (1 = highest, 2, 3, 4... = lower priority). Files apply
+            // general to specific, and the more specific one wins, as it does for settings.
+            None => self
+                .resolved()
+                .into_iter()
+                .rev()
+                .map(|file| file.body.trim())
+                .filter(|body| !body.is_empty())
+                .enumerate()
+                .map(|(i, body)| format!("{}. {}", i + 1, body))
+                .collect(),
+        };
+
+        let rules = self.rules();
+        if !rules.is_empty() {
+            parts.push(format!("Rules:\n{}", rules.iter().map(|rule| format!("- {}", rule)).collect::<Vec<_>>().join("\n")));
+        }
+        parts.join("\n\n")
+    }
+
+    /// The frontmatter settings as instructions for the model
+    fn rules(&self) -> Vec<String> {
+        let settings = &self.settings;
+        let mut rules = Vec::new();
+        if settings.is_empty() {
+            return rules;
+        }
+        if let Some(types) = &settings.types {
+            rules.push(format!("Start the subject with `<type>: ` or `<type>(<scope>): `, where type is one of: {}", types.join(", ")));
+        }
+        if let Some(scopes) = &settings.scopes {
+            rules.push(format!("If you use a scope, it must be one of: {}", scopes.join(", ")));
+        }
+        match (&settings.language, settings.bilingual) {
+            (Some(language), Some(true)) => {
+                rules.push(format!("Write the subject line in English and the body in {}", language))
+            }
+            (Some(language), _) => rules.push(format!("Write the commit message in {}", language)),
+            (None, _) => {}
+        }
+        if let Some(max) = settings.max_subject_length {
+            rules.push(format!("Keep the subject line at most {} characters", max));
+        }
+        match settings.emoji {
+            Some(EmojiStyle::None) => rules.push("Don't use emoji".to_string()),
+            Some(EmojiStyle::Unicode) => rules.push("Start the subject with a gitmoji written as the emoji character, e.g. ✨".to_string()),
+            Some(EmojiStyle::Shortcode) => rules.push("Start the subject with a gitmoji written as a shortcode, e.g. :sparkles:".to_string()),
+            None => {}
+        }
+        rules
+    }
+
+    /// Ways in which `message` breaks the frontmatter settings
+    pub fn problems(&self, message: &str) -> Vec<String> {
+        let settings = &self.settings;
+        let mut problems = Vec::new();
+        let subject = message.lines().next().unwrap_or_default().trim();
+        if let Some(style) = settings.emoji {
+            problems.extend(gitmoji::problems(subject, style));
+        }
+        // Types and scopes come after any gitmoji
+        let subject = split_emoji(subject).map_or(subject, |(_, rest)| rest);
+
+        if let Some(types) = &settings.types {
+            match parse_header(subject) {
+                Some(header) if !types.iter().any(|t| t == header.kind) => problems.push(format!(
+                    "type `{}` is not one of: {}",
+                    header.kind,
+                    types.join(", ")
+                )),
+                Some(_) => {}
+                None => problems.push("subject doesn't start with `<type>: `".to_string()),
+            }
+        }
+        if let Some(scopes) = &settings.scopes
+            && let Some(scope) = parse_header(subject).and_then(|header| header.scope)
+            && !scopes.iter().any(|s| s == scope)
+        {
+            problems.push(format!("scope `{}` is not one of: {}", scope, scopes.join(", ")));
+        }
+        if let Some(max) = settings.max_subject_length {
+            let length = subject.chars().count();
+            if length > max {
+                problems.push(format!("subject is {} characters, longer than {}", length, max));
+            }
+        }
+        problems.extend(self.language_problems(message));
+        for trailer in settings.trailers.iter().flatten() {
+            let prefix = format!("{}:", trailer);
+            if !message.lines().any(|line| line.trim_start().starts_with(&prefix)) {
+                problems.push(format!("missing trailer `{}`", trailer));
+            }
+        }
+        problems
+    }
+}
+
+/// The parts of a `type(scope)!: description` subject line
+#[derive(Debug, Clone, PartialEq)]
+pub struct Header<'a> {
+    pub kind: &'a str,
+    pub scope: Option<&'a str>,
+    pub breaking: bool,
+    pub description: &'a str,
+}
+
+/// Parse a Conventional Commits subject line
+pub fn parse_header(subject: &str) -> Option<Header<'_>> {
+    let (header, description) = subject.split_once(": ")?;
+    let (header, breaking) = match header.strip_suffix('!') {
+        Some(header) => (header, true),
+        None => (header, false),
+    };
+    let (kind, scope) = match header.split_once('(') {
+        Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?)),
+        None => (header, None),
+    };
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    Some(Header { kind, scope, breaking, description })
+}
+
+/// `$XDG_CONFIG_HOME/committo/convention`, or `~/.config/committo/convention`
+pub fn global_convention_path() -> Option<PathBuf> {
+    committo_config_dir().map(|dir| dir.join(GLOBAL_CONVENTION_FILE_NAME))
+}
+
+/// Name of the global convention file inside the committo config directory
+const GLOBAL_CONVENTION_FILE_NAME: &str = "convention";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_plain_text_has_no_settings() {
+        let (settings, body) = parse_convention("Use Conventional Commits\n").unwrap();
+        assert_eq!(settings, ConventionSettings::default());
+        assert_eq!(body, "Use Conventional Commits\n");
+    }
+
+    #[test]
+    fn test_toml_and_yaml_frontmatter() {
+        let toml = "+++\ntypes = [\"feat\", \"fix\"]\nmax-subject-length = 50\n+++\nBody text\n";
+        let (settings, body) = parse_convention(toml).unwrap();
+        assert_eq!(settings.types, Some(vec!["feat".to_string(), "fix".to_string()]));
+        assert_eq!(settings.max_subject_length, Some(50));
+        assert_eq!(body, "Body text\n");
+
+        let yaml = "---\nlanguage: Korean\nemoji: shortcode\ninherit: false\n---\nBody text\n";
+        let (settings, body) = parse_convention(yaml).unwrap();
+        assert_eq!(settings.language.as_deref(), Some("Korean"));
+        assert_eq!(settings.emoji, Some(EmojiStyle::Shortcode));
+        assert_eq!(settings.inherit, Some(false));
+        assert_eq!(body, "Body text\n");
+    }
+
+    #[test]
+    fn test_bad_frontmatter_is_an_error() {
+        assert!(parse_convention("+++\ntypes = \"feat\"\n+++\n").is_err());
+        assert!(parse_convention("---\nunknown: 1\n---\n").is_err());
+    }
+
+    #[test]
+    fn test_unclosed_fence_is_plain_text() {
+        let markdown = "---\nUse the imperative mood.\nKeep subjects short.\n";
+        let (settings, body) = parse_convention(markdown).unwrap();
+        assert_eq!(settings, ConventionSettings::default());
+        assert_eq!(body, markdown);
+    }
+
+    #[test]
+    fn test_files_follow_the_changed_paths() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("repo");
+        for sub in ["frontend/src", "backend", "docs"] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        // Outside the repository: never used
+        fs::write(dir.path().join(CONVENTION_FILE_NAME), "Outside\n").unwrap();
+        fs::write(root.join(CONVENTION_FILE_NAME), "+++\nmax-subject-length = 72\n+++\nRoot\n").unwrap();
+        fs::write(root.join("frontend").join(CONVENTION_FILE_NAME), "+++\nmax-subject-length = 50\n+++\nFrontend\n").unwrap();
+        fs::write(root.join("backend").join(CONVENTION_FILE_NAME), "+++\ninherit = false\n+++\nBackend\n").unwrap();
+        let global = dir.path().join("global");
+        fs::write(&global, "Global\n").unwrap();
+
+        let bodies = |paths: &[&str]| {
+            let paths: Vec<String> = paths.iter().map(|p| p.to_string()).collect();
+            let convention = Convention::for_paths(&root, &paths, Some(&global)).unwrap();
+            let bodies: Vec<String> = convention.files.iter().map(|f| f.body.trim().to_string()).collect();
+            (bodies, convention.settings.max_subject_length)
+        };
+
+        assert_eq!(bodies(&["README.md"]), (vec!["Global".into(), "Root".into()], Some(72)));
+        assert_eq!(bodies(&["frontend/src/app.ts"]), (vec!["Global".into(), "Root".into(), "Frontend".into()], Some(50)));
+        assert_eq!(bodies(&["backend/main.rs"]), (vec!["Backend".into()], None));
+        assert_eq!(
+            bodies(&["backend/main.rs", "docs/guide.md"]),
+            (vec!["Global".into(), "Root".into(), "Backend".into()], Some(72))
+        );
+        assert_eq!(bodies(&[]), (vec!["Global".into(), "Root".into()], Some(72)));
+    }
+
+    #[test]
+    fn test_prompt_lists_bodies_then_rules() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(CONVENTION_FILE_NAME), "+++\nmax-subject-length = 50\n+++\nTop\n").unwrap();
+        let convention = Convention::for_paths(dir.path(), &[], None).unwrap();
+        assert_eq!(convention.prompt(), "1. Top\n\nRules:\n- Keep the subject line at most 50 characters");
+    }
+
+    #[test]
+    fn test_prompt_numbers_the_deepest_file_first() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("repo");
+        fs::create_dir_all(root.join("frontend")).unwrap();
+        fs::write(root.join(CONVENTION_FILE_NAME), "+++\nmax-subject-length = 72\n+++\nRoot\n").unwrap();
+        fs::write(root.join("frontend").join(CONVENTION_FILE_NAME), "+++\nmax-subject-length = 50\n+++\nFrontend\n").unwrap();
+        let global = dir.path().join("global");
+        fs::write(&global, "+++\nmax-subject-length = 100\n+++\nGlobal\n").unwrap();
+
+        let convention = Convention::for_paths(&root, &["frontend/app.ts".to_string()], Some(&global)).unwrap();
+        assert_eq!(
+            convention.prompt(),
+            "1. Frontend\n\n2. Root\n\n3. Global\n\nRules:\n- Keep the subject line at most 50 characters"
+        );
+    }
+
+    #[test]
+    fn test_extends_presets_and_files() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("shared")).unwrap();
+        fs::write(dir.path().join("shared").join("base.convention"), "+++\nmax-subject-length = 60\n+++\nShared\n").unwrap();
+        fs::write(
+            dir.path().join(CONVENTION_FILE_NAME),
+            "+++\nextends = [\"korean\", \"shared/base.convention\"]\n+++\nProject\n",
+        )
+        .unwrap();
+
+        let convention = Convention::for_paths(dir.path(), &[], None).unwrap();
+        let bodies: Vec<&str> = convention.resolved().iter().map(|file| file.body.trim()).collect();
+        assert!(bodies[0].starts_with("Follow Conventional Commits"));
+        assert!(bodies[1].starts_with("Keep the type prefix in English"));
+        assert_eq!(&bodies[2..], ["Shared", "Project"]);
+        assert_eq!(convention.settings.language.as_deref(), Some("Korean"));
+        assert_eq!(convention.settings.max_subject_length, Some(60));
+        assert!(convention.settings.types.as_ref().unwrap().contains(&"feat".to_string()));
+
+        let outline = convention.outline();
+        let lines: Vec<&str> = outline.lines().collect();
+        assert!(lines[0].ends_with(CONVENTION_FILE_NAME));
+        assert_eq!(lines[1], "  preset `korean`");
+        assert_eq!(lines[2], "    preset `conventional`");
+        assert!(lines[3].starts_with("  /") && lines[3].ends_with("base.convention"));
+    }
+
+    #[test]
+    fn test_file_overrides_its_preset() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join(CONVENTION_FILE_NAME),
+            "+++\nextends = \"angular\"\nmax-subject-length = 50\n+++\nWrite the summary in sentence case.\n",
+        )
+        .unwrap();
+
+        let convention = Convention::for_paths(dir.path(), &[], None).unwrap();
+        assert_eq!(convention.settings.max_subject_length, Some(50));
+        let prompt = convention.prompt();
+        assert!(prompt.starts_with("1. Write the summary in sentence case.\n\n2. Follow the Angular commit format"), "{}", prompt);
+        assert!(prompt.contains("- Keep the subject line at most 50 characters"), "{}", prompt);
+    }
+
+    #[test]
+    fn test_extends_cycles_and_missing_files_are_errors() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a"), "+++\ninclude = \"b\"\n+++\n").unwrap();
+        fs::write(dir.path().join("b"), "+++\ninclude = [\"a\"]\n+++\n").unwrap();
+        let error = ConventionFile::load(&dir.path().canonicalize().unwrap().join("a")).unwrap_err();
+        assert!(error.to_string().contains("include cycle"), "{}", error);
+
+        fs::write(dir.path().join("c"), "+++\nextends = \"nope\"\n+++\n").unwrap();
+        let error = ConventionFile::load(&dir.path().join("c")).unwrap_err();
+        assert!(error.to_string().contains("can't extend `nope`"), "{}", error);
+    }
+
+    #[test]
+    fn test_presets_parse() {
+        for name in preset_names() {
+            ConventionFile::resolve(PathBuf::new(), Some(name), &mut Vec::new()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_inferred_prompt_without_files() {
+        let inferred = InferredConvention {
+            sample_size: 40,
+            settings: ConventionSettings { types: Some(vec!["feat".to_string()]), ..Default::default() },
+            guidance: vec!["Start the description with a lowercase letter".to_string()],
+        };
+        let convention = Convention { inferred: Some(inferred), ..Default::default() };
+        assert_eq!(
+            convention.prompt(),
+            "1. Match the style of this repository's last 40 commits:\n\
+             - Start the subject with `<type>: ` or `<type>(<scope>): `, where type is one of: feat\n\
+             - Start the description with a lowercase letter"
+        );
+        assert_eq!(convention.outline(), "inferred from the last 40 commits\n");
+        // Inferred habits are guidance only
+        assert!(convention.problems("Update README").is_empty());
+    }
+
+    #[test]
+    fn test_language_rules() {
+        let mut convention = Convention {
+            settings: ConventionSettings { language: Some("Korean".to_string()), ..Default::default() },
+            ..Default::default()
+        };
+        assert_eq!(convention.rules(), vec!["Write the commit message in Korean"]);
+        assert_eq!(convention.problems("feat: add login"), vec!["subject is not written in Korean"]);
+
+        convention.settings.bilingual = Some(true);
+        assert_eq!(convention.rules(), vec!["Write the subject line in English and the body in Korean"]);
+        assert!(convention.problems("feat: add login\n\n로그인 화면을 추가합니다.").is_empty());
+    }
+
+    #[test]
+    fn test_problems() {
+        let convention = Convention {
+            files: Vec::new(),
+            settings: ConventionSettings {
+                types: Some(vec!["feat".to_string(), "fix".to_string()]),
+                scopes: Some(vec!["api".to_string()]),
+                max_subject_length: Some(20),
+                trailers: Some(vec!["Signed-off-by".to_string()]),
+                ..Default::default()
+            },
+            inferred: None,
+        };
+        assert_eq!(
+            convention.problems("chore(web): tidy up the build scripts"),
+            vec![
+                "type `chore` is not one of: feat, fix",
+                "scope `web` is not one of: api",
+                "subject is 37 characters, longer than 20",
+                "missing trailer `Signed-off-by`",
+            ]
+        );
+        assert!(convention.problems("fix(api): typo\n\nSigned-off-by: A <a@b.c>").is_empty());
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let header = parse_header("feat(api)!: drop v1").unwrap();
+        assert_eq!((header.kind, header.scope, header.breaking, header.description), ("feat", Some("api"), true, "drop v1"));
+        assert_eq!(parse_header("Update README"), None);
+        assert_eq!(parse_header("Fix: something"), None);
+    }
+}
@@ -0,0 +1,729 @@
+//! Unified diff parsing
+//!
+//! Splits the output of `git diff` into files and hunks so that parts of a
+//! diff can be described, selected and re-rendered as standalone patches.
+//! Extended headers (renames, modes, binary files, submodules) are turned
+//! into [`FileChange`]s so they can be described in plain words.
+
+/// One `@@` hunk of a file diff
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    /// The `@@ -a,b +c,d @@ section` line
+    pub header: String,
+    /// Context, added and removed lines (including `\ No newline` markers)
+    pub lines: Vec<String>,
+}
+
+impl Hunk {
+    /// Enclosing function or section that git prints after the second `@@`, if any
+    pub fn section(&self) -> Option<&str> {
+        let (_, section) = self.header.strip_prefix("@@")?.split_once("@@")?;
+        let section = section.trim();
+        (!section.is_empty()).then_some(section)
+    }
+
+    /// Lines of the old file this hunk changes: the removed ones, and for lines
+    /// added without removing any, the line they were added after
+    pub fn changed_old_lines(&self) -> Vec<u32> {
+        // `@@ -a,b +c,d @@`, where `,b` is left out when it is 1
+        let Some(old) = self.header.strip_prefix("@@ -").and_then(|rest| rest.split_whitespace().next()) else {
+            return Vec::new();
+        };
+        let (start, count) = old.split_once(',').unwrap_or((old, "1"));
+        let (Ok(start), Ok(count)) = (start.parse::<u32>(), count.parse::<u32>()) else {
+            return Vec::new();
+        };
+        // A hunk that removes nothing names the line before it
+        let mut line = if count == 0 { start + 1 } else { start };
+
+        let mut changed = Vec::new();
+        let mut in_change = false;
+        for text in &self.lines {
+            match text.chars().next() {
+                Some('-') => {
+                    changed.push(line);
+                    line += 1;
+                    in_change = true;
+                }
+                Some('+') => {
+                    if !in_change && line > 1 {
+                        changed.push(line - 1);
+                    }
+                    in_change = true;
+                }
+                Some('\\') => {}
+                _ => {
+                    line += 1;
+                    in_change = false;
+                }
+            }
+        }
+        changed.sort_unstable();
+        changed.dedup();
+        changed
+    }
+}
+
+/// All changes to a single file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDiff {
+    /// Lines from `diff --git` up to the first hunk (also holds binary patch data)
+    pub header: Vec<String>,
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FileDiff {
+    /// Path used to refer to this file (the new path unless the file was deleted)
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Render this file as a patch, keeping only the hunks at `hunk_indices` (all if `None`)
+    pub fn render(&self, hunk_indices: Option<&[usize]>) -> String {
+        let mut lines: Vec<&str> = self.header.iter().map(String::as_str).collect();
+        for (i, hunk) in self.hunks.iter().enumerate() {
+            if hunk_indices.is_none_or(|indices| indices.contains(&i)) {
+                lines.push(&hunk.header);
+                lines.extend(hunk.lines.iter().map(String::as_str));
+            }
+        }
+        let mut patch = lines.join("\n");
+        patch.push('\n');
+        patch
+    }
+}
+
+/// Parse `git diff` output into per-file diffs
+pub fn parse_diff(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+
+    // Only the `\n` is dropped: a `\r` belongs to the content of CRLF files
+    for line in diff.split_inclusive('\n').map(|line| line.strip_suffix('\n').unwrap_or(line)) {
+        if line.starts_with("diff --git ") {
+            let (old_path, new_path) = paths_from_git_header(line);
+            files.push(FileDiff {
+                header: vec![line.to_string()],
+                old_path,
+                new_path,
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+
+        if line.starts_with("@@") {
+            file.hunks.push(Hunk {
+                header: line.to_string(),
+                lines: Vec::new(),
+            });
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            hunk.lines.push(line.to_string());
+        } else {
+            // Still in the file header: pick up the authoritative paths
+            if let Some(path) = line.strip_prefix("--- ") {
+                file.old_path = header_path(path);
+            } else if let Some(path) = line.strip_prefix("+++ ") {
+                file.new_path = header_path(path);
+            } else if let Some(path) = line.strip_prefix("rename from ") {
+                file.old_path = Some(unquote(path));
+            } else if let Some(path) = line.strip_prefix("rename to ") {
+                file.new_path = Some(unquote(path));
+            } else if let Some(path) = line.strip_prefix("copy from ") {
+                file.old_path = Some(unquote(path));
+            } else if let Some(path) = line.strip_prefix("copy to ") {
+                file.new_path = Some(unquote(path));
+            }
+            file.header.push(line.to_string());
+        }
+    }
+
+    files
+}
+
+/// Mark every hunk with the function or section it changes, so the provider
+/// doesn't have to dig the name out of the `@@` line. Only text hunks are kept;
+/// everything the extended headers say is covered by [`render_file_changes`].
+pub fn annotate_hunks(diff: &str) -> String {
+    let mut output = String::new();
+    for file in parse_diff(diff) {
+        if file.hunks.is_empty() || FileChange::from_diff(&file).submodule {
+            continue;
+        }
+        for line in file.header.iter().filter(|line| {
+            line.starts_with("diff --git ") || line.starts_with("--- ") || line.starts_with("+++ ")
+        }) {
+            output.push_str(line);
+            output.push('\n');
+        }
+        for hunk in &file.hunks {
+            if let Some(section) = hunk.section() {
+                output.push_str(&format!("# {}: in {}\n", file.path(), section));
+            }
+            output.push_str(&hunk.header);
+            output.push('\n');
+            for line in &hunk.lines {
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+    }
+    output
+}
+
+/// The diff as sent to the provider: a plain-words list of file changes, then the text hunks.
+/// The list is kept apart from the hunks so that anything trimming the diff leaves it intact.
+pub fn prompt_diff(diff: &str, blob_info: impl Fn(&str) -> Option<BlobInfo>) -> String {
+    let mut changes: Vec<FileChange> = parse_diff(diff).iter().map(FileChange::from_diff).collect();
+    for change in &mut changes {
+        change.load_blob_info(&blob_info);
+    }
+    let files = render_file_changes(&changes);
+    let hunks = annotate_hunks(diff);
+    if files.is_empty() {
+        hunks
+    } else {
+        format!("{}\n{}", files, hunks)
+    }
+}
+
+/// What happened to a file as a whole
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+    Copied,
+}
+
+/// Size and, for images, format and dimensions of one version of a binary file
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlobInfo {
+    pub size: u64,
+    pub image: Option<ImageInfo>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageInfo {
+    pub format: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Everything the extended `diff --git` header says about a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileChange {
+    pub kind: ChangeKind,
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    /// Rename or copy similarity in percent
+    pub similarity: Option<u8>,
+    pub old_mode: Option<String>,
+    pub new_mode: Option<String>,
+    pub binary: bool,
+    pub submodule: bool,
+    /// Abbreviated object ids from the `index` line (commits for submodules)
+    pub old_id: Option<String>,
+    pub new_id: Option<String>,
+    pub old_blob: Option<BlobInfo>,
+    pub new_blob: Option<BlobInfo>,
+}
+
+/// Mode git uses for submodule entries
+const SUBMODULE_MODE: &str = "160000";
+
+impl FileChange {
+    /// Read the extended header of a parsed file diff
+    pub fn from_diff(file: &FileDiff) -> Self {
+        let mut change = FileChange {
+            kind: ChangeKind::Modified,
+            old_path: file.old_path.clone(),
+            new_path: file.new_path.clone(),
+            similarity: None,
+            old_mode: None,
+            new_mode: None,
+            binary: false,
+            submodule: false,
+            old_id: None,
+            new_id: None,
+            old_blob: None,
+            new_blob: None,
+        };
+
+        for line in &file.header {
+            if let Some(mode) = line.strip_prefix("new file mode ") {
+                change.kind = ChangeKind::Added;
+                change.new_mode = Some(mode.to_string());
+            } else if let Some(mode) = line.strip_prefix("deleted file mode ") {
+                change.kind = ChangeKind::Deleted;
+                change.old_mode = Some(mode.to_string());
+            } else if let Some(mode) = line.strip_prefix("old mode ") {
+                change.old_mode = Some(mode.to_string());
+            } else if let Some(mode) = line.strip_prefix("new mode ") {
+                change.new_mode = Some(mode.to_string());
+            } else if line.starts_with("rename from ") {
+                change.kind = ChangeKind::Renamed;
+            } else if line.starts_with("copy from ") {
+                change.kind = ChangeKind::Copied;
+            } else if let Some(percent) = line.strip_prefix("similarity index ") {
+                change.similarity = percent.trim_end_matches('%').parse().ok();
+            } else if let Some(index) = line.strip_prefix("index ") {
+                let (ids, mode) = index.split_once(' ').unwrap_or((index, ""));
+                if let Some((old, new)) = ids.split_once("..") {
+                    change.old_id = Some(old.to_string()).filter(|id| !is_null_id(id));
+                    change.new_id = Some(new.to_string()).filter(|id| !is_null_id(id));
+                }
+                if mode == SUBMODULE_MODE {
+                    change.submodule = true;
+                }
+            } else if line.starts_with("Binary files ") || line.starts_with("GIT binary patch") {
+                change.binary = true;
+            }
+        }
+        if [&change.old_mode, &change.new_mode].iter().any(|mode| mode.as_deref() == Some(SUBMODULE_MODE)) {
+            change.submodule = true;
+        }
+        change
+    }
+
+    /// Look up sizes (and image dimensions) of binary files
+    pub fn load_blob_info(&mut self, blob_info: impl Fn(&str) -> Option<BlobInfo>) {
+        if !self.binary || self.submodule {
+            return;
+        }
+        self.old_blob = self.old_id.as_deref().and_then(&blob_info);
+        self.new_blob = self.new_id.as_deref().and_then(&blob_info);
+    }
+
+    fn path(&self) -> &str {
+        self.new_path.as_deref().or(self.old_path.as_deref()).unwrap_or_default()
+    }
+
+    /// One line in plain words, e.g. "renamed a.rs -> b.rs (90% similar)"
+    pub fn describe(&self) -> String {
+        if self.submodule {
+            return match (self.kind, &self.old_id, &self.new_id) {
+                (ChangeKind::Added, _, Some(new)) => format!("added submodule {} at {}", self.path(), new),
+                (ChangeKind::Deleted, _, _) => format!("removed submodule {}", self.path()),
+                (_, Some(old), Some(new)) => format!("updated submodule {}: {} -> {}", self.path(), old, new),
+                _ => format!("changed submodule {}", self.path()),
+            };
+        }
+
+        let old = self.old_path.as_deref().unwrap_or_default();
+        let mut line = match self.kind {
+            ChangeKind::Added => format!("added {}", self.path()),
+            ChangeKind::Deleted => format!("deleted {}", self.path()),
+            ChangeKind::Modified => format!("modified {}", self.path()),
+            ChangeKind::Renamed => format!("renamed {} -> {}", old, self.path()),
+            ChangeKind::Copied => format!("copied {} -> {}", old, self.path()),
+        };
+
+        let mut details = Vec::new();
+        if let Some(similarity) = self.similarity {
+            details.push(format!("{}% similar", similarity));
+        }
+        if self.kind == ChangeKind::Modified || self.kind == ChangeKind::Renamed || self.kind == ChangeKind::Copied {
+            if let (Some(old_mode), Some(new_mode)) = (&self.old_mode, &self.new_mode) {
+                details.push(format!("mode {} -> {}{}", old_mode, new_mode, executable_note(old_mode, new_mode)));
+            }
+        } else if let Some(mode) = self.new_mode.as_deref().filter(|mode| *mode == "100755") {
+            details.push(format!("mode {}, executable", mode));
+        } else if let Some(mode) = self.new_mode.as_deref().filter(|mode| *mode == "120000") {
+            details.push(format!("mode {}, symlink", mode));
+        }
+        if self.binary {
+            details.push(self.describe_binary());
+        }
+
+        if !details.is_empty() {
+            line.push_str(&format!(" ({})", details.join(", ")));
+        }
+        line
+    }
+
+    fn describe_binary(&self) -> String {
+        match (&self.old_blob, &self.new_blob) {
+            (Some(old), Some(new)) => format!("binary, {} -> {}", describe_blob(old), describe_blob(new)),
+            (None, Some(blob)) | (Some(blob), None) => format!("binary, {}", describe_blob(blob)),
+            (None, None) => "binary".to_string(),
+        }
+    }
+}
+
+/// Plain-words list of all file changes, empty if there are none
+pub fn render_file_changes(changes: &[FileChange]) -> String {
+    if changes.is_empty() {
+        return String::new();
+    }
+    let mut output = String::from("Files changed:\n");
+    for change in changes {
+        output.push_str(&format!("  {}\n", change.describe()));
+    }
+    output
+}
+
+fn is_null_id(id: &str) -> bool {
+    id.chars().all(|c| c == '0')
+}
+
+fn executable_note(old_mode: &str, new_mode: &str) -> &'static str {
+    match (old_mode, new_mode) {
+        ("100644", "100755") => ", now executable",
+        ("100755", "100644") => ", no longer executable",
+        _ => "",
+    }
+}
+
+fn describe_blob(blob: &BlobInfo) -> String {
+    match blob.image {
+        Some(image) => format!("{} {}x{}, {}", image.format, image.width, image.height, human_size(blob.size)),
+        None => human_size(blob.size),
+    }
+}
+
+/// Byte count for people, e.g. "12.3 KB"
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Format and dimensions of a PNG, GIF, JPEG or BMP image
+pub fn image_info(bytes: &[u8]) -> Option<ImageInfo> {
+    let be32 = |at: usize| Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    let le16 = |at: usize| Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32);
+    let le32 = |at: usize| Some(i32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?).unsigned_abs());
+
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some(ImageInfo { format: "PNG", width: be32(16)?, height: be32(20)? });
+    }
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return Some(ImageInfo { format: "GIF", width: le16(6)?, height: le16(8)? });
+    }
+    if bytes.starts_with(b"BM") {
+        return Some(ImageInfo { format: "BMP", width: le32(18)?, height: le32(22)? });
+    }
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        return jpeg_dimensions(bytes);
+    }
+    None
+}
+
+/// Walk JPEG segments up to the first start-of-frame marker
+fn jpeg_dimensions(bytes: &[u8]) -> Option<ImageInfo> {
+    let be16 = |at: usize| Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32);
+    let mut at = 2;
+    while at + 4 <= bytes.len() {
+        if bytes[at] != 0xFF {
+            return None;
+        }
+        let marker = bytes[at + 1];
+        let length = be16(at + 2)? as usize;
+        // SOF0..SOF15, except DHT (C4), JPG (C8) and DAC (CC)
+        if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+            return Some(ImageInfo { format: "JPEG", width: be16(at + 7)?, height: be16(at + 5)? });
+        }
+        at += 2 + length;
+    }
+    None
+}
+
+/// Best-effort paths from `diff --git a/<old> b/<new>`; refined later by `---`/`+++` lines
+fn paths_from_git_header(line: &str) -> (Option<String>, Option<String>) {
+    let rest = line.trim_start_matches("diff --git ");
+    let (old, new) = if rest.starts_with('"') {
+        // `"a/..." b/...` or `"a/..." "b/..."`
+        match quoted_end(rest) {
+            Some(end) => (&rest[..end], rest[end..].trim_start()),
+            None => return (None, None),
+        }
+    } else if let Some(start) = rest.ends_with('"').then(|| rest.rfind(" \"b/")).flatten() {
+        (&rest[..start], &rest[start + 1..])
+    } else {
+        // Unquoted paths may contain spaces, so they can only be told apart when they
+        // are the same; renames and copies have `rename`/`copy` lines with the paths
+        let half = rest.len().saturating_sub(1) / 2;
+        match (rest.get(..half), rest.get(half + 1..)) {
+            (Some(old), Some(new)) if rest.as_bytes()[half] == b' ' && old.get(2..) == new.get(2..) => (old, new),
+            _ => return (None, None),
+        }
+    };
+    let strip = |path: &str, prefix: &str| {
+        let path = unquote(path);
+        path.strip_prefix(prefix).map(str::to_string).unwrap_or(path)
+    };
+    (Some(strip(old, "a/")), Some(strip(new, "b/")))
+}
+
+/// Byte index just past the closing quote of the C-quoted string `text` starts with
+fn quoted_end(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Path from a `---`/`+++` line, `None` for `/dev/null`
+fn header_path(path: &str) -> Option<String> {
+    let path = unquote(path.trim_end_matches(['\t', '\r']));
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(
+        path.strip_prefix("a/")
+            .or_else(|| path.strip_prefix("b/"))
+            .unwrap_or(&path)
+            .to_string(),
+    )
+}
+
+/// Decode a path git wrote in C quotes (because of spaces, quotes or non-ASCII bytes)
+fn unquote(path: &str) -> String {
+    let Some(quoted) = path.strip_prefix('"').and_then(|path| path.strip_suffix('"')) else {
+        return path.to_string();
+    };
+    let mut bytes = Vec::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('a') => bytes.push(0x07),
+            Some('b') => bytes.push(0x08),
+            Some('t') => bytes.push(b'\t'),
+            Some('n') => bytes.push(b'\n'),
+            Some('v') => bytes.push(0x0b),
+            Some('f') => bytes.push(0x0c),
+            Some('r') => bytes.push(b'\r'),
+            // Octal escapes are single bytes of a UTF-8 sequence
+            Some(digit @ '0'..='7') => {
+                let mut value = digit.to_digit(8).unwrap_or_default();
+                for _ in 0..2 {
+                    let next = chars.clone().next().and_then(|c| c.to_digit(8));
+                    let Some(next) = next else { break };
+                    value = value * 8 + next;
+                    chars.next();
+                }
+                bytes.push(value as u8);
+            }
+            Some(other) => {
+                let mut buffer = [0; 4];
+                bytes.extend_from_slice(other.encode_utf8(&mut buffer).as_bytes());
+            }
+            None => bytes.push(b'\\'),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_old_lines() {
+        let hunk = |header: &str, lines: &[&str]| Hunk {
+            header: header.to_string(),
+            lines: lines.iter().map(|line| line.to_string()).collect(),
+        };
+        // Replaced line 11, inserted after 13
+        let replaced = hunk("@@ -10,4 +10,5 @@ fn run()", &[" a", "-b", "+B", " c", " d", "+e"]);
+        assert_eq!(replaced.changed_old_lines(), vec![11, 13]);
+        // Pure insertion after line 5
+        assert_eq!(hunk("@@ -5,0 +6,2 @@", &["+x", "+y"]).changed_old_lines(), vec![5]);
+        // New file
+        assert!(hunk("@@ -0,0 +1 @@", &["+x"]).changed_old_lines().is_empty());
+        assert_eq!(hunk("@@ -3 +3 @@", &["-x", "+y"]).changed_old_lines(), vec![3]);
+    }
+
+    const DIFF: &str = "diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@ pub mod api;
+ a
+-b
++c
+@@ -10,2 +10,3 @@ fn run()
+ x
++y
+diff --git a/new.txt b/new.txt
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1 @@
++hello
+";
+
+    #[test]
+    fn test_parse_files_and_hunks() {
+        let files = parse_diff(DIFF);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path(), "src/lib.rs");
+        assert_eq!(files[0].hunks.len(), 2);
+        assert_eq!(files[0].hunks[1].lines, vec![" x", "+y"]);
+        assert_eq!(files[1].old_path, None);
+        assert_eq!(files[1].path(), "new.txt");
+    }
+
+    #[test]
+    fn test_render_selected_hunks() {
+        let files = parse_diff(DIFF);
+        let patch = files[0].render(Some(&[1]));
+        assert!(patch.starts_with("diff --git a/src/lib.rs b/src/lib.rs\n"));
+        assert!(patch.contains("@@ -10,2 +10,3 @@ fn run()\n x\n+y\n"));
+        assert!(!patch.contains("-b"));
+    }
+
+    #[test]
+    fn test_render_all_roundtrips() {
+        let files = parse_diff(DIFF);
+        let rendered: String = files.iter().map(|f| f.render(None)).collect();
+        assert_eq!(rendered, DIFF);
+    }
+
+    #[test]
+    fn test_hunk_section() {
+        let files = parse_diff(DIFF);
+        assert_eq!(files[0].hunks[0].section(), Some("pub mod api;"));
+        assert_eq!(files[0].hunks[1].section(), Some("fn run()"));
+        assert_eq!(files[1].hunks[0].section(), None);
+    }
+
+    #[test]
+    fn test_annotate_hunks() {
+        let annotated = annotate_hunks(DIFF);
+        assert!(annotated.contains("# src/lib.rs: in fn run()\n@@ -10,2 +10,3 @@ fn run()\n"));
+        assert!(!annotated.contains("# new.txt"));
+        // Extended headers are described elsewhere
+        assert!(!annotated.contains("index 1111111..2222222"));
+        assert!(!annotated.contains("new file mode"));
+    }
+
+    #[test]
+    fn test_describe_file_changes() {
+        let diff = "diff --git a/old.rs b/new.rs
+similarity index 90%
+rename from old.rs
+rename to new.rs
+diff --git a/run.sh b/run.sh
+old mode 100644
+new mode 100755
+diff --git a/logo.png b/logo.png
+new file mode 100644
+index 0000000..abc1234
+Binary files /dev/null and b/logo.png differ
+diff --git a/vendor/lib b/vendor/lib
+index 1234567..89abcde 160000
+--- a/vendor/lib
++++ b/vendor/lib
+@@ -1 +1 @@
+-Subproject commit 1234567
++Subproject commit 89abcde
+diff --git a/a.rs b/b.rs
+similarity index 100%
+copy from a.rs
+copy to b.rs
+";
+        let png = BlobInfo {
+            size: 2048,
+            image: Some(ImageInfo { format: "PNG", width: 64, height: 32 }),
+        };
+        let rendered = prompt_diff(diff, |id| (id == "abc1234").then(|| png.clone()));
+        assert_eq!(
+            rendered,
+            "Files changed:
+  renamed old.rs -> new.rs (90% similar)
+  modified run.sh (mode 100644 -> 100755, now executable)
+  added logo.png (binary, PNG 64x32, 2.0 KB)
+  updated submodule vendor/lib: 1234567 -> 89abcde
+  copied a.rs -> b.rs (100% similar)
+
+"
+        );
+    }
+
+    #[test]
+    fn test_image_info() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.extend(300u32.to_be_bytes());
+        png.extend(200u32.to_be_bytes());
+        assert_eq!(image_info(&png), Some(ImageInfo { format: "PNG", width: 300, height: 200 }));
+
+        let gif = b"GIF89a\x0a\x00\x14\x00";
+        assert_eq!(image_info(gif), Some(ImageInfo { format: "GIF", width: 10, height: 20 }));
+
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00, 0x30, 0x00, 0x40];
+        assert_eq!(image_info(&jpeg), Some(ImageInfo { format: "JPEG", width: 64, height: 48 }));
+
+        assert_eq!(image_info(b"plain text"), None);
+        assert_eq!(human_size(512), "512 B");
+        assert_eq!(human_size(1536), "1.5 KB");
+    }
+
+    #[test]
+    fn test_rename_paths() {
+        let diff = "diff --git a/old.rs b/new.rs\nsimilarity index 100%\nrename from old.rs\nrename to new.rs\n";
+        let files = parse_diff(diff);
+        assert_eq!(files[0].old_path.as_deref(), Some("old.rs"));
+        assert_eq!(files[0].new_path.as_deref(), Some("new.rs"));
+        assert!(files[0].hunks.is_empty());
+    }
+
+    #[test]
+    fn test_crlf_lines_survive_a_roundtrip() {
+        let diff = "diff --git a/win.txt b/win.txt\nindex 1111111..2222222 100644\n--- a/win.txt\n+++ b/win.txt\n@@ -1,2 +1,2 @@\n a\r\n-b\r\n+c\r\n";
+        let files = parse_diff(diff);
+        assert_eq!(files[0].hunks[0].lines, vec![" a\r", "-b\r", "+c\r"]);
+        assert_eq!(files[0].render(None), diff);
+    }
+
+    #[test]
+    fn test_quoted_paths_are_decoded() {
+        let diff = "diff --git \"a/caf\\303\\251 \\\"menu\\\".txt\" \"b/caf\\303\\251 \\\"menu\\\".txt\"\nindex 1111111..2222222 100644\nBinary files differ\n";
+        let files = parse_diff(diff);
+        assert_eq!(files[0].old_path.as_deref(), Some("café \"menu\".txt"));
+        assert_eq!(files[0].new_path.as_deref(), Some("café \"menu\".txt"));
+        assert_eq!(unquote("\"back\\\\slash\""), "back\\slash");
+        assert_eq!(unquote("\"a\\tb\""), "a\tb");
+    }
+
+    #[test]
+    fn test_paths_containing_b_slash() {
+        // Without ---/+++ lines the paths come from the `diff --git` line alone
+        let diff = "diff --git a/docs/a b/c.png b/docs/a b/c.png\nindex 1111111..2222222 100644\nBinary files differ\n";
+        assert_eq!(parse_diff(diff)[0].path(), "docs/a b/c.png");
+
+        let diff = "diff --git a/x b/y.txt b/x b/y.txt\nindex 1111111..2222222 100644\n--- a/x b/y.txt\n+++ b/x b/y.txt\n@@ -1 +1 @@\n-a\n+b\n";
+        let files = parse_diff(diff);
+        assert_eq!(files[0].old_path.as_deref(), Some("x b/y.txt"));
+        assert_eq!(files[0].new_path.as_deref(), Some("x b/y.txt"));
+    }
+}
@@ -0,0 +1,212 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+use crate::commit::{SigningFormat, is_identity, parse_trailer};
+use crate::config::{
+    API_KEY_CONFIG, BILINGUAL_CONFIG, CA_BUNDLES_CONFIG, CANDIDATE_COUNT_CONFIG,
+    CLIENT_CERT_CONFIG, CLIENT_KEY_CONFIG, CO_AUTHORS_CONFIG, CONFIG_VERSION_CONFIG,
+    CONNECT_TIMEOUT_CONFIG, CURRENT_CONFIG_VERSION, Config, DETECT_FIXUPS_CONFIG,
+    DIFF_ALGORITHM_CONFIG, DIFF_CONTEXT_CONFIG, DIFF_FUNCTION_CONTEXT_CONFIG,
+    DIFF_IGNORE_WHITESPACE_CONFIG, DIFF_RENAMES_CONFIG, EMOJI_CONFIG, FALLBACK_BUDGET_CONFIG,
+    FALLBACK_CONFIG, HTTPS_PROXY_CONFIG, INFER_CONVENTION_CONFIG, LANGUAGE_CONFIG,
+    LLM_MODEL_CONFIG, LLM_PROVIDER_CONFIG, MAX_RETRIES_CONFIG, NO_PROXY_CONFIG,
+    REQUEST_TIMEOUT_CONFIG, RUN_EDIT_CONFIG, SCOPE_MODE_CONFIG, SETTABLE_CONFIG_KEYS,
+    SIGN_COMMITS_CONFIG, SIGNING_FORMAT_CONFIG, SIGNING_KEY_CONFIG, SIGNOFF_CONFIG,
+    STRUCTURAL_SUMMARY_CONFIG, SUPPORTED_PROVIDERS, TRAILERS_CONFIG, parse_choice,
+};
+use crate::convention::EmojiStyle;
+use crate::git::{DiffAlgorithm, RenameDetection};
+use crate::http::parse_proxy;
+use crate::migration::{self, DEPRECATED_KEYS};
+use crate::providers::FallbackEntry;
+use crate::scope::ScopeMode;
+
+/// Result of inspecting a config file without modifying it
+#[derive(Debug, Default)]
+pub struct DoctorReport {
+    pub config_path: PathBuf,
+    pub version: u32,
+    /// Deprecated keys as (old name, replacement)
+    pub deprecated: Vec<(String, String)>,
+    /// Keys committo doesn't use
+    pub unknown: Vec<String>,
+    /// Keys with unusable values as (key, problem)
+    pub invalid: Vec<(String, String)>,
+}
+
+impl DoctorReport {
+    /// Whether the file needs no attention at all
+    pub fn is_healthy(&self) -> bool {
+        self.version == CURRENT_CONFIG_VERSION
+            && self.deprecated.is_empty()
+            && self.unknown.is_empty()
+            && self.invalid.is_empty()
+    }
+
+    /// Render the report for display
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        output.push_str("--- Config Doctor ---\n");
+        output.push_str(&format!("Config file : {}\n", self.config_path.display()));
+        output.push_str(&format!(
+            "Schema version : {} (current: {})\n",
+            self.version, CURRENT_CONFIG_VERSION
+        ));
+
+        if self.version < CURRENT_CONFIG_VERSION {
+            output.push_str("\nOutdated schema: the file will be upgraded (with a backup) the next time it is loaded.\n");
+        }
+        if !self.deprecated.is_empty() {
+            output.push_str("\nDeprecated keys:\n");
+            for (old, new) in &self.deprecated {
+                output.push_str(&format!("  - {} (use '{}' instead)\n", old, new));
+            }
+        }
+        if !self.invalid.is_empty() {
+            output.push_str("\nInvalid values:\n");
+            for (key, problem) in &self.invalid {
+                output.push_str(&format!("  - {}: {}\n", key, problem));
+            }
+        }
+        if !self.unknown.is_empty() {
+            output.push_str("\nUnused keys (ignored by committo):\n");
+            for key in &self.unknown {
+                output.push_str(&format!("  - {}\n", key));
+            }
+        }
+        if self.is_healthy() {
+            output.push_str("\nNo problems found.\n");
+        }
+        output
+    }
+}
+
+/// Inspect the config file at `config_path` and report deprecated, invalid or unused settings
+pub fn diagnose(config_path: &Path) -> io::Result<DoctorReport> {
+    let table = Config::load_table(config_path)?;
+    let mut report = DoctorReport {
+        config_path: config_path.to_path_buf(),
+        version: migration::table_version(&table),
+        ..Default::default()
+    };
+
+    for (key, value) in &table {
+        let current_key = match DEPRECATED_KEYS.iter().find(|(old, _)| old == key) {
+            Some((old, new)) => {
+                report.deprecated.push((old.to_string(), new.to_string()));
+                *new
+            }
+            None => key.as_str(),
+        };
+
+        if current_key != CONFIG_VERSION_CONFIG && !SETTABLE_CONFIG_KEYS.contains(&current_key) {
+            report.unknown.push(key.clone());
+            continue;
+        }
+
+        if let Err(problem) = validate_value(current_key, value) {
+            report.invalid.push((key.clone(), problem));
+        }
+    }
+
+    Ok(report)
+}
+
+/// Check that a string setting names one of the allowed choices
+fn validate_choice<T: clap::ValueEnum>(key: &str, value: &Value) -> Result<(), String> {
+    match value.as_str() {
+        Some(s) => parse_choice::<T>(key, s).map(|_| ()).map_err(|e| {
+            // Drop the key prefix, the report already names it
+            e.to_string().trim_start_matches(key).trim_start().to_string()
+        }),
+        None => Err("must be a string".to_string()),
+    }
+}
+
+/// Check that a list setting holds strings of the expected shape
+fn validate_list(value: &Value, valid: impl Fn(&str) -> bool, shape: &str) -> Result<(), String> {
+    let Some(items) = value.as_array() else {
+        return Err("must be a list of strings".to_string());
+    };
+    for item in items {
+        match item.as_str() {
+            Some(s) if valid(s) => {}
+            Some(s) => return Err(format!("`{}` doesn't look like {}", s, shape)),
+            None => return Err("must be a list of strings".to_string()),
+        }
+    }
+    Ok(())
+}
+
+/// Check that a known key holds a usable value
+fn validate_value(key: &str, value: &Value) -> Result<(), String> {
+    match key {
+        CONFIG_VERSION_CONFIG => match value.as_integer() {
+            Some(v) if (0..=i64::from(CURRENT_CONFIG_VERSION)).contains(&v) => Ok(()),
+            Some(v) => Err(format!("unsupported version {}", v)),
+            None => Err("must be a number".to_string()),
+        },
+        API_KEY_CONFIG | LLM_MODEL_CONFIG | LANGUAGE_CONFIG | SIGNING_KEY_CONFIG => match value.as_str() {
+            Some(s) if !s.trim().is_empty() => Ok(()),
+            Some(_) => Err("must not be empty".to_string()),
+            None => Err("must be a string".to_string()),
+        },
+        CANDIDATE_COUNT_CONFIG => match value.as_integer() {
+            Some(v) if v >= 1 && u32::try_from(v).is_ok() => Ok(()),
+            Some(_) => Err("must be at least 1".to_string()),
+            None => Err("must be a number".to_string()),
+        },
+        LLM_PROVIDER_CONFIG => match value.as_str() {
+            Some(s) if SUPPORTED_PROVIDERS.contains(&s) => Ok(()),
+            Some(s) => Err(format!(
+                "unsupported provider '{}' (supported: {})",
+                s,
+                SUPPORTED_PROVIDERS.join(", ")
+            )),
+            None => Err("must be a string".to_string()),
+        },
+        RUN_EDIT_CONFIG | DIFF_FUNCTION_CONTEXT_CONFIG | DIFF_IGNORE_WHITESPACE_CONFIG | STRUCTURAL_SUMMARY_CONFIG
+        | INFER_CONVENTION_CONFIG | BILINGUAL_CONFIG | SIGNOFF_CONFIG | SIGN_COMMITS_CONFIG
+        | DETECT_FIXUPS_CONFIG => {
+            match value.as_bool() {
+                Some(_) => Ok(()),
+                None => Err("must be true or false".to_string()),
+            }
+        }
+        REQUEST_TIMEOUT_CONFIG | CONNECT_TIMEOUT_CONFIG | FALLBACK_BUDGET_CONFIG => match value.as_integer() {
+            Some(v) if v > 0 => Ok(()),
+            Some(_) => Err("must be at least 1".to_string()),
+            None => Err("must be a number".to_string()),
+        },
+        DIFF_CONTEXT_CONFIG | MAX_RETRIES_CONFIG => match value.as_integer() {
+            Some(v) if u32::try_from(v).is_ok() => Ok(()),
+            Some(_) => Err("must not be negative".to_string()),
+            None => Err("must be a number".to_string()),
+        },
+        DIFF_ALGORITHM_CONFIG => validate_choice::<DiffAlgorithm>(key, value),
+        DIFF_RENAMES_CONFIG => validate_choice::<RenameDetection>(key, value),
+        SCOPE_MODE_CONFIG => validate_choice::<ScopeMode>(key, value),
+        EMOJI_CONFIG => validate_choice::<EmojiStyle>(key, value),
+        SIGNING_FORMAT_CONFIG => validate_choice::<SigningFormat>(key, value),
+        CO_AUTHORS_CONFIG => validate_list(value, is_identity, "`Name <email>`"),
+        HTTPS_PROXY_CONFIG => match value.as_str() {
+            Some(s) => parse_proxy(s).map(|_| ()).map_err(|err| err.to_string()),
+            None => Err("must be a string".to_string()),
+        },
+        NO_PROXY_CONFIG => validate_list(value, |host| !host.trim().is_empty(), "a host name"),
+        CA_BUNDLES_CONFIG => validate_list(value, |path| Path::new(path).is_file(), "the path of an existing file"),
+        CLIENT_CERT_CONFIG | CLIENT_KEY_CONFIG => match value.as_str() {
+            Some(s) if Path::new(s).is_file() => Ok(()),
+            Some(s) => Err(format!("no file at {}", s)),
+            None => Err("must be a string".to_string()),
+        },
+        FALLBACK_CONFIG => validate_list(
+            value,
+            |entry| FallbackEntry::parse(entry).is_some(),
+            "`provider` or `provider:model` with a supported provider",
+        ),
+        TRAILERS_CONFIG => validate_list(value, |trailer| parse_trailer(trailer).is_some(), "`Key: value`"),
+        _ => Ok(()),
+    }
+}
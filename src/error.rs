@@ -0,0 +1,117 @@
+use std::io;
+
+use crate::api::LlmError;
+use crate::commit::CommitFailed;
+
+/// Exit code for failures without a more specific code
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code when there is nothing staged to commit
+pub const EXIT_NO_STAGED_CHANGES: i32 = 3;
+/// Exit code when the LLM provider failed
+pub const EXIT_PROVIDER_ERROR: i32 = 4;
+/// Exit code when a prompt was needed but stdin is not a terminal
+pub const EXIT_NOT_INTERACTIVE: i32 = 5;
+/// Exit code when the user cancelled a prompt (same as SIGINT)
+pub const EXIT_ABORTED: i32 = 130;
+
+/// Error type returned by the application runner
+#[derive(Debug)]
+pub enum RunError {
+    Io(io::Error),
+    NoStagedChanges,
+    Provider(LlmError),
+    NotInteractive(String),
+    Aborted,
+    /// `git commit` itself failed; the process exits with git's code
+    Commit(CommitFailed),
+}
+
+impl RunError {
+    /// Process exit code for this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunError::Io(_) => EXIT_FAILURE,
+            RunError::NoStagedChanges => EXIT_NO_STAGED_CHANGES,
+            RunError::Provider(_) => EXIT_PROVIDER_ERROR,
+            RunError::NotInteractive(_) => EXIT_NOT_INTERACTIVE,
+            RunError::Aborted => EXIT_ABORTED,
+            RunError::Commit(failed) => failed.code.filter(|&code| code != 0).unwrap_or(EXIT_FAILURE),
+        }
+    }
+
+    /// Stable machine-readable code for this error
+    pub fn code(&self) -> &'static str {
+        match self {
+            RunError::Io(_) => "io_error",
+            RunError::NoStagedChanges => "no_staged_changes",
+            RunError::Provider(err) => err.code(),
+            RunError::NotInteractive(_) => "not_interactive",
+            RunError::Aborted => "aborted",
+            RunError::Commit(_) => "commit_failed",
+        }
+    }
+
+    /// What the user can do about this error, if there is something
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            RunError::Provider(err) => err.hint(),
+            _ => None,
+        }
+    }
+
+    /// Structured form of this error for `--format json`
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut error = serde_json::json!({
+            "code": self.code(),
+            "message": self.to_string(),
+            "exit_code": self.exit_code(),
+        });
+        if let Some(hint) = self.hint() {
+            error["hint"] = hint.into();
+        }
+        serde_json::json!({ "error": error })
+    }
+}
+
+impl From<io::Error> for RunError {
+    fn from(err: io::Error) -> Self {
+        match err.get_ref().and_then(|inner| inner.downcast_ref::<CommitFailed>()) {
+            Some(failed) => RunError::Commit(failed.clone()),
+            None => RunError::Io(err),
+        }
+    }
+}
+
+impl From<LlmError> for RunError {
+    fn from(err: LlmError) -> Self {
+        RunError::Provider(err)
+    }
+}
+
+impl From<inquire::InquireError> for RunError {
+    fn from(err: inquire::InquireError) -> Self {
+        use inquire::InquireError;
+        match err {
+            InquireError::OperationCanceled | InquireError::OperationInterrupted => RunError::Aborted,
+            InquireError::NotTTY => RunError::NotInteractive(
+                "stdin is not a terminal; use --yes, --pick <n> or --print".to_string(),
+            ),
+            other => RunError::Io(io::Error::other(other)),
+        }
+    }
+}
+
+impl std::fmt::Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunError::Io(err) => write!(f, "{}", err),
+            RunError::NoStagedChanges => write!(f, "No staged changes to commit."),
+            RunError::Provider(err) => write!(f, "{}", err),
+            RunError::NotInteractive(msg) => write!(f, "{}", msg),
+            RunError::Aborted => write!(f, "Aborted by user."),
+            RunError::Commit(failed) => write!(f, "{}", failed),
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
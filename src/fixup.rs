@@ -0,0 +1,89 @@
+//! Fixup commit targeting
+//!
+//! When the staged changes only touch lines written earlier on the current
+//! branch, blame tells which commit they belong to, and a `fixup! <subject>`
+//! message for `git rebase --autosquash` is offered next to the model's.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+
+use crate::diff::parse_diff;
+use crate::git;
+
+/// Subject prefixes `git rebase --autosquash` acts on
+const AUTOSQUASH_PREFIXES: &[&str] = &["fixup! ", "squash! ", "amend! "];
+
+/// An earlier commit on the branch that the changes fix
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FixupTarget {
+    pub commit: String,
+    pub subject: String,
+}
+
+impl FixupTarget {
+    /// The message `git commit --fixup=<commit>` would write
+    pub fn message(&self) -> String {
+        format!("fixup! {}", self.subject)
+    }
+}
+
+/// Whether `message` is a fixup, squash or amend commit for autosquash
+pub fn is_autosquash(message: &str) -> bool {
+    AUTOSQUASH_PREFIXES.iter().any(|prefix| message.starts_with(prefix))
+}
+
+/// The commit to fix up, if every line `diff` changes was last written by a commit
+/// on the current branch and one of those commits wrote most of them
+pub fn find_target(diff: &str) -> io::Result<Option<FixupTarget>> {
+    let branch = git::branch_commits()?;
+    if branch.is_empty() {
+        return Ok(None);
+    }
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for file in parse_diff(diff) {
+        // New files have no history to blame
+        let Some(path) = &file.old_path else {
+            continue;
+        };
+        let lines: Vec<u32> = file.hunks.iter().flat_map(|hunk| hunk.changed_old_lines()).collect();
+        for commit in git::blame_lines(path, &lines)? {
+            *counts.entry(commit).or_default() += 1;
+        }
+    }
+
+    let total: usize = counts.values().sum();
+    if total == 0 || counts.keys().any(|commit| !branch.contains(commit)) {
+        return Ok(None);
+    }
+    // Most lines first, then the newest commit
+    let position = |commit: &str| branch.iter().position(|c| c == commit).unwrap_or(usize::MAX);
+    let Some((commit, count)) = counts.into_iter().min_by_key(|(commit, count)| (usize::MAX - count, position(commit))) else {
+        return Ok(None);
+    };
+    if count * 2 <= total {
+        return Ok(None);
+    }
+
+    // Fixing a fixup still targets the original commit
+    let mut subject = git::commit_subject(&commit)?;
+    while let Some(rest) = AUTOSQUASH_PREFIXES.iter().find_map(|prefix| subject.strip_prefix(prefix)) {
+        subject = rest.to_string();
+    }
+    Ok(Some(FixupTarget { commit, subject }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixup_message() {
+        let target = FixupTarget { commit: "abc".to_string(), subject: "feat: add login".to_string() };
+        assert_eq!(target.message(), "fixup! feat: add login");
+        assert!(is_autosquash(&target.message()));
+        assert!(is_autosquash("squash! feat: add login"));
+        assert!(!is_autosquash("feat: fixup! handling"));
+    }
+}
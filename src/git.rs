@@ -1,19 +1,560 @@
-use std::io;
-use std::process::Command;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::diff::{image_info, BlobInfo};
+
+/// Summary of the size of a diff
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DiffStats {
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl DiffStats {
+    /// Count changed files and lines in a unified diff
+    pub fn from_diff(diff: &str) -> Self {
+        let mut stats = DiffStats::default();
+        // `---`/`+++` are file headers only before the first hunk of a file
+        let mut in_header = false;
+        for line in diff.lines() {
+            if line.starts_with("diff --git ") {
+                stats.files_changed += 1;
+                in_header = true;
+            } else if line.starts_with("@@") {
+                in_header = false;
+            } else if in_header {
+                continue;
+            } else if line.starts_with('+') {
+                stats.insertions += 1;
+            } else if line.starts_with('-') {
+                stats.deletions += 1;
+            }
+        }
+        stats
+    }
+}
+
+/// Context lines around each change, unless configured otherwise
+pub const DEFAULT_CONTEXT_LINES: u32 = 1;
+
+/// Diff algorithms git can use for the described diff
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffAlgorithm {
+    Myers,
+    Minimal,
+    Patience,
+    Histogram,
+}
+
+/// How renamed and copied files are shown in the described diff
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenameDetection {
+    /// Show renames as a deletion plus an addition
+    Off,
+    #[default]
+    Renames,
+    /// Also detect copies of modified files
+    Copies,
+}
+
+/// How the diff that is sent to the provider is produced
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffOptions {
+    pub context_lines: u32,
+    /// Show the whole enclosing function as context, like `git diff -W`
+    pub function_context: bool,
+    /// `None` uses git's own default (or `diff.algorithm`)
+    pub algorithm: Option<DiffAlgorithm>,
+    pub ignore_whitespace: bool,
+    pub renames: RenameDetection,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            context_lines: DEFAULT_CONTEXT_LINES,
+            function_context: false,
+            algorithm: None,
+            ignore_whitespace: false,
+            renames: RenameDetection::default(),
+        }
+    }
+}
+
+impl DiffOptions {
+    /// Arguments for `git diff --cached` producing this diff
+    fn args(&self) -> Vec<String> {
+        let mut args = vec![
+            "diff".to_string(),
+            "--cached".to_string(),
+            "--no-color".to_string(),
+            "--no-ext-diff".to_string(),
+            format!("--unified={}", self.context_lines),
+        ];
+        if self.function_context {
+            args.push("--function-context".to_string());
+        }
+        if let Some(algorithm) = self.algorithm {
+            args.push(format!("--diff-algorithm={}", value_name(algorithm)));
+        }
+        if self.ignore_whitespace {
+            args.push("--ignore-all-space".to_string());
+        }
+        args.push(
+            match self.renames {
+                RenameDetection::Off => "--no-renames",
+                RenameDetection::Renames => "--find-renames",
+                RenameDetection::Copies => "--find-copies",
+            }
+            .to_string(),
+        );
+        args
+    }
+}
+
+/// The name a `ValueEnum` variant is spelled with on the command line and in the config
+pub fn value_name<T: ValueEnum>(value: T) -> String {
+    value
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// Which changes to describe and commit, beyond what is already staged
+#[derive(Debug, Clone, Default)]
+pub struct ChangeScope {
+    /// Also include modified and deleted tracked files (`git commit -a`)
+    pub all: bool,
+    /// Also include untracked files that aren't ignored
+    pub include_untracked: bool,
+    /// Only commit these paths, like `git commit -- <paths>`
+    pub paths: Vec<String>,
+}
+
+impl ChangeScope {
+    /// Whether this is plain "commit what's staged"
+    pub fn is_staged_only(&self) -> bool {
+        !self.all && !self.include_untracked && self.paths.is_empty()
+    }
+}
+
+/// The changes committo described, and the index that holds exactly those changes
+#[derive(Debug)]
+pub struct ChangeSet {
+    pub diff: String,
+    /// `git diff --name-status` lines for the header of the picker
+    pub files: Vec<String>,
+    /// Temporary index to commit from, `None` for the regular index
+    pub index_file: Option<PathBuf>,
+    /// Pathspecs to sync back into the regular index after committing
+    paths: Vec<String>,
+}
+
+impl ChangeSet {
+    /// Whether there is nothing to commit (the diff alone can be empty when ignoring whitespace)
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Contents of `path` as it will be committed, `None` if it is deleted
+    pub fn staged_file(&self, path: &str) -> Option<String> {
+        run_git_in(self.index_file.as_deref(), &["show", &format!(":{path}")], "").ok()
+    }
+
+    /// Update the regular index after a commit made from the temporary index
+    pub fn finish_commit(&self) -> io::Result<()> {
+        let Some(index_file) = &self.index_file else {
+            return Ok(());
+        };
+        if self.paths.is_empty() {
+            // The temporary index started from the regular one, so it now matches HEAD exactly
+            fs::copy(index_file, git_path("index")?)?;
+        } else {
+            // Only the given paths were committed; make just those match the new HEAD
+            let mut args = vec!["reset", "-q", "--"];
+            args.extend(self.paths.iter().map(String::as_str));
+            run_git(&args)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ChangeSet {
+    fn drop(&mut self) {
+        if let Some(index_file) = &self.index_file {
+            let _ = fs::remove_file(index_file);
+        }
+    }
+}
+
+/// Stage the requested changes into a temporary index and describe them
+pub fn prepare_changes(scope: &ChangeScope, diff_options: &DiffOptions) -> io::Result<ChangeSet> {
+    if scope.is_staged_only() {
+        return Ok(ChangeSet {
+            diff: get_staged_diff(diff_options)?,
+            files: name_status(None)?,
+            index_file: None,
+            paths: Vec::new(),
+        });
+    }
+
+    let index_file = git_path(TEMP_INDEX_NAME)?;
+    let index = Some(index_file.as_path());
+    // Own the file from here on, so it is removed on every error path
+    let mut changes = ChangeSet {
+        diff: String::new(),
+        files: Vec::new(),
+        index_file: Some(index_file.clone()),
+        paths: scope.paths.clone(),
+    };
+
+    if scope.paths.is_empty() {
+        let real_index = git_path("index")?;
+        if real_index.exists() {
+            fs::copy(&real_index, &index_file)?;
+        } else {
+            run_git_in(index, &["read-tree", "--empty"], "")?;
+        }
+        if scope.all {
+            run_git_in(index, &["add", "-u"], "")?;
+        }
+        if scope.include_untracked {
+            add_untracked(index, &[])?;
+        }
+    } else {
+        // Like `git commit -- <paths>`: HEAD plus the current state of just these paths
+        let head = head_commit()?;
+        let tree = head.as_deref().unwrap_or("--empty");
+        run_git_in(index, &["read-tree", tree], "")?;
+
+        let mut args = vec!["add", "-u", "--"];
+        args.extend(scope.paths.iter().map(String::as_str));
+        run_git_in(index, &args, "")?;
+
+        // Files added to the regular index since HEAD are tracked too
+        let mut ls_args = vec!["ls-files", "--"];
+        ls_args.extend(scope.paths.iter().map(String::as_str));
+        let known: Vec<String> = run_git(&ls_args)?
+            .lines()
+            .filter(|path| Path::new(path).exists())
+            .map(str::to_string)
+            .collect();
+        if !known.is_empty() {
+            let mut add_args = vec!["add", "--"];
+            add_args.extend(known.iter().map(String::as_str));
+            run_git_in(index, &add_args, "")?;
+        }
+
+        if scope.include_untracked {
+            add_untracked(index, &scope.paths)?;
+        }
+    }
+
+    changes.diff = diff_in(index, diff_options)?;
+    changes.files = name_status(index)?;
+    Ok(changes)
+}
+
+/// Name of the temporary index inside the git directory
+const TEMP_INDEX_NAME: &str = "committo-index";
+
+/// Stage untracked, non-ignored files (optionally limited to `paths`) into `index`
+fn add_untracked(index: Option<&Path>, paths: &[String]) -> io::Result<()> {
+    let mut args = vec!["ls-files", "--others", "--exclude-standard", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    let untracked: Vec<String> = run_git(&args)?.lines().map(str::to_string).collect();
+    if untracked.is_empty() {
+        return Ok(());
+    }
+
+    let mut add_args = vec!["add", "--"];
+    add_args.extend(untracked.iter().map(String::as_str));
+    run_git_in(index, &add_args, "")?;
+    Ok(())
+}
+
+/// `git diff --cached --name-status` for the given index
+fn name_status(index: Option<&Path>) -> io::Result<Vec<String>> {
+    let output = run_git_in(index, &["diff", "--cached", "--name-status"], "")?;
+    Ok(output.lines().map(|line| line.replace('\t', "  ")).collect())
+}
+
+/// Absolute path of a file inside the git directory
+fn git_path(name: &str) -> io::Result<PathBuf> {
+    let path = run_git(&["rev-parse", "--git-path", name])?;
+    Ok(env::current_dir()?.join(path.trim()))
+}
 
 /// Get staged git diff
-pub fn get_staged_diff() -> io::Result<String> {
+pub fn get_staged_diff(options: &DiffOptions) -> io::Result<String> {
+    diff_in(None, options)
+}
+
+/// Diff of `index` against HEAD, produced according to `options`
+fn diff_in(index: Option<&Path>, options: &DiffOptions) -> io::Result<String> {
+    let args = options.args();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    run_git_in(index, &args, "")
+}
+
+/// Staged diff in a form `git apply` can replay: full context, binary-safe, no external tools
+pub fn get_staged_patch() -> io::Result<String> {
+    run_git(&["diff", "--staged", "--binary", "--no-color", "--no-ext-diff", "--unified=3"])
+}
+
+/// Current HEAD commit, or `None` on an unborn branch
+pub fn head_commit() -> io::Result<Option<String>> {
     let output = Command::new("git")
-        .arg("diff")
-        .arg("--staged")
-        .arg("--unified=1")
+        .args(["rev-parse", "--verify", "--quiet", "HEAD"])
         .output()?;
+    if output.status.success() {
+        Ok(Some(String::from_utf8_lossy(&output.stdout).trim().to_string()))
+    } else {
+        Ok(None)
+    }
+}
 
-    if !output.status.success() {
-        eprintln!("Error: Failed to execute git diff --staged");
-        eprintln!("Stderr: {}", String::from_utf8_lossy(&output.stderr));
-        std::process::exit(1);
+/// Messages of the last `limit` non-merge commits, newest first
+pub fn recent_messages(limit: usize) -> io::Result<Vec<String>> {
+    if head_commit()?.is_none() {
+        return Ok(Vec::new());
+    }
+    let log = run_git(&["log", "--no-merges", &format!("--max-count={limit}"), "--format=%B%x00"])?;
+    Ok(log
+        .split('\0')
+        .map(str::trim)
+        .filter(|message| !message.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// `Name <email>` of the committer, as `git commit --signoff` would use it
+pub fn committer_identity() -> io::Result<String> {
+    let ident = run_git(&["var", "GIT_COMMITTER_IDENT"])?;
+    // The identity is followed by a timestamp and a time zone
+    let ident = ident.trim();
+    let end = ident.rfind('>').map_or(ident.len(), |i| i + 1);
+    Ok(ident[..end].to_string())
+}
+
+/// `Name <email>` of the authors of the last `limit` commits, most recent first, without repeats
+pub fn recent_authors(limit: usize) -> io::Result<Vec<String>> {
+    if head_commit()?.is_none() {
+        return Ok(Vec::new());
     }
+    let log = run_git(&["log", &format!("--max-count={limit}"), "--format=%aN <%aE>"])?;
+    let mut authors: Vec<String> = Vec::new();
+    for author in log.lines().map(str::trim).filter(|author| !author.is_empty()) {
+        if !authors.iter().any(|known| known == author) {
+            authors.push(author.to_string());
+        }
+    }
+    Ok(authors)
+}
+
+/// `message` with `trailers` added by `git interpret-trailers`, skipping exact duplicates
+pub fn interpret_trailers(message: &str, trailers: &[(String, String)]) -> io::Result<String> {
+    let mut args = vec!["interpret-trailers".to_string(), "--if-exists".to_string(), "addIfDifferent".to_string()];
+    for (key, value) in trailers {
+        args.push("--trailer".to_string());
+        args.push(format!("{}: {}", key, value));
+    }
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = run_git_with_input(&args, &format!("{}\n", message.trim_end()))?;
+    Ok(output.trim_end().to_string())
+}
+
+/// Refs the current branch may have started from, in order of preference
+const BRANCH_BASES: &[&str] = &["@{upstream}", "origin/HEAD", "main", "master"];
+
+/// Commits on the current branch that aren't on its upstream (or, without one,
+/// on the default branch), newest first. Empty when there is nothing to compare with.
+pub fn branch_commits() -> io::Result<Vec<String>> {
+    if head_commit()?.is_none() {
+        return Ok(Vec::new());
+    }
+    let Some(base) = BRANCH_BASES.iter().find(|base| run_git(&["rev-parse", "--verify", "--quiet", base]).is_ok()) else {
+        return Ok(Vec::new());
+    };
+    let commits = run_git(&["rev-list", "HEAD", &format!("^{}", base)])?;
+    Ok(commits.lines().map(str::to_string).collect())
+}
+
+/// The commit that last changed each of `lines` of `path` in HEAD, once per line
+pub fn blame_lines(path: &str, lines: &[u32]) -> io::Result<Vec<String>> {
+    if lines.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut args = vec!["blame".to_string(), "--porcelain".to_string()];
+    // One range per run of consecutive lines
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for &line in lines {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == line => *end = line,
+            _ => ranges.push((line, line)),
+        }
+    }
+    for (start, end) in ranges {
+        args.push(format!("-L{},{}", start, end));
+    }
+    args.extend(["HEAD".to_string(), "--".to_string(), path.to_string()]);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let blame = run_git(&args)?;
+
+    // Every blamed line starts with `<commit> <old line> <new line>[ <group size>]`
+    Ok(blame
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+            let commit = fields.next()?;
+            let is_header = commit.len() >= 40
+                && commit.chars().all(|c| c.is_ascii_hexdigit())
+                && fields.all(|field| field.parse::<u32>().is_ok());
+            is_header.then(|| commit.to_string())
+        })
+        .collect())
+}
+
+/// First line of the message of `commit`
+pub fn commit_subject(commit: &str) -> io::Result<String> {
+    run_git(&["log", "-1", "--format=%s", commit]).map(|subject| subject.trim().to_string())
+}
+
+/// Name of the checked-out branch, `None` when HEAD is detached
+pub fn current_branch() -> Option<String> {
+    let branch = run_git(&["branch", "--show-current"]).ok()?;
+    Some(branch.trim().to_string()).filter(|branch| !branch.is_empty())
+}
+
+/// Top-level directory of the working tree
+pub fn repo_root() -> io::Result<PathBuf> {
+    run_git(&["rev-parse", "--show-toplevel"]).map(|root| PathBuf::from(root.trim()))
+}
+
+/// Contents of `path` at HEAD, `None` if it doesn't exist there
+pub fn head_file(path: &str) -> Option<String> {
+    run_git(&["show", &format!("HEAD:{path}")]).ok()
+}
 
+/// Blobs larger than this are measured but not read
+const MAX_INSPECTED_BLOB_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Size of a blob, plus image format and dimensions when it is a small enough image
+pub fn blob_info(id: &str) -> Option<BlobInfo> {
+    let size = run_git(&["cat-file", "-s", id]).ok()?.trim().parse().ok()?;
+    let image = if size <= MAX_INSPECTED_BLOB_BYTES {
+        let output = Command::new("git").args(["cat-file", "blob", id]).output().ok()?;
+        image_info(&output.stdout)
+    } else {
+        None
+    };
+    Some(BlobInfo { size, image })
+}
+
+/// Write the index to a tree object, capturing exactly what is staged
+pub fn write_index_tree() -> io::Result<String> {
+    run_git(&["write-tree"]).map(|tree| tree.trim().to_string())
+}
+
+/// Replace the index with `tree` (or empty it for `None`), leaving the working tree alone
+pub fn read_index_tree(tree: Option<&str>) -> io::Result<()> {
+    match tree {
+        Some(tree) => run_git(&["read-tree", tree])?,
+        None => run_git(&["read-tree", "--empty"])?,
+    };
+    // read-tree drops stat info; refresh so unchanged files don't show as modified
+    let _ = Command::new("git").args(["update-index", "-q", "--refresh"]).output();
+    Ok(())
+}
+
+/// Stage a patch without touching the working tree
+pub fn apply_to_index(patch: &str) -> io::Result<()> {
+    run_git_with_input(&["apply", "--cached", "--whitespace=nowarn", "-"], patch).map(|_| ())
+}
+
+/// Move the current branch back to `commit` (or unborn for `None`) without touching index or files
+pub fn reset_head(commit: Option<&str>) -> io::Result<()> {
+    match commit {
+        Some(commit) => run_git(&["reset", "--soft", commit])?,
+        None => run_git(&["update-ref", "-d", "HEAD"])?,
+    };
+    Ok(())
+}
+
+/// Run a git command and return its stdout
+fn run_git(args: &[&str]) -> io::Result<String> {
+    run_git_in(None, args, "")
+}
+
+/// Run a git command with `input` on stdin and return its stdout
+fn run_git_with_input(args: &[&str], input: &str) -> io::Result<String> {
+    run_git_in(None, args, input)
+}
+
+/// Run a git command against `index` (the regular index for `None`) and return its stdout
+fn run_git_in(index: Option<&Path>, args: &[&str], input: &str) -> io::Result<String> {
+    let mut command = Command::new("git");
+    if let Some(index) = index {
+        command.env("GIT_INDEX_FILE", index);
+    }
+    let mut child = command
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_options_args() {
+        assert_eq!(
+            DiffOptions::default().args(),
+            vec!["diff", "--cached", "--no-color", "--no-ext-diff", "--unified=1", "--find-renames"]
+        );
+
+        let options = DiffOptions {
+            context_lines: 5,
+            function_context: true,
+            algorithm: Some(DiffAlgorithm::Histogram),
+            ignore_whitespace: true,
+            renames: RenameDetection::Copies,
+        };
+        let args = options.args();
+        assert!(args.contains(&"--unified=5".to_string()));
+        assert!(args.contains(&"--function-context".to_string()));
+        assert!(args.contains(&"--diff-algorithm=histogram".to_string()));
+        assert!(args.contains(&"--ignore-all-space".to_string()));
+        assert!(args.contains(&"--find-copies".to_string()));
+    }
+
+    #[test]
+    fn test_diff_stats() {
+        let diff = "diff --git a/a.txt b/a.txt\n--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n-old\n+new\n+more\n context\ndiff --git a/b.txt b/b.txt\n--- a/b.txt\n+++ b/b.txt\n@@ -1 +0,0 @@\n-gone\n";
+        let stats = DiffStats::from_diff(diff);
+        assert_eq!(stats, DiffStats { files_changed: 2, insertions: 2, deletions: 2 });
+    }
+}
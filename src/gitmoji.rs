@@ -0,0 +1,164 @@
+//! Gitmoji prefixes for commit subjects
+
+use crate::convention::{parse_header, EmojiStyle};
+
+/// Conventional Commits type, emoji and shortcode, from the gitmoji list
+const GITMOJI: &[(&str, &str, &str)] = &[
+    ("feat", "✨", ":sparkles:"),
+    ("fix", "🐛", ":bug:"),
+    ("docs", "📝", ":memo:"),
+    ("style", "🎨", ":art:"),
+    ("refactor", "♻️", ":recycle:"),
+    ("perf", "⚡️", ":zap:"),
+    ("test", "✅", ":white_check_mark:"),
+    ("build", "📦️", ":package:"),
+    ("ci", "👷", ":construction_worker:"),
+    ("chore", "🔧", ":wrench:"),
+    ("revert", "⏪️", ":rewind:"),
+    ("security", "🔒️", ":lock:"),
+    ("deps", "⬆️", ":arrow_up:"),
+    ("i18n", "🌐", ":globe_with_meridians:"),
+    ("release", "🔖", ":bookmark:"),
+    ("hotfix", "🚑️", ":ambulance:"),
+    ("init", "🎉", ":tada:"),
+    ("wip", "🚧", ":construction:"),
+];
+
+/// A leading emoji or `:shortcode:` and the rest of the subject after it
+pub fn split_emoji(subject: &str) -> Option<(&str, &str)> {
+    let end = if let Some(rest) = subject.strip_prefix(':') {
+        let name_end = rest.find(':')?;
+        let name = &rest[..name_end];
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_+-".contains(c)) {
+            return None;
+        }
+        name_end + 2
+    } else {
+        subject.find(|c: char| c.is_ascii() || c.is_alphanumeric()).unwrap_or(subject.len())
+    };
+    if end == 0 {
+        return None;
+    }
+    Some((&subject[..end], subject[end..].trim_start()))
+}
+
+/// Whether `emoji` is written as a `:shortcode:`
+pub fn is_shortcode(emoji: &str) -> bool {
+    emoji.starts_with(':')
+}
+
+/// The table entry for an emoji in either form; variation selectors are ignored
+fn lookup(emoji: &str) -> Option<&'static (&'static str, &'static str, &'static str)> {
+    let plain = |s: &str| s.replace('\u{FE0F}', "");
+    GITMOJI.iter().find(|(_, unicode, shortcode)| *shortcode == emoji || plain(unicode) == plain(emoji))
+}
+
+/// The gitmoji for a Conventional Commits type, in `style`
+pub fn for_type(kind: &str, style: EmojiStyle) -> Option<&'static str> {
+    let (_, unicode, shortcode) = GITMOJI.iter().find(|(t, _, _)| *t == kind)?;
+    match style {
+        EmojiStyle::None => None,
+        EmojiStyle::Unicode => Some(unicode),
+        EmojiStyle::Shortcode => Some(shortcode),
+    }
+}
+
+/// Give the subject of `message` the emoji prefix `style` asks for. The emoji follows the
+/// Conventional Commits type when there is one; otherwise the model's own emoji is kept,
+/// converted to the right form where it is a known gitmoji.
+pub fn apply(message: &str, style: EmojiStyle) -> String {
+    let (subject, rest) = match message.split_once('\n') {
+        Some((subject, rest)) => (subject, Some(rest)),
+        None => (message, None),
+    };
+    let (emoji, text) = match split_emoji(subject) {
+        Some((emoji, text)) => (Some(emoji), text),
+        None => (None, subject),
+    };
+
+    let prefix = match style {
+        EmojiStyle::None => None,
+        _ => parse_header(text)
+            .and_then(|header| for_type(header.kind, style))
+            .or_else(|| {
+                let emoji = emoji?;
+                match lookup(emoji) {
+                    Some(&(kind, _, _)) => for_type(kind, style),
+                    None => Some(emoji),
+                }
+            }),
+    };
+
+    let mut out = match prefix {
+        Some(prefix) => format!("{} {}", prefix, text),
+        None => text.to_string(),
+    };
+    if let Some(rest) = rest {
+        out.push('\n');
+        out.push_str(rest);
+    }
+    out
+}
+
+/// Problems with the emoji prefix of `subject` under `style`
+pub fn problems(subject: &str, style: EmojiStyle) -> Vec<String> {
+    let emoji = split_emoji(subject);
+    let problem = match (style, emoji) {
+        (EmojiStyle::None, Some((emoji, _))) => Some(format!("subject starts with `{}`, but emoji are off", emoji)),
+        (EmojiStyle::None, None) => None,
+        (_, None) => Some("subject doesn't start with a gitmoji".to_string()),
+        (EmojiStyle::Unicode, Some((emoji, _))) if is_shortcode(emoji) => {
+            Some(format!("`{}` should be written as the emoji character", emoji))
+        }
+        (EmojiStyle::Shortcode, Some((emoji, _))) if !is_shortcode(emoji) => {
+            Some(format!("`{}` should be written as a :shortcode:", emoji))
+        }
+        (_, Some((emoji, text))) => parse_header(text).and_then(|header| {
+            let expected = for_type(header.kind, style)?;
+            let matches = lookup(emoji).is_some_and(|&(kind, _, _)| kind == header.kind);
+            (!matches).then(|| format!("`{}` doesn't match type `{}`, expected `{}`", emoji, header.kind, expected))
+        }),
+    };
+    problem.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_emoji() {
+        assert_eq!(split_emoji("✨ feat: add"), Some(("✨", "feat: add")));
+        assert_eq!(split_emoji("♻️refactor: tidy"), Some(("♻️", "refactor: tidy")));
+        assert_eq!(split_emoji(":bug: fix: crash"), Some((":bug:", "fix: crash")));
+        assert_eq!(split_emoji("feat: add"), None);
+        assert_eq!(split_emoji("로그인 추가"), None);
+        assert_eq!(split_emoji("Note: x"), None);
+    }
+
+    #[test]
+    fn test_apply_follows_the_type() {
+        assert_eq!(apply("feat: add login", EmojiStyle::Unicode), "✨ feat: add login");
+        assert_eq!(apply("🐛 feat: add login", EmojiStyle::Unicode), "✨ feat: add login");
+        assert_eq!(apply(":sparkles: feat: add login\n\nbody", EmojiStyle::Shortcode), ":sparkles: feat: add login\n\nbody");
+        assert_eq!(apply("✨ feat: add login", EmojiStyle::Shortcode), ":sparkles: feat: add login");
+        assert_eq!(apply("✨ feat: add login", EmojiStyle::None), "feat: add login");
+    }
+
+    #[test]
+    fn test_apply_without_a_type() {
+        assert_eq!(apply(":bug: Fix the crash", EmojiStyle::Unicode), "🐛 Fix the crash");
+        assert_eq!(apply("🦀 Port to Rust", EmojiStyle::Shortcode), "🦀 Port to Rust");
+        assert_eq!(apply("Fix the crash", EmojiStyle::Unicode), "Fix the crash");
+    }
+
+    #[test]
+    fn test_problems() {
+        assert!(problems("✨ feat: add", EmojiStyle::Unicode).is_empty());
+        assert!(problems("feat: add", EmojiStyle::None).is_empty());
+        assert_eq!(problems("feat: add", EmojiStyle::Unicode), vec!["subject doesn't start with a gitmoji"]);
+        assert_eq!(problems(":sparkles: feat: add", EmojiStyle::Unicode), vec!["`:sparkles:` should be written as the emoji character"]);
+        assert_eq!(problems("🐛 feat: add", EmojiStyle::Unicode), vec!["`🐛` doesn't match type `feat`, expected `✨`"]);
+        assert_eq!(problems("✨ feat: add", EmojiStyle::None), vec!["subject starts with `✨`, but emoji are off"]);
+    }
+}
@@ -0,0 +1,417 @@
+//! Conventions inferred from existing commit messages
+
+use std::collections::BTreeMap;
+
+use crate::convention::{parse_header, ConventionSettings, EmojiStyle};
+use crate::gitmoji::{is_shortcode, split_emoji};
+
+/// Fewer commits than this say too little about a project's habits
+pub const MIN_SAMPLE_SIZE: usize = 5;
+
+/// How many recent commits are analysed by default
+pub const HISTORY_LIMIT: usize = 200;
+
+/// Share of commits that must follow a habit before it becomes a rule
+const MAJORITY: f64 = 0.6;
+
+/// Counts over a sample of commit messages
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryStats {
+    /// Number of commits looked at
+    pub sample_size: usize,
+    /// Subjects in `type(scope): description` form
+    pub conventional: usize,
+    /// Types by number of uses, most used first
+    pub types: Vec<(String, usize)>,
+    /// Conventional subjects with a scope
+    pub scoped: usize,
+    pub scopes: Vec<(String, usize)>,
+    pub average_subject_length: usize,
+    /// Subjects of at most 50 and at most 72 characters
+    pub within_50: usize,
+    pub within_72: usize,
+    /// Subjects whose first word could be judged, and those in the imperative mood
+    pub mood_checked: usize,
+    pub imperative: usize,
+    /// Descriptions starting with a lowercase and with an uppercase letter
+    pub lowercase: usize,
+    pub uppercase: usize,
+    /// Subjects containing Hangul
+    pub korean: usize,
+    pub unicode_emoji: usize,
+    pub shortcode_emoji: usize,
+    /// Ticket references at the start of the subject, at its end, and in the body
+    pub tickets_start: usize,
+    pub tickets_end: usize,
+    pub tickets_body: usize,
+    /// First ticket reference seen, to show the format
+    pub ticket_example: Option<String>,
+    /// Trailers by number of messages carrying them
+    pub trailers: Vec<(String, usize)>,
+}
+
+/// What the commit history says about how messages are written
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InferredConvention {
+    /// Number of commits looked at
+    pub sample_size: usize,
+    /// Habits that map onto convention settings
+    pub settings: ConventionSettings,
+    /// Other habits, as instructions for the model
+    pub guidance: Vec<String>,
+}
+
+/// Count the habits in `messages`
+pub fn analyze(messages: &[String]) -> HistoryStats {
+    let mut stats = HistoryStats { sample_size: messages.len(), ..Default::default() };
+    let mut types: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut scopes: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut trailers: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut total_length = 0;
+
+    for message in messages {
+        let subject = message.lines().next().unwrap_or_default().trim();
+        let length = subject.chars().count();
+        total_length += length;
+        stats.within_50 += usize::from(length <= 50);
+        stats.within_72 += usize::from(length <= 72);
+        stats.korean += usize::from(subject.chars().any(is_hangul));
+
+        let mut description = subject;
+        if let Some((emoji, rest)) = split_emoji(subject) {
+            if is_shortcode(emoji) {
+                stats.shortcode_emoji += 1;
+            } else {
+                stats.unicode_emoji += 1;
+            }
+            description = rest;
+        }
+        if let Some(header) = parse_header(description) {
+            stats.conventional += 1;
+            *types.entry(header.kind).or_default() += 1;
+            if let Some(scope) = header.scope {
+                stats.scoped += 1;
+                *scopes.entry(scope).or_default() += 1;
+            }
+            description = header.description;
+        }
+
+        // Tickets
+        let tokens: Vec<&str> = description.split_whitespace().collect();
+        let at_start = tokens.first().is_some_and(|token| is_ticket(token));
+        let at_end = !at_start && tokens.len() > 1 && tokens.last().is_some_and(|token| is_ticket(token));
+        let body_ticket = message.lines().skip(1).flat_map(str::split_whitespace).find(|token| is_ticket(token));
+        stats.tickets_start += usize::from(at_start);
+        stats.tickets_end += usize::from(at_end);
+        stats.tickets_body += usize::from(!at_start && !at_end && body_ticket.is_some());
+        if stats.ticket_example.is_none() {
+            let subject_ticket = if at_start { tokens.first() } else if at_end { tokens.last() } else { None };
+            stats.ticket_example = subject_ticket.copied().or(body_ticket).map(str::to_string);
+        }
+        if at_start {
+            description = description.split_once(char::is_whitespace).map_or("", |(_, rest)| rest.trim_start());
+        }
+
+        // Casing and mood of the description itself
+        if let Some(first) = description.chars().next() {
+            stats.lowercase += usize::from(first.is_lowercase());
+            stats.uppercase += usize::from(first.is_uppercase());
+        }
+        if let Some(word) = description.split_whitespace().next()
+            && word.chars().all(|c| c.is_ascii_alphabetic())
+        {
+            stats.mood_checked += 1;
+            stats.imperative += usize::from(is_imperative(&word.to_ascii_lowercase()));
+        }
+
+        for trailer in message_trailers(message) {
+            *trailers.entry(trailer).or_default() += 1;
+        }
+    }
+
+    if !messages.is_empty() {
+        stats.average_subject_length = total_length / messages.len();
+    }
+    stats.types = by_frequency(types);
+    stats.scopes = by_frequency(scopes);
+    stats.trailers = by_frequency(trailers);
+    stats
+}
+
+impl HistoryStats {
+    /// Whether `count` out of `total` is enough to call it a habit
+    fn most(count: usize, total: usize) -> bool {
+        total > 0 && count as f64 >= total as f64 * MAJORITY
+    }
+
+    /// The habits most commits share, or `None` if there are too few commits or no habit stands out
+    pub fn infer(&self) -> Option<InferredConvention> {
+        let total = self.sample_size;
+        if total < MIN_SAMPLE_SIZE {
+            return None;
+        }
+        let mut settings = ConventionSettings::default();
+        let mut guidance = Vec::new();
+
+        if Self::most(self.unicode_emoji, total) {
+            settings.emoji = Some(EmojiStyle::Unicode);
+        } else if Self::most(self.shortcode_emoji, total) {
+            settings.emoji = Some(EmojiStyle::Shortcode);
+        }
+        if Self::most(self.conventional, total) {
+            settings.types = Some(self.types.iter().map(|(kind, _)| kind.clone()).collect());
+            let scopes: Vec<String> = self.scopes.iter().filter(|(_, count)| *count >= 2).map(|(scope, _)| scope.clone()).collect();
+            if !scopes.is_empty() {
+                settings.scopes = Some(scopes);
+            }
+        }
+        if Self::most(self.korean, total) {
+            settings.language = Some("Korean".to_string());
+        }
+        settings.max_subject_length = if self.within_50 as f64 >= total as f64 * 0.9 {
+            Some(50)
+        } else if self.within_72 as f64 >= total as f64 * 0.9 {
+            Some(72)
+        } else {
+            None
+        };
+        let trailers: Vec<String> =
+            self.trailers.iter().filter(|(_, count)| Self::most(*count, total)).map(|(name, _)| name.clone()).collect();
+        if !trailers.is_empty() {
+            settings.trailers = Some(trailers);
+        }
+
+        if Self::most(self.lowercase, total) {
+            guidance.push("Start the description with a lowercase letter".to_string());
+        } else if Self::most(self.uppercase, total) {
+            guidance.push("Start the description with a capital letter".to_string());
+        }
+        if self.mood_checked >= MIN_SAMPLE_SIZE && Self::most(self.imperative, self.mood_checked) {
+            guidance.push("Use the imperative mood (\"add\", not \"added\" or \"adds\")".to_string());
+        }
+        let example = self.ticket_example.as_deref().unwrap_or("PROJ-123");
+        if Self::most(self.tickets_start, total) {
+            guidance.push(format!("Put the ticket reference at the start of the description, like `{}`", example));
+        } else if Self::most(self.tickets_end, total) {
+            guidance.push(format!("Put the ticket reference at the end of the subject, like `{}`", example));
+        } else if Self::most(self.tickets_body, total) {
+            guidance.push(format!("Mention the ticket reference in the body, like `{}`", example));
+        }
+
+        if settings == ConventionSettings::default() && guidance.is_empty() {
+            return None;
+        }
+        Some(InferredConvention { sample_size: total, settings, guidance })
+    }
+
+    /// Human-readable statistics
+    pub fn report(&self) -> String {
+        let total = self.sample_size;
+        let share = |count: usize, of: usize| (count * 100).checked_div(of).unwrap_or(0);
+        let list = |items: &[(String, usize)]| {
+            items.iter().map(|(name, count)| format!("{} {}", name, count)).collect::<Vec<_>>().join(", ")
+        };
+
+        let mut out = format!("Analyzed the last {} commits:\n", total);
+        out.push_str(&format!("  Conventional Commits: {} ({}%)\n", self.conventional, share(self.conventional, total)));
+        if !self.types.is_empty() {
+            out.push_str(&format!("    types: {}\n", list(&self.types)));
+        }
+        if !self.scopes.is_empty() {
+            out.push_str(&format!("    scoped: {} ({}%): {}\n", self.scoped, share(self.scoped, self.conventional), list(&self.scopes)));
+        }
+        out.push_str(&format!(
+            "  Subject length: {} characters on average, {}% within 50, {}% within 72\n",
+            self.average_subject_length,
+            share(self.within_50, total),
+            share(self.within_72, total)
+        ));
+        out.push_str(&format!("  Imperative mood: {} of {} ({}%)\n", self.imperative, self.mood_checked, share(self.imperative, self.mood_checked)));
+        out.push_str(&format!("  Description starts lowercase: {}, uppercase: {}\n", self.lowercase, self.uppercase));
+        out.push_str(&format!("  Korean: {} ({}%)\n", self.korean, share(self.korean, total)));
+        out.push_str(&format!("  Gitmoji: {} unicode, {} shortcode\n", self.unicode_emoji, self.shortcode_emoji));
+        out.push_str(&format!(
+            "  Tickets: {} at the start, {} at the end, {} in the body{}\n",
+            self.tickets_start,
+            self.tickets_end,
+            self.tickets_body,
+            self.ticket_example.as_ref().map(|example| format!(" (e.g. {})", example)).unwrap_or_default()
+        ));
+        if !self.trailers.is_empty() {
+            out.push_str(&format!("  Trailers: {}\n", list(&self.trailers)));
+        }
+        out
+    }
+}
+
+impl InferredConvention {
+    /// One line per inferred habit, for reports
+    pub fn summary(&self) -> Vec<String> {
+        let settings = &self.settings;
+        let mut lines = Vec::new();
+        if let Some(types) = &settings.types {
+            lines.push(format!("Conventional Commits with types: {}", types.join(", ")));
+        }
+        if let Some(scopes) = &settings.scopes {
+            lines.push(format!("scopes: {}", scopes.join(", ")));
+        }
+        if let Some(language) = &settings.language {
+            lines.push(format!("written in {}", language));
+        }
+        if let Some(max) = settings.max_subject_length {
+            lines.push(format!("subjects of at most {} characters", max));
+        }
+        match settings.emoji {
+            Some(EmojiStyle::Unicode) => lines.push("subjects start with a gitmoji".to_string()),
+            Some(EmojiStyle::Shortcode) => lines.push("subjects start with a gitmoji shortcode".to_string()),
+            _ => {}
+        }
+        for trailer in settings.trailers.iter().flatten() {
+            lines.push(format!("`{}` trailer", trailer));
+        }
+        lines.extend(self.guidance.iter().cloned());
+        lines
+    }
+
+    /// Contents of a convention file holding these settings, with the other habits as its text
+    pub fn to_file(&self) -> String {
+        let frontmatter = toml::to_string(&self.settings).unwrap_or_default();
+        let body: String = self.guidance.iter().map(|line| format!("{}.\n", line)).collect();
+        format!(
+            "+++\n# Inferred from the last {} commits\n{}+++\n{}",
+            self.sample_size, frontmatter, body
+        )
+    }
+}
+
+/// Infer the habits shared by most of `messages`
+pub fn infer(messages: &[String]) -> Option<InferredConvention> {
+    analyze(messages).infer()
+}
+
+/// Keys by count, most frequent first
+fn by_frequency(counts: BTreeMap<&str, usize>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    counts.into_iter().map(|(key, count)| (key.to_string(), count)).collect()
+}
+
+/// `#123` or `PROJ-123`, optionally in brackets or followed by punctuation
+fn is_ticket(token: &str) -> bool {
+    let token = token.trim_matches(|c: char| "[]()<>:,;.".contains(c));
+    if let Some(number) = token.strip_prefix('#') {
+        return !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+    }
+    match token.split_once('-') {
+        Some((project, number)) => {
+            project.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+                && project.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+                && !number.is_empty()
+                && number.chars().all(|c| c.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+/// Rough check that an English word is a bare verb rather than "added", "adding" or "adds"
+fn is_imperative(word: &str) -> bool {
+    let third_person = word.ends_with('s') && !["ss", "us", "is"].iter().any(|suffix| word.ends_with(suffix));
+    !(word.ends_with("ed") || word.ends_with("ing") || third_person)
+}
+
+/// `Key: value` lines in the last paragraph of a message with a body
+fn message_trailers(message: &str) -> Vec<&str> {
+    let Some((_, rest)) = message.trim_end().split_once("\n\n") else {
+        return Vec::new();
+    };
+    let last = rest.rsplit("\n\n").next().unwrap_or_default();
+    last.lines()
+        .filter_map(|line| line.split_once(": ").map(|(key, _)| key))
+        .filter(|key| key.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        .collect()
+}
+
+fn is_hangul(c: char) -> bool {
+    ('\u{AC00}'..='\u{D7A3}').contains(&c) || ('\u{1100}'..='\u{11FF}').contains(&c) || ('\u{3130}'..='\u{318F}').contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(subjects: &[&str]) -> Vec<String> {
+        subjects.iter().map(|subject| subject.to_string()).collect()
+    }
+
+    #[test]
+    fn test_infers_conventional_commits() {
+        let inferred = infer(&messages(&[
+            "feat(api): add login endpoint",
+            "fix(api): handle empty tokens",
+            "fix: typo in README",
+            "docs: describe the setup",
+            "feat(web): show the login form",
+            "Merge branch 'main'",
+        ]))
+        .unwrap();
+        assert_eq!(inferred.sample_size, 6);
+        assert_eq!(inferred.settings.types, Some(vec!["feat".into(), "fix".into(), "docs".into()]));
+        assert_eq!(inferred.settings.scopes, Some(vec!["api".into()]));
+        assert_eq!(inferred.settings.max_subject_length, Some(50));
+        assert_eq!(inferred.settings.language, None);
+        assert_eq!(
+            inferred.guidance,
+            vec!["Start the description with a lowercase letter", "Use the imperative mood (\"add\", not \"added\" or \"adds\")"]
+        );
+        let file = inferred.to_file();
+        assert!(file.starts_with("+++\n# Inferred from the last 6 commits\n"), "{}", file);
+        assert!(file.contains("types = [\"feat\", \"fix\", \"docs\"]"));
+        assert!(file.ends_with("+++\nStart the description with a lowercase letter.\nUse the imperative mood (\"add\", not \"added\" or \"adds\").\n"));
+    }
+
+    #[test]
+    fn test_infers_gitmoji_and_korean() {
+        let inferred = infer(&messages(&[
+            "✨ feat: 로그인 화면 추가",
+            "🐛 fix: 토큰 검증 수정",
+            "📝 docs: 설치 방법 정리",
+            "✨ feat: 회원가입 추가",
+            "♻️ refactor: 설정 로딩 정리",
+        ]))
+        .unwrap();
+        assert_eq!(inferred.settings.emoji, Some(EmojiStyle::Unicode));
+        assert_eq!(inferred.settings.language.as_deref(), Some("Korean"));
+        assert_eq!(inferred.settings.types.as_ref().unwrap()[0], "feat");
+    }
+
+    #[test]
+    fn test_tickets_mood_and_trailers() {
+        let stats = analyze(&messages(&[
+            "[PROJ-1] Added caching\n\nSigned-off-by: A <a@b.c>",
+            "[PROJ-2] Fixed the login\n\nSome context.\n\nSigned-off-by: A <a@b.c>",
+            "[PROJ-3] Updates docs\n\nSigned-off-by: A <a@b.c>",
+            "[PROJ-4] Removed dead code\n\nSigned-off-by: A <a@b.c>",
+            "Bump version (#12)",
+        ]));
+        assert_eq!((stats.tickets_start, stats.tickets_end, stats.tickets_body), (4, 1, 0));
+        assert_eq!(stats.ticket_example.as_deref(), Some("[PROJ-1]"));
+        assert_eq!((stats.imperative, stats.mood_checked), (1, 5));
+        assert_eq!((stats.lowercase, stats.uppercase), (0, 5));
+        assert_eq!(stats.trailers, vec![("Signed-off-by".to_string(), 4)]);
+
+        let inferred = stats.infer().unwrap();
+        assert_eq!(inferred.settings.trailers, Some(vec!["Signed-off-by".to_string()]));
+        assert_eq!(
+            inferred.guidance,
+            vec!["Start the description with a capital letter", "Put the ticket reference at the start of the description, like `[PROJ-1]`"]
+        );
+        assert!(stats.report().contains("  Tickets: 4 at the start, 1 at the end, 0 in the body (e.g. [PROJ-1])\n"));
+    }
+
+    #[test]
+    fn test_too_few_or_unremarkable_messages() {
+        assert_eq!(infer(&messages(&["feat: a", "fix: b"])), None);
+        let long = format!("# {}", "x".repeat(100));
+        assert_eq!(infer(&messages(&[&long, &long, &long, &long, &long])), None);
+    }
+}
@@ -0,0 +1,152 @@
+//! Checking which language a commit message is written in
+//!
+//! Languages are told apart by script only: Korean by Hangul, Russian by
+//! Cyrillic and so on. English and other Latin-script languages are not
+//! distinguished from each other.
+
+use crate::convention::parse_header;
+
+/// Share of letters that must be in the expected script for a non-Latin language
+const NON_LATIN_SHARE: f64 = 0.4;
+
+/// Share of letters that may be in other scripts for a Latin-script language
+const FOREIGN_SHARE: f64 = 0.2;
+
+/// Writing systems committo can recognise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Script {
+    Latin,
+    Hangul,
+    Kana,
+    Han,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Thai,
+}
+
+/// Scripts a language is written in, by English name, native name or ISO 639-1 code
+pub fn scripts_for(language: &str) -> Option<&'static [Script]> {
+    let scripts: &'static [Script] = match language.trim().to_lowercase().as_str() {
+        "english" | "en" | "french" | "fr" | "german" | "de" | "spanish" | "es" | "portuguese" | "pt" | "italian"
+        | "it" | "dutch" | "nl" | "polish" | "pl" | "swedish" | "sv" | "norwegian" | "no" | "danish" | "da"
+        | "finnish" | "fi" | "czech" | "cs" | "turkish" | "tr" | "vietnamese" | "vi" | "indonesian" | "id" => &[Script::Latin],
+        "korean" | "ko" | "한국어" => &[Script::Hangul],
+        "japanese" | "ja" | "日本語" => &[Script::Kana, Script::Han],
+        "chinese" | "zh" | "中文" => &[Script::Han],
+        "russian" | "ru" | "ukrainian" | "uk" | "bulgarian" | "bg" | "serbian" | "sr" => &[Script::Cyrillic],
+        "greek" | "el" => &[Script::Greek],
+        "arabic" | "ar" | "persian" | "fa" => &[Script::Arabic],
+        "hebrew" | "he" => &[Script::Hebrew],
+        "hindi" | "hi" => &[Script::Devanagari],
+        "thai" | "th" => &[Script::Thai],
+        _ => return None,
+    };
+    Some(scripts)
+}
+
+fn script_of(c: char) -> Option<Script> {
+    let script = match c {
+        'a'..='z' | 'A'..='Z' | '\u{C0}'..='\u{24F}' | '\u{1E00}'..='\u{1EFF}' => Script::Latin,
+        '\u{AC00}'..='\u{D7A3}' | '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' => Script::Hangul,
+        '\u{3040}'..='\u{30FF}' => Script::Kana,
+        '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => Script::Han,
+        '\u{400}'..='\u{4FF}' => Script::Cyrillic,
+        '\u{370}'..='\u{3FF}' => Script::Greek,
+        '\u{600}'..='\u{6FF}' => Script::Arabic,
+        '\u{590}'..='\u{5FF}' => Script::Hebrew,
+        '\u{900}'..='\u{97F}' => Script::Devanagari,
+        '\u{E00}'..='\u{E7F}' => Script::Thai,
+        _ => return None,
+    };
+    Some(script)
+}
+
+/// Whether `text` is written in `language`; `None` if the language is unknown or
+/// the text has no words to judge by. Code spans, paths and identifiers don't count.
+pub fn is_written_in(text: &str, language: &str) -> Option<bool> {
+    let expected = scripts_for(language)?;
+    let (mut matching, mut total) = (0usize, 0usize);
+    for (i, part) in text.split('`').enumerate() {
+        if i % 2 == 1 {
+            continue;
+        }
+        for word in part.split_whitespace() {
+            if word.chars().any(|c| c.is_ascii_digit() || "/_.:()[]{}<>=#@".contains(c)) {
+                continue;
+            }
+            for script in word.chars().filter_map(script_of) {
+                total += 1;
+                matching += usize::from(expected.contains(&script));
+            }
+        }
+    }
+    if total == 0 {
+        return None;
+    }
+    let share = matching as f64 / total as f64;
+    Some(if expected == [Script::Latin] { share >= 1.0 - FOREIGN_SHARE } else { share >= NON_LATIN_SHARE })
+}
+
+/// Problems with the language of `message`. In bilingual mode the subject must be
+/// English and the body `language`; otherwise the whole message must be `language`.
+pub fn problems(message: &str, language: &str, bilingual: bool) -> Vec<String> {
+    let subject = message.lines().next().unwrap_or_default();
+    let description = parse_header(subject).map_or(subject, |header| header.description);
+    let body: Vec<&str> = message
+        .lines()
+        .skip(1)
+        .filter(|line| !is_trailer(line))
+        .collect();
+    let body = body.join("\n");
+
+    let subject_language = if bilingual { "English" } else { language };
+    let mut problems = Vec::new();
+    if is_written_in(description, subject_language) == Some(false) {
+        problems.push(format!("subject is not written in {}", subject_language));
+    }
+    if is_written_in(&body, language) == Some(false) {
+        problems.push(format!("body is not written in {}", language));
+    }
+    problems
+}
+
+/// `Signed-off-by: ...` and similar lines, which are never translated
+fn is_trailer(line: &str) -> bool {
+    line.split_once(": ").is_some_and(|(key, _)| {
+        key.contains('-') && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_written_in() {
+        assert_eq!(is_written_in("로그인 화면 추가", "Korean"), Some(true));
+        assert_eq!(is_written_in("`LoginView` 로그인 API 추가", "ko"), Some(true));
+        assert_eq!(is_written_in("add the login screen", "Korean"), Some(false));
+        assert_eq!(is_written_in("add the login screen", "English"), Some(true));
+        assert_eq!(is_written_in("로그인 화면 추가", "English"), Some(false));
+        assert_eq!(is_written_in("ログイン画面を追加", "Japanese"), Some(true));
+        assert_eq!(is_written_in("добавить экран входа", "Russian"), Some(true));
+        assert_eq!(is_written_in("src/app.rs v1.2", "Korean"), None);
+        assert_eq!(is_written_in("add login", "Klingon"), None);
+    }
+
+    #[test]
+    fn test_problems() {
+        assert!(problems("feat(auth): 로그인 화면 추가", "Korean", false).is_empty());
+        assert_eq!(problems("feat(auth): add login screen", "Korean", false), vec!["subject is not written in Korean"]);
+
+        let bilingual = "feat: add login screen\n\n로그인 화면을 추가하고 세션을 저장합니다.\n\nSigned-off-by: A <a@b.c>";
+        assert!(problems(bilingual, "Korean", true).is_empty());
+        assert_eq!(
+            problems("feat: 로그인 추가\n\nAdds the login screen.", "Korean", true),
+            vec!["subject is not written in English", "body is not written in Korean"]
+        );
+    }
+}
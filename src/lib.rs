@@ -1,123 +1,565 @@
 pub mod api;
 mod cli;
-mod commit;
+mod clipboard;
+pub mod commit;
 pub mod config;
 mod convention;
-mod git;
+mod diff;
+pub mod doctor;
+pub mod error;
+pub mod fixup;
+pub mod git;
+mod gitmoji;
+mod history;
+mod http;
+mod language;
+mod migration;
+mod output;
+mod picker;
+mod postprocess;
+pub mod prompt;
+mod retry;
+pub mod scope;
+pub mod split;
+#[cfg(feature = "structure")]
+mod structure;
 pub mod providers;
 mod utils;
 
-pub use cli::{Cli, Commands};
+pub use cli::{Cli, Commands, ConfigCommands, ConventionCommands, OutputFormat};
+pub use error::RunError;
 
-use std::io;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::Path;
 
 /// Main application runner
-pub async fn run(cli: Cli) -> io::Result<()> {
+pub async fn run(cli: Cli) -> Result<(), RunError> {
     // Get home directory and config path
     let home_path = home::home_dir().ok_or_else(|| {
         io::Error::new(io::ErrorKind::NotFound, "Cannot find home directory")
     })?;
     let config_path = home_path.join(config::CONFIG_FILE_NAME);
-    match cli.command {
+    match &cli.command {
         Some(Commands::Set { key, value }) => {
             // Set command doesn't need interactive setup - just use static method
-            config::Config::handle_set_command(&key, &value, &config_path)?;
+            config::Config::handle_set_command(key, value, &config_path)?;
+        }
+        Some(Commands::Config { command: ConfigCommands::Doctor }) => {
+            // Doctor only reads the file, so it never triggers setup or migration
+            if !config_path.exists() {
+                println!("No configuration file found at: {}", config_path.display());
+                return Ok(());
+            }
+            let report = doctor::diagnose(&config_path)?;
+            print!("{}", report.render());
+        }
+        Some(Commands::Convention { command }) => {
+            // Conventions don't involve the provider, so no config file is needed
+            convention_command(command, &config_path)?;
         }
         Some(Commands::Show) => {
             // Create config instance - this will handle setup if needed
             let (config, _) = config::Config::new(&config_path)?;
             config.show()?;
         }
+        Some(Commands::Split) => {
+            if !cli.change_scope()?.is_staged_only() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "split works on staged changes only; stage them first instead of using --all, --include-untracked or paths",
+                )
+                .into());
+            }
+            let (config, _) = config::Config::new(&config_path)?;
+            let options = commit::CommitOptions {
+                trailers: commit_trailers(&cli, &config)?,
+                signing: config.get_signing()?,
+                extra_args: cli.commit_args()?.options,
+                ..Default::default()
+            };
+            let provider = providers::ProviderFactory::create_chain(config)?;
+            split::run_split(provider.as_ref(), cli.dry_run, cli.yes, io::stdin().is_terminal(), &options).await?;
+        }
         Some(Commands::Generate) | None => {
             // Default to generate when no subcommand is provided
-            // Create config instance - this will handle setup if needed
-            let (config, _) = config::Config::new(&config_path)?;
-            let provider = providers::ProviderFactory::create_provider(config.clone());
-            
-            // Get effective dry run mode from global CLI flag
-            let effective_dry_run = cli.dry_run;
-
-            let diff = git::get_staged_diff()?;
-            if !effective_dry_run && diff.trim().is_empty() {
-                println!("No staged changes to commit.");
-                return Ok(());
+            generate(&cli, &config_path).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Run one of the `committo convention` subcommands
+fn convention_command(command: &ConventionCommands, config_path: &Path) -> Result<(), RunError> {
+    match command {
+        ConventionCommands::Init { preset, from_history, force } => {
+            let root = git::repo_root().or_else(|_| std::env::current_dir())?;
+            let path = root.join(config::CONVENTION_FILE_NAME);
+            if path.exists() && !force {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} already exists; use --force to overwrite it", path.display()),
+                )
+                .into());
             }
 
-            let mut response = provider.generate_commit_message(&diff, effective_dry_run)
-                .await
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-            
-            if effective_dry_run {
-                let candidate_count = provider.get_candidate_count();
-                if candidate_count > 1 {
-                    println!("Dry run: Would generate {} candidates", candidate_count);
-                } else {
-                    println!("{response}");
+            const FROM_HISTORY: &str = "infer from git history";
+            let choice = match (preset, from_history) {
+                (Some(preset), _) => preset.clone(),
+                (None, true) => FROM_HISTORY.to_string(),
+                (None, false) if io::stdin().is_terminal() => {
+                    let mut options: Vec<&str> = convention::preset_names();
+                    options.push(FROM_HISTORY);
+                    inquire::Select::new("Start the convention from:", options).prompt()?.to_string()
                 }
-                return Ok(());
-            }
-            
-            // Parse the response into candidates and handle selection with retry
-            let candidate_count = provider.get_candidate_count();
-            let selected_message = loop {
-                let candidates = utils::parse_commit_message_candidates(&response, candidate_count);
-                
-                if candidates.is_empty() {
-                    println!("No commit message candidates generated.");
-                    return Ok(());
+                (None, false) => {
+                    return Err(RunError::NotInteractive(
+                        "stdin is not a terminal; use --preset <name> or --from-history".to_string(),
+                    ));
                 }
-                
-                if candidates.len() == 1 {
-                    // Single candidate - ask if user wants to retry or use it
-                    use inquire::Select;
-                    let options = vec!["🔄 Retry (generate new commit message)", &candidates[0]];
-                    let selection = Select::new("Select an option:", options.clone())
-                        .with_starting_cursor(1) // Default to the generated message
-                        .prompt()
-                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-                    let selection_index = options.iter().position(|&x| x == selection).unwrap();
-                    
-                    if selection_index == 0 {
-                        // Retry - generate new message
-                        println!("🔄 Generating new commit message...");
-                        let new_response = provider.generate_commit_message(&diff, false)
-                            .await
-                            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-                        response = new_response;
-                        continue;
-                    } else {
-                        break candidates[0].clone();
-                    }
-                } else {
-                    // Multiple candidates - add retry option at the top
-                    use inquire::Select;
-                    let mut options = vec!["🔄 Retry (generate new messages)".to_string()];
-                    options.extend(candidates.iter().cloned());
-                    
-                    let selection = Select::new("Select a commit message:", options.clone())
-                        .with_starting_cursor(1) // Default to first generated message
-                        .prompt()
-                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-                    let selection_index = options.iter().position(|x| x == &selection).unwrap();
-                    
-                    if selection_index == 0 {
-                        // Retry - generate new messages
-                        println!("🔄 Generating new commit messages...");
-                        let new_response = provider.generate_commit_message(&diff, false)
-                            .await
-                            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-                        response = new_response;
-                        continue;
-                    } else {
-                        break candidates[selection_index - 1].clone();
-                    }
+            };
+
+            let content = if choice == FROM_HISTORY {
+                let messages = git::recent_messages(200)?;
+                let inferred = history::infer(&messages).ok_or_else(|| {
+                    io::Error::other(format!(
+                        "couldn't infer a convention from {} commit(s); at least {} commits with a common style are needed",
+                        messages.len(),
+                        history::MIN_SAMPLE_SIZE
+                    ))
+                })?;
+                println!("Found in the last {} commits:", inferred.sample_size);
+                for line in inferred.summary() {
+                    println!("  {}", line);
                 }
+                inferred.to_file()
+            } else if convention::preset_names().contains(&choice.as_str()) {
+                convention::preset_file(&choice)
+            } else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unknown preset `{}`; available: {}", choice, convention::preset_names().join(", ")),
+                )
+                .into());
+            };
+            fs::write(&path, content)?;
+            println!("Created {}", path.display());
+        }
+        ConventionCommands::Analyze { limit } => {
+            let stats = history::analyze(&git::recent_messages(*limit)?);
+            print!("{}", stats.report());
+            match stats.infer() {
+                Some(inferred) => {
+                    let convention = convention::Convention { inferred: Some(inferred), ..Default::default() };
+                    println!("\nWithout a convention file, the model is told:");
+                    println!("{}", convention.prompt());
+                }
+                None => println!("\nNo common style found; at least {} commits are needed.", history::MIN_SAMPLE_SIZE),
+            }
+        }
+        ConventionCommands::Show => {
+            let convention = staged_convention(config_path)?;
+            if convention.outline().is_empty() {
+                println!("No convention files apply to the staged changes. Create one with `committo convention init`.");
+                return Ok(());
+            }
+            println!("Conventions, from general to specific:");
+            print!("{}", convention.outline());
+            println!("\n--- Prompt ---");
+            let prompt = prompt::Prompt { templates: prompt::Templates::load()?, convention, ..Default::default() };
+            println!("{}", prompt.system(1));
+        }
+        ConventionCommands::Check { message } => {
+            let message = if message == "-" {
+                let mut message = String::new();
+                io::stdin().read_to_string(&mut message)?;
+                message
+            } else {
+                message.clone()
             };
-            
-            // Use run-edit config setting to determine whether to open editor
-            let run_edit = config.get_run_edit();
-            commit::execute_git_commit_with_pipe(&selected_message, run_edit)?;
+            let problems = staged_convention(config_path)?.problems(message.trim());
+            if problems.is_empty() {
+                println!("The message follows the convention.");
+                return Ok(());
+            }
+            for problem in &problems {
+                println!("- {}", problem);
+            }
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("the message breaks {} convention rule(s)", problems.len()),
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// The convention for the staged changes, completed from the config the same way `generate` does
+fn staged_convention(config_path: &Path) -> io::Result<convention::Convention> {
+    let config = if config_path.exists() { config::Config::new(config_path)?.0 } else { config::Config::default() };
+    convention::Convention::for_staged()?.with_config(&config)
+}
+
+/// How many times a candidate in the wrong language is sent back
+const LANGUAGE_RETRIES: usize = 2;
+
+/// Ask again for candidates that aren't written in the convention's language
+async fn fix_languages(
+    provider: &dyn api::LlmProvider,
+    system_prompt: &str,
+    diff: &str,
+    response: String,
+    convention: &convention::Convention,
+    candidate_count: u32,
+) -> Result<String, RunError> {
+    let Some(language) = &convention.settings.language else {
+        return Ok(response);
+    };
+    let mut candidates = utils::parse_commit_message_candidates(&response, candidate_count);
+    if candidates.iter().all(|candidate| convention.language_problems(candidate).is_empty()) {
+        return Ok(response);
+    }
+
+    let instruction = if convention.bilingual() {
+        format!("write the subject line in English and the body in {}", language)
+    } else {
+        format!("write all of it in {}", language)
+    };
+    let mut conversation = api::Conversation::new(system_prompt, diff);
+    conversation.push_response(response.clone());
+    for (i, candidate) in candidates.iter_mut().enumerate() {
+        for _ in 0..LANGUAGE_RETRIES {
+            let problems = convention.language_problems(candidate);
+            if problems.is_empty() {
+                break;
+            }
+            eprintln!("Candidate {}: {}; asking again", i + 1, problems.join(", "));
+            *candidate = api::refine_candidate(provider, &mut conversation, candidate, &instruction).await?;
+        }
+    }
+    Ok(utils::join_candidates(&candidates))
+}
+
+/// Number of recent commit subjects offered to prompt templates
+const RECENT_LOG_LENGTH: usize = 10;
+
+/// Generate commit message candidates for the staged changes and commit the chosen one
+async fn generate(cli: &Cli, config_path: &Path) -> Result<(), RunError> {
+    let json_output = cli.format == OutputFormat::Json;
+    if json_output && !config_path.exists() {
+        return Err(RunError::NotInteractive(format!(
+            "No configuration file found at: {}. Run 'committo set api-key <key>' to create one.",
+            config_path.display()
+        )));
+    }
+
+    // Create config instance - this will handle setup if needed
+    let (config, _) = config::Config::new(config_path)?;
+    let provider = providers::ProviderFactory::create_chain(config.clone())?;
+    generate_with_provider(cli, &config, provider.as_ref()).await
+}
+
+/// Generate candidates with `provider` and commit the chosen one, as `committo generate` does
+pub async fn generate_with_provider(
+    cli: &Cli,
+    config: &config::Config,
+    provider: &dyn api::LlmProvider,
+) -> Result<(), RunError> {
+    let json_output = cli.format == OutputFormat::Json;
+
+    // Get effective dry run mode from global CLI flag
+    let effective_dry_run = cli.dry_run;
+
+    let diff_options = cli.diff_options(config.get_diff_options()?);
+    let commit_args = cli.commit_args()?;
+    let changes = git::prepare_changes(&cli.change_scope()?, &diff_options)?;
+    let diff = diff::prompt_diff(&changes.diff, git::blob_info);
+    #[cfg(feature = "structure")]
+    let diff = if config.get_structural_summary() {
+        structure::with_summary(&changes, diff)
+    } else {
+        diff
+    };
+    if !effective_dry_run && changes.is_empty() && !commit_args.allows_empty() {
+        return Err(RunError::NoStagedChanges);
+    }
+
+    // Rules depend on which files changed, not on the working directory
+    let convention = convention::Convention::for_changes(&changes.diff)?.with_config(config)?;
+
+    let scope_mode = config.get_scope_mode()?;
+    let packages = if scope_mode == scope::ScopeMode::Off {
+        Vec::new()
+    } else {
+        scope::changed_packages(&scope::discover_packages(&git::repo_root()?), &changes.diff)
+    };
+    // Best effort: a failing blame shouldn't stop the model's candidates
+    let fixup = if config.get_detect_fixups() { fixup::find_target(&changes.diff).ok().flatten() } else { None };
+
+    let trailers = commit_trailers(cli, config)?;
+    let mut commit_options = commit::CommitOptions {
+        index_file: changes.index_file.clone(),
+        trailers: trailers.clone(),
+        signing: config.get_signing()?,
+        extra_args: commit_args.options,
+        ..Default::default()
+    };
+    let hint = scope::scope_hint(&packages);
+    let diff = if hint.is_empty() { diff } else { format!("{}\n{}", hint, diff) };
+    let postprocessor = postprocess::Postprocessor {
+        scope: match packages.as_slice() {
+            [package] if scope_mode == scope::ScopeMode::Enforce => Some(package.clone()),
+            _ => None,
+        },
+        convention: convention.clone(),
+        trailers: trailers.clone(),
+        extra: fixup.iter().map(fixup::FixupTarget::message).collect(),
+    };
+    if packages.len() > 1 && !json_output {
+        eprintln!(
+            "Note: these changes span {} packages ({}); `committo split` can commit them separately.",
+            packages.len(),
+            packages.join(", ")
+        );
+    }
+
+    // The first user message comes from a template too; by default it is just the diff
+    let language = convention.language().map(str::to_string);
+    let prompt = prompt::Prompt {
+        templates: prompt::Templates::load()?,
+        context: prompt::PromptContext {
+            diff,
+            files: diff::parse_diff(&changes.diff).iter().map(|file| file.path().to_string()).collect(),
+            branch: git::current_branch(),
+            language,
+            bilingual: convention.bilingual(),
+            recent_log: git::recent_messages(RECENT_LOG_LENGTH)
+                .unwrap_or_default()
+                .iter()
+                .map(|message| message.lines().next().unwrap_or_default().to_string())
+                .collect(),
+        },
+        convention: convention.clone(),
+    };
+    let diff = prompt.user(provider.get_candidate_count())?;
+    let system_prompt = provider.build_system_prompt(&prompt);
+
+    // Fail before calling the API if we would have to prompt without a terminal
+    let preselected = cli.preselected_index();
+    let interactive = io::stdin().is_terminal();
+    if !effective_dry_run && !cli.print && !json_output && preselected.is_none() && !interactive {
+        return Err(RunError::NotInteractive(
+            "stdin is not a terminal; use --yes, --pick <n> or --print to run without prompts".to_string(),
+        ));
+    }
+
+    let completion = provider.generate_completion(&prompt, effective_dry_run).await?;
+    let response = completion.content;
+
+    if effective_dry_run {
+        let candidate_count = provider.get_candidate_count();
+        if candidate_count > 1 {
+            println!("Dry run: Would generate {} candidates", candidate_count);
+        } else {
+            println!("{response}");
         }
+        if let Some(target) = &fixup {
+            println!("Would also offer `{}` for {}", target.message(), target.commit);
+        }
+        return Ok(());
+    }
+
+    // Parse the response into candidates and handle selection with retry
+    let candidate_count = provider.get_candidate_count();
+    let response = fix_languages(provider, &system_prompt, &diff, response, &convention, candidate_count).await?;
+
+    if json_output {
+        let report = output::CandidatesReport {
+            candidates: postprocessor.candidates(&response, candidate_count),
+            model: provider.get_config().model.clone(),
+            provider: provider.get_provider_name(),
+            usage: completion.usage,
+            diff_stats: git::DiffStats::from_diff(&changes.diff),
+            packages,
+            fixup,
+        };
+        println!("{}", report.to_json());
+        return Ok(());
     }
+
+    if cli.print || preselected.is_some() {
+        let candidates = postprocessor.candidates(&response, candidate_count);
+        if cli.print {
+            for candidate in &candidates {
+                println!("{candidate}");
+            }
+            return Ok(());
+        }
+
+        let index = preselected.unwrap_or(0);
+        let selected_message = candidates.get(index).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("--pick {} is out of range: got {} candidate(s)", index + 1, candidates.len()),
+            )
+        })?;
+
+        for problem in postprocessor.problems(&selected_message) {
+            eprintln!("Warning: {}", problem);
+        }
+
+        // Never open an editor when nobody is there to close it
+        commit_options.run_edit = config.get_run_edit() && interactive;
+        commit_changes(&selected_message, &commit_options, &changes)?;
+        return Ok(());
+    }
+
+    if !changes.files.is_empty() {
+        println!("Changes to be committed:");
+        for file in &changes.files {
+            println!("    {file}");
+        }
+    }
+
+    let Some(selected_message) = picker::pick_commit_message(provider, &system_prompt, &diff, &response, &postprocessor).await? else {
+        return Ok(());
+    };
+
+    // Use run-edit config setting to determine whether to open editor
+    commit_options.run_edit = config.get_run_edit();
+    commit_changes(&selected_message, &commit_options, &changes)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Sign-off, co-authors and static trailers for the commits of this run
+fn commit_trailers(cli: &Cli, config: &config::Config) -> Result<commit::Trailers, RunError> {
+    let mut trailers = commit::Trailers::default();
+    for (key, value) in config.get_trailers()? {
+        trailers.push(&key, &value);
+    }
+
+    if !cli.co_authors.is_empty() || cli.choose_co_authors {
+        let known = commit::known_co_authors(config.co_authors.as_deref().unwrap_or_default())?;
+        let mut co_authors = cli
+            .co_authors
+            .iter()
+            .map(|who| commit::resolve_co_author(who, &known))
+            .collect::<io::Result<Vec<_>>>()?;
+        if cli.choose_co_authors {
+            if !io::stdin().is_terminal() {
+                return Err(RunError::NotInteractive(
+                    "stdin is not a terminal; name co-authors with --co-author instead of --choose-co-authors".to_string(),
+                ));
+            }
+            let options: Vec<String> = known.into_iter().filter(|person| !co_authors.contains(person)).collect();
+            co_authors.extend(inquire::MultiSelect::new("Co-authors:", options).prompt()?);
+        }
+        for co_author in &co_authors {
+            trailers.push(commit::CO_AUTHORED_BY, co_author);
+        }
+    }
+
+    // The sign-off goes last, as `git commit --signoff` puts it
+    if cli.signoff || config.get_signoff() {
+        let identity = git::committer_identity().map_err(|e| {
+            io::Error::other(format!("signing off needs a git identity; set user.name and user.email ({})", e))
+        })?;
+        trailers.push(commit::SIGNED_OFF_BY, &identity);
+    }
+    Ok(trailers)
+}
+
+/// Commit exactly the described changes, then bring the regular index up to date
+fn commit_changes(message: &str, options: &commit::CommitOptions, changes: &git::ChangeSet) -> io::Result<()> {
+    commit::execute_git_commit(message, options)?;
+    changes.finish_commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Answers with `replies` in order
+    struct ScriptedProvider {
+        config: api::LlmConfig,
+        app_config: config::Config,
+        replies: Mutex<Vec<&'static str>>,
+    }
+
+    #[async_trait]
+    impl api::LlmProvider for ScriptedProvider {
+        fn get_config(&self) -> &api::LlmConfig {
+            &self.config
+        }
+
+        fn get_provider_name(&self) -> String {
+            "scripted".to_string()
+        }
+
+        async fn generate_commit_message_impl(&self, _messages: &[api::ChatMessage]) -> Result<String, api::LlmError> {
+            Ok(self.replies.lock().unwrap().remove(0).to_string())
+        }
+
+        fn get_api_key(&self) -> Result<String, api::LlmError> {
+            Ok("key".to_string())
+        }
+
+        fn get_app_config(&self) -> &config::Config {
+            &self.app_config
+        }
+
+        fn get_candidate_count(&self) -> u32 {
+            2
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fix_languages_asks_again_for_wrong_candidates() {
+        let provider = ScriptedProvider {
+            config: api::LlmConfig { model: "m".to_string(), endpoint: String::new() },
+            app_config: config::Config::default(),
+            replies: Mutex::new(vec!["feat: still english", "feat: 로그인 화면 추가"]),
+        };
+        let convention = convention::Convention {
+            settings: convention::ConventionSettings { language: Some("Korean".to_string()), ..Default::default() },
+            ..Default::default()
+        };
+
+        let response = "fix: 토큰 검증 수정\nfeat: add login screen".to_string();
+        let fixed = fix_languages(&provider, "system", "diff", response, &convention, 2).await.unwrap();
+        assert_eq!(fixed, "fix: 토큰 검증 수정\nfeat: 로그인 화면 추가");
+        assert!(provider.replies.lock().unwrap().is_empty());
+
+        // Without a language nothing is sent
+        let fixed = fix_languages(&provider, "system", "diff", "feat: add".to_string(), &convention::Convention::default(), 1).await.unwrap();
+        assert_eq!(fixed, "feat: add");
+    }
+
+    #[tokio::test]
+    async fn test_fix_languages_keeps_bilingual_bodies() {
+        let provider = ScriptedProvider {
+            config: api::LlmConfig { model: "m".to_string(), endpoint: String::new() },
+            app_config: config::Config::default(),
+            replies: Mutex::new(vec!["feat: add sign-in screen\n\n로그인 화면을 추가합니다."]),
+        };
+        let convention = convention::Convention {
+            settings: convention::ConventionSettings {
+                language: Some("Korean".to_string()),
+                bilingual: Some(true),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let response = "feat: add login\n\n로그인 기능을 추가합니다.\n---\nfeat: add sign-in screen\n\nAdds the sign-in screen.".to_string();
+        let fixed = fix_languages(&provider, "system", "diff", response, &convention, 2).await.unwrap();
+        assert_eq!(
+            fixed,
+            "feat: add login\n\n로그인 기능을 추가합니다.\n---\nfeat: add sign-in screen\n\n로그인 화면을 추가합니다."
+        );
+        assert!(provider.replies.lock().unwrap().is_empty());
+    }
+}
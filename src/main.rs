@@ -1,11 +1,20 @@
 use clap::Parser;
-use committo::{Cli, run};
+use committo::{Cli, OutputFormat, run};
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
+    let format = cli.format;
     if let Err(e) = run(cli).await {
-        eprintln!("Error: {e}");
-        std::process::exit(1);
+        match format {
+            OutputFormat::Json => println!("{}", e.to_json()),
+            OutputFormat::Text => {
+                eprintln!("Error: {e}");
+                if let Some(hint) = e.hint() {
+                    eprintln!("Hint: {hint}");
+                }
+            }
+        }
+        std::process::exit(e.exit_code());
     }
 }
@@ -0,0 +1,88 @@
+//! Config schema migrations
+//!
+//! Each migration upgrades a raw TOML table by exactly one schema version.
+//! Migrations operate on the untyped table so that keys which no longer
+//! exist in `Config` can still be read and carried over.
+
+use toml::{Table, Value};
+
+use crate::config::{CONFIG_VERSION_CONFIG, CURRENT_CONFIG_VERSION};
+
+/// A single upgrade step from `from` to `from + 1`
+struct Migration {
+    from: u32,
+    description: &'static str,
+    apply: fn(&mut Table),
+}
+
+/// All known migrations, ordered by source version
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "add the config-version marker",
+    apply: migrate_v0_to_v1,
+}];
+
+/// Keys that were renamed, as (old name, new name); migrations move their values over
+pub const DEPRECATED_KEYS: &[(&str, &str)] = &[];
+
+/// Read the schema version of a raw config table (files without a marker are version 0)
+pub fn table_version(table: &Table) -> u32 {
+    table
+        .get(CONFIG_VERSION_CONFIG)
+        .and_then(Value::as_integer)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(0)
+}
+
+/// Upgrade a raw config table to the current schema version.
+/// Returns the descriptions of the migrations that were applied.
+pub fn migrate(table: &mut Table) -> Vec<&'static str> {
+    let mut applied = Vec::new();
+    let mut version = table_version(table);
+
+    while version < CURRENT_CONFIG_VERSION {
+        let Some(migration) = MIGRATIONS.iter().find(|m| m.from == version) else {
+            break;
+        };
+        (migration.apply)(table);
+        applied.push(migration.description);
+        version += 1;
+    }
+
+    table.insert(
+        CONFIG_VERSION_CONFIG.to_string(),
+        Value::Integer(i64::from(CURRENT_CONFIG_VERSION)),
+    );
+    applied
+}
+
+/// v0 → v1: files from before versioning use the same keys, they only lack the marker
+fn migrate_v0_to_v1(_table: &mut Table) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unversioned_table_is_version_zero() {
+        let table: Table = toml::from_str("api-key = \"k\"").unwrap();
+        assert_eq!(table_version(&table), 0);
+    }
+
+    #[test]
+    fn test_migrate_stamps_unversioned_table() {
+        let mut table: Table = toml::from_str("api-key = \"k\"\ncandidate-count = 3").unwrap();
+        let applied = migrate(&mut table);
+
+        assert_eq!(applied.len(), 1);
+        assert_eq!(table.get("api-key").and_then(Value::as_str), Some("k"));
+        assert_eq!(table.get("candidate-count").and_then(Value::as_integer), Some(3));
+        assert_eq!(table_version(&table), CURRENT_CONFIG_VERSION);
+    }
+
+    #[test]
+    fn test_current_table_is_untouched() {
+        let mut table: Table = toml::from_str("config-version = 1\napi-key = \"k\"").unwrap();
+        assert!(migrate(&mut table).is_empty());
+    }
+}
@@ -0,0 +1,27 @@
+use serde::Serialize;
+
+use crate::api::TokenUsage;
+use crate::fixup::FixupTarget;
+use crate::git::DiffStats;
+
+/// Candidates and metadata printed by `--format json`
+#[derive(Debug, Serialize)]
+pub struct CandidatesReport {
+    pub candidates: Vec<String>,
+    pub model: String,
+    pub provider: String,
+    pub usage: Option<TokenUsage>,
+    pub diff_stats: DiffStats,
+    /// Monorepo packages the changes belong to
+    pub packages: Vec<String>,
+    /// Earlier commit on the branch the changes fix; its `fixup!` message is the last candidate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixup: Option<FixupTarget>,
+}
+
+impl CandidatesReport {
+    /// Render as a single line of JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("candidates report is always serializable")
+    }
+}
@@ -0,0 +1,139 @@
+use inquire::{Editor, Select, Text};
+
+use crate::api::{refine_candidate, regenerate_candidate, retry_with_feedback, Conversation, LlmProvider};
+use crate::clipboard::copy_to_clipboard;
+use crate::error::RunError;
+use crate::postprocess::Postprocessor;
+
+const ACTION_COMMIT: &str = "✅ Commit this message";
+const ACTION_EDIT: &str = "✏️  Edit in your editor";
+const ACTION_REFINE: &str = "💬 Refine with an instruction";
+const ACTION_REGENERATE: &str = "🔄 Regenerate only this one";
+const ACTION_COPY: &str = "📋 Copy to clipboard";
+const ACTION_BACK: &str = "↩️  Back to the list";
+
+/// Actions offered for a chosen candidate, in display order
+const CANDIDATE_ACTIONS: &[&str] = &[
+    ACTION_COMMIT,
+    ACTION_EDIT,
+    ACTION_REFINE,
+    ACTION_REGENERATE,
+    ACTION_COPY,
+    ACTION_BACK,
+];
+
+/// Let the user choose, retry, edit or refine candidates until one is accepted.
+/// Returns `None` if the provider produced no candidates.
+pub async fn pick_commit_message(
+    provider: &dyn LlmProvider,
+    system_prompt: &str,
+    diff: &str,
+    response: &str,
+    postprocessor: &Postprocessor,
+) -> Result<Option<String>, RunError> {
+    let candidate_count = provider.get_candidate_count();
+    let mut candidates = postprocessor.candidates(response, candidate_count);
+
+    // Retries continue this conversation so the model sees what was already rejected
+    let mut conversation = Conversation::new(system_prompt, diff);
+    conversation.push_response(response);
+
+    loop {
+        if candidates.is_empty() {
+            println!("No commit message candidates generated.");
+            return Ok(None);
+        }
+
+        let (prompt, retry_label) = if candidates.len() == 1 {
+            ("Select an option:", "🔄 Retry (generate new commit message)")
+        } else {
+            ("Select a commit message:", "🔄 Retry (generate new messages)")
+        };
+        let mut options = vec![retry_label.to_string()];
+        options.extend(candidates.iter().cloned());
+
+        let selection = Select::new(prompt, options)
+            .with_starting_cursor(1) // Default to first generated message
+            .raw_prompt()?;
+
+        if selection.index == 0 {
+            let feedback = Text::new("What should change?")
+                .with_help_message("Optional, e.g. \"mention the config migration\"; press Enter to just try again")
+                .prompt_skippable()?;
+            // Esc returns to the current candidates
+            let Some(feedback) = feedback else {
+                continue;
+            };
+
+            println!("🔄 Generating new commit messages...");
+            let response = retry_with_feedback(provider, &mut conversation, &feedback).await?;
+            candidates = postprocessor.candidates(&response, candidate_count);
+            continue;
+        }
+
+        let index = selection.index - 1;
+        if let Some(message) = candidate_actions(provider, &mut conversation, postprocessor, &mut candidates, index).await? {
+            return Ok(Some(message));
+        }
+    }
+}
+
+/// Show the action menu for one candidate. Returns the message to commit,
+/// or `None` to go back to the candidate list (keeping any edits).
+async fn candidate_actions(
+    provider: &dyn LlmProvider,
+    conversation: &mut Conversation,
+    postprocessor: &Postprocessor,
+    candidates: &mut [String],
+    index: usize,
+) -> Result<Option<String>, RunError> {
+    loop {
+        let mut prompt = candidates[index].clone();
+        for problem in postprocessor.problems(&candidates[index]) {
+            prompt.push_str(&format!("\n  ⚠️  {}", problem));
+        }
+        prompt.push_str("\n  What would you like to do?");
+        // Esc goes back to the list rather than aborting
+        let Some(action) = Select::new(&prompt, CANDIDATE_ACTIONS.to_vec()).prompt_skippable()? else {
+            return Ok(None);
+        };
+
+        match action {
+            ACTION_COMMIT => return Ok(Some(candidates[index].clone())),
+            ACTION_EDIT => {
+                // An editor rather than a one-line prompt, so the body can be edited too
+                let edited = Editor::new("Edit commit message:")
+                    .with_predefined_text(&candidates[index])
+                    .with_file_extension(".txt")
+                    .prompt_skippable()?;
+                if let Some(edited) = edited.filter(|m| !m.trim().is_empty()) {
+                    let edited = edited.trim().to_string();
+                    conversation.push_edit(&candidates[index], &edited);
+                    candidates[index] = edited;
+                }
+            }
+            ACTION_REFINE => {
+                let instruction = Text::new("How should it change?")
+                    .with_help_message("e.g. \"make it shorter\", \"mention the migration\"")
+                    .prompt_skippable()?;
+                if let Some(instruction) = instruction.filter(|i| !i.trim().is_empty()) {
+                    let refined = refine_candidate(provider, conversation, &candidates[index], instruction.trim()).await?;
+                    if let Some(refined) = postprocessor.reply(&refined) {
+                        candidates[index] = refined;
+                    }
+                }
+            }
+            ACTION_REGENERATE => {
+                let regenerated = regenerate_candidate(provider, conversation, candidates, index).await?;
+                if let Some(regenerated) = postprocessor.reply(&regenerated) {
+                    candidates[index] = regenerated;
+                }
+            }
+            ACTION_COPY => match copy_to_clipboard(&candidates[index]) {
+                Ok(()) => println!("📋 Copied to clipboard."),
+                Err(e) => eprintln!("Could not copy to clipboard: {e}"),
+            },
+            _ => return Ok(None),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Fix-ups applied to every generated candidate before it is shown or committed
+
+use crate::commit::Trailers;
+use crate::convention::Convention;
+use crate::fixup;
+use crate::gitmoji;
+use crate::scope::apply_scope;
+use crate::utils;
+
+/// Rewrites candidates to follow rules the model doesn't always keep to
+#[derive(Debug, Clone, Default)]
+pub struct Postprocessor {
+    /// Conventional Commits scope every candidate must use
+    pub scope: Option<String>,
+    /// Rules candidates are checked against
+    pub convention: Convention,
+    /// Trailers added on commit; the model's own versions are dropped
+    pub trailers: Trailers,
+    /// Candidates offered after the model's, such as a `fixup!` message, used as they are
+    pub extra: Vec<String>,
+}
+
+impl Postprocessor {
+    /// Apply all fix-ups to one candidate
+    pub fn apply(&self, message: &str) -> String {
+        let message = self.trailers.strip(message);
+        let message = match &self.scope {
+            Some(scope) => apply_scope(&message, scope),
+            None => message,
+        };
+        match self.convention.settings.emoji {
+            Some(style) => gitmoji::apply(&message, style),
+            None => message,
+        }
+    }
+
+    /// Ways in which a candidate breaks the convention, for showing next to it
+    pub fn problems(&self, message: &str) -> Vec<String> {
+        // Autosquash messages are squashed away, so the convention doesn't apply
+        if fixup::is_autosquash(message) {
+            return Vec::new();
+        }
+        self.convention.problems(&self.trailers.preview(message))
+    }
+
+    /// Fix up a reply that holds a single candidate, such as a refined message.
+    /// Returns `None` if the reply is empty.
+    pub fn reply(&self, response: &str) -> Option<String> {
+        utils::parse_commit_message_candidates(response, 1)
+            .into_iter()
+            .find(|candidate| !candidate.is_empty())
+            .map(|candidate| self.apply(&candidate))
+    }
+
+    /// Split a provider response into candidates and fix each of them up
+    pub fn candidates(&self, response: &str, expected_count: u32) -> Vec<String> {
+        utils::parse_commit_message_candidates(response, expected_count)
+            .iter()
+            .map(|candidate| self.apply(candidate))
+            .chain(self.extra.iter().cloned())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convention::EmojiStyle;
+
+    #[test]
+    fn test_enforces_scope_on_every_candidate() {
+        let postprocessor = Postprocessor { scope: Some("api".to_string()), ..Default::default() };
+        let candidates = postprocessor.candidates("1. feat: add login\n2. fix(web): handle errors", 2);
+        assert_eq!(candidates, vec!["feat(api): add login", "fix(api): handle errors"]);
+    }
+
+    #[test]
+    fn test_emoji_follows_the_type_after_scoping() {
+        let mut convention = Convention::default();
+        convention.settings.emoji = Some(EmojiStyle::Shortcode);
+        let postprocessor = Postprocessor { scope: Some("api".to_string()), convention, ..Default::default() };
+        let candidates = postprocessor.candidates("1. 🐛 feat(web): add login\n2. fix: handle errors", 2);
+        assert_eq!(candidates, vec![":sparkles: feat(api): add login", ":bug: fix(api): handle errors"]);
+        assert!(candidates.iter().all(|candidate| postprocessor.problems(candidate).is_empty()));
+    }
+
+    #[test]
+    fn test_trailers_are_left_to_the_commit() {
+        let mut convention = Convention::default();
+        convention.settings.trailers = Some(vec!["Signed-off-by".to_string()]);
+        let mut postprocessor = Postprocessor { convention, ..Default::default() };
+        let candidate = postprocessor.apply("feat: add login\n\nSigned-off-by: Someone <x@example.com>");
+        assert_eq!(candidate, "feat: add login");
+        assert_eq!(postprocessor.problems(&candidate), vec!["missing trailer `Signed-off-by`"]);
+
+        postprocessor.trailers.push("Signed-off-by", "Me <me@example.com>");
+        assert!(postprocessor.problems(&candidate).is_empty());
+    }
+
+    #[test]
+    fn test_extra_candidates_come_last_unchanged() {
+        let mut convention = Convention::default();
+        convention.settings.types = Some(vec!["feat".to_string()]);
+        let postprocessor = Postprocessor {
+            scope: Some("api".to_string()),
+            convention,
+            extra: vec!["fixup! feat: add login".to_string()],
+            ..Default::default()
+        };
+        let candidates = postprocessor.candidates("feat: handle errors", 1);
+        assert_eq!(candidates, vec!["feat(api): handle errors", "fixup! feat: add login"]);
+        assert!(postprocessor.problems(&candidates[1]).is_empty());
+    }
+
+    #[test]
+    fn test_reply_keeps_the_body() {
+        let postprocessor = Postprocessor { scope: Some("api".to_string()), ..Default::default() };
+        let reply = postprocessor.reply("\nfeat: add login\n\nUsers can now sign in.\n");
+        assert_eq!(reply.as_deref(), Some("feat(api): add login\n\nUsers can now sign in."));
+        assert_eq!(postprocessor.reply("  \n"), None);
+    }
+
+    #[test]
+    fn test_default_leaves_candidates_alone() {
+        assert_eq!(Postprocessor::default().apply("feat: add login"), "feat: add login");
+    }
+}
@@ -0,0 +1,246 @@
+//! Prompt templates
+//!
+//! The system prompt and the user message are minijinja templates. The
+//! built-in ones can be replaced by `system.jinja` and `user.jinja` in the
+//! `prompts` directory of the committo config directory.
+
+use minijinja::{Environment, UndefinedBehavior};
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::committo_config_dir;
+use crate::convention::Convention;
+
+/// Extension of template override files
+const TEMPLATE_EXTENSION: &str = "jinja";
+
+/// How to read the numbered convention, a macro so that `concat!` can use it
+macro_rules! priority_rules {
+    () => {
+        "**IMPORTANT PRIORITY RULES:**
+- Numbers indicate priority: 1 = HIGHEST priority, 2, 3, 4, 5... = lower priority
+- When instructions conflict, ALWAYS follow the higher priority (lower number)
+- Apply these rules when analyzing git diff and generating commit messages
+"
+    };
+}
+
+const DEFAULT_SYSTEM_TEMPLATE: &str = concat!(
+    "\
+You are an AI assistant that helps programmers who struggle with writing commit messages. \
+Based on the following diff, generate a concise and informative commit message.
+{%- if convention %}
+
+",
+    priority_rules!(),
+    "
+{{ convention }}
+{%- endif %}
+{%- if candidate_count > 1 %}
+
+Generate {{ candidate_count }} different commit message options. \
+{%- if bilingual %} Each message has a subject line and a body; put a line containing only `---` between messages.\
+{%- else %} Each message should be on a separate line and be concise and informative.{% endif %}
+{%- endif %}"
+);
+
+const DEFAULT_USER_TEMPLATE: &str = "{{ diff }}";
+
+/// Values available to the templates
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PromptContext {
+    /// The diff as described to the model, with file summaries
+    pub diff: String,
+    /// Paths of the changed files
+    pub files: Vec<String>,
+    pub branch: Option<String>,
+    /// Language from the convention, e.g. "Korean"
+    pub language: Option<String>,
+    /// Whether messages have an English subject and a body in `language`
+    pub bilingual: bool,
+    /// Subjects of the most recent commits, newest first
+    pub recent_log: Vec<String>,
+}
+
+/// Everything the templates can use at render time
+#[derive(Serialize)]
+struct RenderContext<'a> {
+    #[serde(flatten)]
+    context: &'a PromptContext,
+    /// Numbered convention text
+    convention: String,
+    candidate_count: u32,
+}
+
+/// The templates in use, each either built in or loaded from a file
+#[derive(Debug, Clone)]
+pub struct Templates {
+    /// (name, source, file it came from)
+    templates: Vec<(&'static str, String, Option<PathBuf>)>,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Templates {
+            templates: vec![
+                ("system", DEFAULT_SYSTEM_TEMPLATE.to_string(), None),
+                ("user", DEFAULT_USER_TEMPLATE.to_string(), None),
+            ],
+        }
+    }
+}
+
+impl Templates {
+    /// Built-in templates, replaced by any overrides in the config directory
+    pub fn load() -> io::Result<Self> {
+        match committo_config_dir() {
+            Some(dir) => Self::load_from(&dir.join("prompts")),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Built-in templates, replaced by `<name>.jinja` files in `dir`. Every template
+    /// is checked for syntax errors and unknown variables.
+    pub fn load_from(dir: &Path) -> io::Result<Self> {
+        let mut templates = Self::default();
+        for (name, source, origin) in &mut templates.templates {
+            let path = dir.join(format!("{}.{}", name, TEMPLATE_EXTENSION));
+            if path.is_file() {
+                *source = fs::read_to_string(&path)?;
+                *origin = Some(path);
+            }
+        }
+
+        let sample = PromptContext::default();
+        for (name, _, origin) in &templates.templates {
+            templates.render(name, &sample, "", 2).map_err(|e| {
+                let origin = origin.as_ref().map_or_else(|| format!("built-in {} template", name), |path| path.display().to_string());
+                io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", origin, e))
+            })?;
+        }
+        Ok(templates)
+    }
+
+    /// Files that replace built-in templates
+    pub fn overrides(&self) -> Vec<&Path> {
+        self.templates.iter().filter_map(|(_, _, origin)| origin.as_deref()).collect()
+    }
+
+    fn render(&self, name: &str, context: &PromptContext, convention: &str, candidate_count: u32) -> Result<String, minijinja::Error> {
+        let mut env = Environment::new();
+        env.set_undefined_behavior(UndefinedBehavior::Strict);
+        for (name, source, _) in &self.templates {
+            env.add_template_owned(*name, source.clone())?;
+        }
+        let render_context = RenderContext { context, convention: convention.to_string(), candidate_count };
+        env.get_template(name)?.render(&render_context)
+    }
+}
+
+/// Templates together with the values for this run
+#[derive(Debug, Clone, Default)]
+pub struct Prompt {
+    pub templates: Templates,
+    pub context: PromptContext,
+    /// Convention for the changes being described
+    pub convention: Convention,
+}
+
+impl Prompt {
+    /// The system prompt, with the convention
+    pub fn system(&self, candidate_count: u32) -> String {
+        let convention = self.convention.prompt();
+        self.templates
+            .render("system", &self.context, &convention, candidate_count)
+            .or_else(|e| {
+                eprintln!("Warning: the system prompt template failed ({}); using the built-in one", e);
+                Templates::default().render("system", &self.context, &convention, candidate_count)
+            })
+            .unwrap_or_default()
+    }
+
+    /// The first user message, normally just the diff
+    pub fn user(&self, candidate_count: u32) -> io::Result<String> {
+        let convention = self.convention.prompt();
+        self.templates
+            .render("user", &self.context, &convention, candidate_count)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("user prompt template: {}", e)))
+    }
+}
+
+/// Priority rules followed by the numbered convention, as in the built-in system
+/// prompt, or empty if there is no convention. For prompts that aren't templates.
+pub fn convention_guidance(convention: &Convention) -> String {
+    let custom_conventions = convention.prompt();
+    if custom_conventions.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n{}", priority_rules!(), custom_conventions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn render_system(convention: &str, candidate_count: u32) -> String {
+        Templates::default().render("system", &PromptContext::default(), convention, candidate_count).unwrap()
+    }
+
+    #[test]
+    fn test_default_system_prompt() {
+        let intro = "You are an AI assistant that helps programmers who struggle with writing commit messages. Based on the following diff, generate a concise and informative commit message.";
+        assert_eq!(render_system("", 1), intro);
+        assert_eq!(
+            render_system("1. Use Korean", 3),
+            format!(
+                "{}\n\n**IMPORTANT PRIORITY RULES:**\n- Numbers indicate priority: 1 = HIGHEST priority, 2, 3, 4, 5... = lower priority\n- When instructions conflict, ALWAYS follow the higher priority (lower number)\n- Apply these rules when analyzing git diff and generating commit messages\n\n1. Use Korean\n\nGenerate 3 different commit message options. Each message should be on a separate line and be concise and informative.",
+                intro
+            )
+        );
+    }
+
+    #[test]
+    fn test_bilingual_candidates_are_separated() {
+        let context = PromptContext { bilingual: true, ..Default::default() };
+        let system = Templates::default().render("system", &context, "", 2).unwrap();
+        assert!(system.ends_with("Generate 2 different commit message options. Each message has a subject line and a body; put a line containing only `---` between messages."), "{}", system);
+    }
+
+    #[test]
+    fn test_overrides_are_loaded_and_rendered() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("user.jinja"),
+            "Branch: {{ branch or \"detached\" }}\nFiles: {{ files | join(\", \") }}\nRecent:\n{% for subject in recent_log %}- {{ subject }}\n{% endfor %}\n{{ diff }}",
+        )
+        .unwrap();
+        let templates = Templates::load_from(dir.path()).unwrap();
+        assert_eq!(templates.overrides(), vec![dir.path().join("user.jinja")]);
+
+        let context = PromptContext {
+            diff: "diff --git a/a b/a".to_string(),
+            files: vec!["a".to_string(), "b".to_string()],
+            branch: Some("main".to_string()),
+            recent_log: vec!["feat: x".to_string()],
+            ..Default::default()
+        };
+        let prompt = Prompt { templates, context, ..Default::default() };
+        assert_eq!(prompt.user(1).unwrap(), "Branch: main\nFiles: a, b\nRecent:\n- feat: x\n\ndiff --git a/a b/a");
+    }
+
+    #[test]
+    fn test_invalid_templates_fail_to_load() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("system.jinja"), "{% if candidate_count %}unclosed").unwrap();
+        let error = Templates::load_from(dir.path()).unwrap_err();
+        assert!(error.to_string().contains("system.jinja"), "{}", error);
+
+        fs::write(dir.path().join("system.jinja"), "{{ no_such_variable }}").unwrap();
+        let error = Templates::load_from(dir.path()).unwrap_err();
+        assert!(error.to_string().contains("undefined"), "{}", error);
+    }
+}
@@ -0,0 +1,148 @@
+//! Provider fallback chain
+//!
+//! Tries a list of providers in order, moving on to the next one when a provider is
+//! down, rate limited or too slow, optionally within a total time budget.
+
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use crate::api::{ChatMessage, Completion, LlmConfig, LlmError, LlmProvider};
+use crate::config::{Config, SUPPORTED_PROVIDERS};
+
+/// One `fallback` entry: a provider, optionally with the model to use there
+#[derive(Debug, Clone, PartialEq)]
+pub struct FallbackEntry {
+    pub provider: String,
+    pub model: Option<String>,
+}
+
+impl FallbackEntry {
+    /// Parse `provider` or `provider:model`
+    pub fn parse(entry: &str) -> Option<Self> {
+        let (provider, model) = match entry.split_once(':') {
+            Some((provider, model)) => (provider.trim(), Some(model.trim())),
+            None => (entry.trim(), None),
+        };
+        if !SUPPORTED_PROVIDERS.contains(&provider) || model.is_some_and(str::is_empty) {
+            return None;
+        }
+        Some(Self { provider: provider.to_string(), model: model.map(str::to_string) })
+    }
+
+    /// The config for this entry's provider, otherwise the same as `config`
+    pub fn config(&self, config: &Config) -> Config {
+        Config {
+            llm_provider: Some(self.provider.clone()),
+            llm_model: self.model.clone(),
+            ..config.clone()
+        }
+    }
+}
+
+/// Provider that tries each of `providers` in order until one answers
+pub struct FallbackProvider {
+    providers: Vec<Box<dyn LlmProvider + Send + Sync>>,
+    budget: Option<Duration>,
+    /// Index of the provider that answered last
+    answered: AtomicUsize,
+}
+
+impl FallbackProvider {
+    /// `providers` must not be empty; the first one is the primary
+    pub fn new(providers: Vec<Box<dyn LlmProvider + Send + Sync>>, budget: Option<Duration>) -> Self {
+        assert!(!providers.is_empty(), "a fallback chain needs at least one provider");
+        Self { providers, budget, answered: AtomicUsize::new(0) }
+    }
+
+    fn primary(&self) -> &(dyn LlmProvider + Send + Sync) {
+        self.providers[0].as_ref()
+    }
+
+    fn answered(&self) -> &(dyn LlmProvider + Send + Sync) {
+        self.providers[self.answered.load(Ordering::Relaxed)].as_ref()
+    }
+}
+
+/// Provider and model, for notes about the chain
+fn label(provider: &dyn LlmProvider) -> String {
+    format!("{} ({})", provider.get_provider_name(), provider.get_config().model)
+}
+
+#[async_trait]
+impl LlmProvider for FallbackProvider {
+    /// The config of the provider that answered last, or of the primary
+    fn get_config(&self) -> &LlmConfig {
+        self.answered().get_config()
+    }
+
+    fn get_provider_name(&self) -> String {
+        self.answered().get_provider_name()
+    }
+
+    async fn generate_commit_message_impl(&self, messages: &[ChatMessage]) -> Result<String, LlmError> {
+        self.generate_completion_impl(messages).await.map(|completion| completion.content)
+    }
+
+    async fn generate_completion_impl(&self, messages: &[ChatMessage]) -> Result<Completion, LlmError> {
+        let started = Instant::now();
+        let mut providers = self.providers.iter().enumerate().peekable();
+        while let Some((index, provider)) = providers.next() {
+            let result = match self.budget {
+                Some(budget) => {
+                    let remaining = budget.saturating_sub(started.elapsed());
+                    match tokio::time::timeout(remaining, provider.generate_completion_impl(messages)).await {
+                        Ok(result) => result,
+                        // The budget is spent, so there is no time left for the next provider either
+                        Err(_) => return Err(LlmError::Timeout(budget)),
+                    }
+                }
+                None => provider.generate_completion_impl(messages).await,
+            };
+
+            match result {
+                Ok(completion) => {
+                    self.answered.store(index, Ordering::Relaxed);
+                    if index > 0 {
+                        eprintln!("Answered by fallback {}.", label(provider.as_ref()));
+                    }
+                    return Ok(completion);
+                }
+                Err(err) => match providers.peek() {
+                    Some((_, next)) if err.is_retryable() => {
+                        eprintln!("{} failed: {}; falling back to {}", label(provider.as_ref()), err, label(next.as_ref()));
+                    }
+                    _ => return Err(err),
+                },
+            }
+        }
+        unreachable!("the chain has at least one provider")
+    }
+
+    fn get_api_key(&self) -> Result<String, LlmError> {
+        self.primary().get_api_key()
+    }
+
+    fn get_app_config(&self) -> &Config {
+        self.primary().get_app_config()
+    }
+
+    fn get_candidate_count(&self) -> u32 {
+        self.primary().get_candidate_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fallback_entry() {
+        assert_eq!(
+            FallbackEntry::parse("openai:gpt-4o-mini"),
+            Some(FallbackEntry { provider: "openai".to_string(), model: Some("gpt-4o-mini".to_string()) })
+        );
+        assert_eq!(FallbackEntry::parse(" openai "), Some(FallbackEntry { provider: "openai".to_string(), model: None }));
+        assert_eq!(FallbackEntry::parse("openai:"), None);
+        assert_eq!(FallbackEntry::parse("carrier-pigeon"), None);
+    }
+}
@@ -3,40 +3,64 @@
 //! This module contains implementations for different LLM providers.
 //! Each provider is in its own file for better organization and maintainability.
 
+pub mod fallback;
 pub mod openai;
 
 // Re-export common provider types
+pub use fallback::{FallbackEntry, FallbackProvider};
 pub use openai::OpenAiProvider;
 
+use std::io;
 use crate::api::LlmProvider;
+use crate::http;
 use crate::config::{DEFAULT_OPENAI_MODEL, PROVIDER_OPENAI, Config};
 
 /// Provider factory for creating LLM providers
 pub struct ProviderFactory;
 
 impl ProviderFactory {
-    /// Create provider based on provided config
-    pub fn create_provider(config: Config) -> Box<dyn LlmProvider + Send + Sync> {
-        let provider_name = config.llm_provider.clone().unwrap_or_else(|| PROVIDER_OPENAI.to_string());
+    /// Create provider based on provided config; fails on invalid proxy or certificate settings
+    pub fn create_provider(config: Config) -> io::Result<Box<dyn LlmProvider + Send + Sync>> {
+        let client = http::client_for(&config)?;
+        Ok(Self::create_provider_with_client(config, client))
+    }
+
+    /// Create provider that sends its requests through `client`
+    pub fn create_provider_with_client(config: Config, client: reqwest::Client) -> Box<dyn LlmProvider + Send + Sync> {
         let model = config.llm_model.clone().unwrap_or_else(|| DEFAULT_OPENAI_MODEL.to_string());
-        
-        match provider_name.as_str() {
-            PROVIDER_OPENAI => Box::new(OpenAiProvider::with_model(config, &model)),
+
+        match config.llm_provider.as_deref().unwrap_or(PROVIDER_OPENAI) {
+            PROVIDER_OPENAI => Box::new(OpenAiProvider::with_client(config, &model, client)),
             // Future providers can be added here:
             // "claude" => Box::new(claude::ClaudeProvider::new()),
             // "local" => Box::new(local::LocalLlmProvider::new()),
-            _ => Box::new(OpenAiProvider::new(config)), // Default
+            _ => Box::new(OpenAiProvider::with_client(config, DEFAULT_OPENAI_MODEL, client)), // Default
         }
     }
-    
+
+    /// Create the configured provider, wrapped in a `FallbackProvider` when `fallback` lists others.
+    /// All providers share one HTTP client, so bad proxy or certificate settings fail here.
+    pub fn create_chain(config: Config) -> io::Result<Box<dyn LlmProvider + Send + Sync>> {
+        let client = http::client_for(&config)?;
+        let entries = config.get_fallback()?;
+        if entries.is_empty() {
+            return Ok(Self::create_provider_with_client(config, client));
+        }
+        let mut providers = vec![Self::create_provider_with_client(config.clone(), client.clone())];
+        providers.extend(
+            entries.iter().map(|entry| Self::create_provider_with_client(entry.config(&config), client.clone())),
+        );
+        Ok(Box::new(FallbackProvider::new(providers, config.get_fallback_budget())))
+    }
+
     /// Create specific OpenAI provider with config
-    pub fn create_openai(config: Config) -> Box<dyn LlmProvider + Send + Sync> {
-        Box::new(OpenAiProvider::new(config))
+    pub fn create_openai(config: Config) -> io::Result<Box<dyn LlmProvider + Send + Sync>> {
+        Ok(Box::new(OpenAiProvider::new(config)?))
     }
     
     /// Create OpenAI provider with specific model and config
-    pub fn create_openai_with_model(config: Config, model: &str) -> Box<dyn LlmProvider + Send + Sync> {
-        Box::new(OpenAiProvider::with_model(config, model))
+    pub fn create_openai_with_model(config: Config, model: &str) -> io::Result<Box<dyn LlmProvider + Send + Sync>> {
+        Ok(Box::new(OpenAiProvider::with_model(config, model)?))
     }
 }
 
@@ -1,33 +1,135 @@
 use async_trait::async_trait;
-use crate::api::{LlmConfig, LlmError, LlmProvider};
+use reqwest::header::RETRY_AFTER;
+use std::io;
+use crate::api::{ChatMessage, Completion, LlmConfig, LlmError, LlmProvider, TokenUsage};
 use crate::config::{DEFAULT_OPENAI_MODEL, Config};
+use crate::http;
+use crate::retry::{is_retryable, parse_retry_after, RetryPolicy, MAX_RETRY_AFTER};
 
 /// OpenAI provider implementation
 pub struct OpenAiProvider {
     config: LlmConfig,
     app_config: Config,
+    client: reqwest::Client,
+    retry: RetryPolicy,
 }
 
 impl OpenAiProvider {
-    pub fn new(app_config: Config) -> Self {
-        Self {
-            config: LlmConfig {
-                model: DEFAULT_OPENAI_MODEL.to_string(),
-                endpoint: "https://api.openai.com/v1/chat/completions".to_string(),
-            },
-            app_config,
-        }
+    pub fn new(app_config: Config) -> io::Result<Self> {
+        Self::with_model(app_config, DEFAULT_OPENAI_MODEL)
     }
     
-    pub fn with_model(app_config: Config, model: &str) -> Self {
+    /// Fails on invalid proxy or certificate settings in `app_config`
+    pub fn with_model(app_config: Config, model: &str) -> io::Result<Self> {
+        let client = http::client_for(&app_config)?;
+        Ok(Self::with_client(app_config, model, client))
+    }
+
+    /// Use `client`, e.g. one shared with the other providers in a fallback chain
+    pub fn with_client(app_config: Config, model: &str, client: reqwest::Client) -> Self {
         Self {
             config: LlmConfig {
                 model: model.to_string(),
                 endpoint: "https://api.openai.com/v1/chat/completions".to_string(),
             },
+            retry: app_config.get_retry_policy(),
             app_config,
+            client,
         }
     }
+
+    /// Send `body` to the API, retrying rate limits and server errors
+    async fn post(&self, api_key: &str, body: &serde_json::Value) -> Result<serde_json::Value, LlmError> {
+        let mut attempt = 0;
+        loop {
+            let response = self
+                .client
+                .post(&self.config.endpoint)
+                .bearer_auth(api_key)
+                .json(body)
+                .send()
+                .await
+                .map_err(|e| self.request_error(e))?;
+
+            let status = response.status();
+            if status.is_success() {
+                return response.json().await.map_err(|e| self.request_error(e));
+            }
+
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_retry_after);
+            let body = response.text().await.unwrap_or_default();
+            let error = api_error(status.as_u16(), &body, retry_after);
+
+            // An exhausted quota is reported as a 429 too, but waiting doesn't help
+            let retryable = is_retryable(status.as_u16())
+                && !matches!(error, LlmError::ApiError(_) if status.as_u16() == 429)
+                && retry_after.is_none_or(|wait| wait <= MAX_RETRY_AFTER);
+            if !retryable || attempt >= self.retry.max_retries {
+                return Err(error);
+            }
+
+            let delay = self.retry.delay(attempt, retry_after);
+            attempt += 1;
+            eprintln!(
+                "{}; retrying in {:.1}s ({} of {})",
+                error,
+                delay.as_secs_f64(),
+                attempt,
+                self.retry.max_retries
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Name the limit a timed-out request ran into
+    fn request_error(&self, err: reqwest::Error) -> LlmError {
+        if !err.is_timeout() {
+            return err.into();
+        }
+        if err.is_connect() {
+            LlmError::Timeout(self.app_config.get_connect_timeout())
+        } else {
+            LlmError::Timeout(self.app_config.get_request_timeout())
+        }
+    }
+}
+
+/// Turn an error response into an error with the provider's own message
+fn api_error(status: u16, body: &str, retry_after: Option<std::time::Duration>) -> LlmError {
+    // {"error": {"message": "...", "type": "...", "code": "..."}}
+    let parsed: Option<serde_json::Value> = serde_json::from_str(body).ok();
+    let error = parsed.as_ref().and_then(|value| value.get("error"));
+    let field = |name: &str| error.and_then(|e| e.get(name)).and_then(|v| v.as_str()).unwrap_or_default();
+    let code = field("code");
+    let message = match field("message") {
+        "" if body.trim().is_empty() => "empty response".to_string(),
+        "" => body.trim().chars().take(200).collect(),
+        message => message.to_string(),
+    };
+
+    match status {
+        401 | 403 => LlmError::Auth(message),
+        429 if code == "insufficient_quota" => LlmError::ApiError(message),
+        429 => LlmError::RateLimited { message, retry_after },
+        _ if code == "context_length_exceeded" || message.contains("maximum context length") => {
+            LlmError::ContextLengthExceeded(message)
+        }
+        500..=599 => LlmError::Unavailable(with_status(message, status)),
+        _ => LlmError::ApiError(with_status(message, status)),
+    }
+}
+
+/// `message` with the HTTP status, unless it already mentions it
+fn with_status(message: String, status: u16) -> String {
+    if message.contains(&status.to_string()) {
+        message
+    } else {
+        format!("{} (HTTP {})", message, status)
+    }
 }
 
 #[async_trait]
@@ -40,34 +142,27 @@ impl LlmProvider for OpenAiProvider {
         self.app_config.llm_provider.clone().unwrap_or_else(|| "OpenAI".to_string())
     }
 
-    async fn generate_commit_message_impl(&self, system_prompt: &str, diff: &str) -> Result<String, LlmError> {
+    async fn generate_commit_message_impl(&self, messages: &[ChatMessage]) -> Result<String, LlmError> {
+        self.generate_completion_impl(messages)
+            .await
+            .map(|completion| completion.content)
+    }
+
+    async fn generate_completion_impl(&self, messages: &[ChatMessage]) -> Result<Completion, LlmError> {
         let api_key = self.get_api_key()?;
-        let client = reqwest::Client::new();
+
+        let messages: Vec<serde_json::Value> = messages
+            .iter()
+            .map(|message| serde_json::json!({"role": message.role.as_str(), "content": message.content}))
+            .collect();
 
         let request_body = serde_json::json!({
             "model": self.config.model,
             "temperature": 0.2,
-            "messages": [
-                {"role": "system", "content": system_prompt},
-                {"role": "user", "content": diff}
-            ]
+            "messages": messages
         });
 
-        let response = client
-            .post(&self.config.endpoint)
-            .bearer_auth(api_key)
-            .json(&request_body)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(LlmError::ApiError(format!(
-                "API request failed with status: {}",
-                response.status()
-            )));
-        }
-
-        let response_data: serde_json::Value = response.json().await?;
+        let response_data = self.post(&api_key, &request_body).await?;
 
         let content = response_data
             .get("choices")
@@ -77,7 +172,19 @@ impl LlmProvider for OpenAiProvider {
             .and_then(|content| content.as_str())
             .ok_or_else(|| LlmError::ApiError("Invalid response format from OpenAI API".to_string()))?;
 
-        Ok(content.to_string())
+        let usage = response_data.get("usage").map(|usage| {
+            let field = |name: &str| usage.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+            TokenUsage {
+                prompt_tokens: field("prompt_tokens"),
+                completion_tokens: field("completion_tokens"),
+                total_tokens: field("total_tokens"),
+            }
+        });
+
+        Ok(Completion {
+            content: content.to_string(),
+            usage,
+        })
     }
 
     fn get_api_key(&self) -> Result<String, LlmError> {
@@ -93,4 +200,81 @@ impl LlmProvider for OpenAiProvider {
     fn get_candidate_count(&self) -> u32 {
         self.app_config.candidate_count.unwrap_or(1)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve `responses` in order, one per connection, and return the endpoint
+    async fn serve(responses: Vec<String>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; 65536];
+                let _ = socket.read(&mut request).await;
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+        });
+        format!("http://{}/v1/chat/completions", address)
+    }
+
+    fn provider(endpoint: String, max_retries: u32) -> OpenAiProvider {
+        let config = Config { api_key: Some("key".to_string()), max_retries: Some(max_retries), ..Default::default() };
+        let mut provider = OpenAiProvider::new(config).unwrap();
+        provider.config.endpoint = endpoint;
+        provider.retry.base_delay = Duration::from_millis(1);
+        provider
+    }
+
+    fn response(status: &str, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\n{}content-type: application/json\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{}",
+            status,
+            headers,
+            body.len(),
+            body
+        )
+    }
+
+    fn rate_limited() -> String {
+        response("429 Too Many Requests", "retry-after: 0\r\n", r#"{"error": {"message": "Slow down"}}"#)
+    }
+
+    fn ok() -> String {
+        response("200 OK", "", r#"{"choices": [{"message": {"content": "feat: add"}}]}"#)
+    }
+
+    #[tokio::test]
+    async fn test_retries_rate_limits() {
+        let provider = provider(serve(vec![rate_limited(), rate_limited(), ok()]).await, 2);
+        let completion = provider.generate_completion_impl(&[ChatMessage::user("diff")]).await.unwrap();
+        assert_eq!(completion.content, "feat: add");
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let provider = provider(serve(vec![rate_limited(), rate_limited()]).await, 1);
+        let error = provider.generate_completion_impl(&[ChatMessage::user("diff")]).await.unwrap_err();
+        assert_eq!(error.code(), "rate_limited");
+        assert!(error.to_string().contains("Slow down"), "{}", error);
+    }
+
+    #[test]
+    fn test_api_error_uses_the_provider_message() {
+        let body = r#"{"error": {"message": "This model's maximum context length is 4097 tokens", "code": "context_length_exceeded"}}"#;
+        assert_eq!(api_error(400, body, None).code(), "context_length_exceeded");
+        assert_eq!(api_error(401, r#"{"error": {"message": "Incorrect API key"}}"#, None).to_string(), "Authentication failed: Incorrect API key");
+        assert_eq!(api_error(429, r#"{"error": {"message": "Quota", "code": "insufficient_quota"}}"#, None).code(), "api_error");
+        assert_eq!(api_error(400, "Bad request", None).to_string(), "API Error: Bad request (HTTP 400)");
+        assert_eq!(api_error(502, "Bad gateway", None).to_string(), "Provider unavailable: Bad gateway (HTTP 502)");
+        assert_eq!(api_error(500, "", None).to_string(), "Provider unavailable: empty response (HTTP 500)");
+        assert_eq!(api_error(503, "503 Service Unavailable", None).to_string(), "Provider unavailable: 503 Service Unavailable");
+    }
+}
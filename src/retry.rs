@@ -0,0 +1,95 @@
+//! Retrying provider requests
+//!
+//! Rate limits (429) and server errors (5xx) are retried with exponential
+//! backoff and full jitter. A `Retry-After` from the provider takes the place
+//! of the backoff, as long as it is not unreasonably long.
+
+use std::time::Duration;
+
+/// Defaults for when the config doesn't set the limits
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 60;
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Longest `Retry-After` that is waited out; a longer one is reported instead
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// How often and how patiently a request is retried
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    /// Upper bound of the first backoff, doubled for every retry
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// How long to wait before retry number `attempt` (starting at 0): the provider's
+    /// `Retry-After` if it sent one, otherwise a random time up to the exponential backoff.
+    /// Both get a little jitter so that parallel clients don't retry in lockstep.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        match retry_after {
+            Some(wait) => wait + self.base_delay.mul_f64(fastrand::f64() / 2.0),
+            None => {
+                let backoff = self.base_delay.saturating_mul(2u32.saturating_pow(attempt)).min(self.max_delay);
+                backoff.mul_f64(fastrand::f64())
+            }
+        }
+    }
+}
+
+/// Whether a response with this HTTP status is worth retrying
+pub fn is_retryable(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+/// A `Retry-After` header in seconds. HTTP dates aren't understood and give `None`;
+/// waits too long to represent become `Duration::MAX`.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let seconds: f64 = value.trim().parse().ok()?;
+    (seconds >= 0.0).then(|| Duration::try_from_secs_f64(seconds).unwrap_or(Duration::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy { max_retries: 5, base_delay: Duration::from_secs(1), max_delay: Duration::from_secs(4) };
+        for _ in 0..50 {
+            assert!(policy.delay(0, None) <= Duration::from_secs(1));
+            assert!(policy.delay(1, None) <= Duration::from_secs(2));
+            assert!(policy.delay(10, None) <= Duration::from_secs(4));
+        }
+    }
+
+    #[test]
+    fn test_retry_after_is_honoured() {
+        let policy = RetryPolicy::default();
+        let delay = policy.delay(0, Some(Duration::from_secs(7)));
+        assert!(delay >= Duration::from_secs(7) && delay <= Duration::from_secs(7) + policy.base_delay);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("3"), Some(Duration::from_secs(3)));
+        assert_eq!(parse_retry_after(" 0.5 "), Some(Duration::from_millis(500)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+        assert_eq!(parse_retry_after("-1"), None);
+        assert_eq!(parse_retry_after("NaN"), None);
+        assert_eq!(parse_retry_after("1e20"), Some(Duration::MAX));
+        assert!(is_retryable(429) && is_retryable(503) && !is_retryable(400));
+    }
+}
@@ -0,0 +1,369 @@
+//! Monorepo scope inference
+//!
+//! Finds the packages of a repository (Cargo workspace members, npm and pnpm
+//! workspace packages, git submodules) and works out which of them the
+//! changed files belong to, so the package name can be used as the
+//! Conventional Commits scope.
+
+use clap::ValueEnum;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::convention::parse_header;
+use crate::gitmoji::split_emoji;
+use crate::diff::parse_diff;
+
+/// How the inferred scope is used
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScopeMode {
+    /// Don't look for packages
+    Off,
+    /// Tell the model which package changed
+    #[default]
+    Suggest,
+    /// Also rewrite the scope of every candidate when one package changed
+    Enforce,
+}
+
+/// A workspace member or submodule
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    pub name: String,
+    /// Directory relative to the repository root, with `/` separators
+    pub path: String,
+}
+
+/// Directories never searched for workspace members
+const SKIPPED_DIRS: &[&str] = &[".git", "node_modules", "target"];
+
+/// How deep `**` patterns descend
+const MAX_GLOB_DEPTH: usize = 6;
+
+/// All packages declared in the repository at `root`
+pub fn discover_packages(root: &Path) -> Vec<Package> {
+    let mut packages = Vec::new();
+
+    for dir in cargo_members(root) {
+        let name = read_toml(&dir.join("Cargo.toml"))
+            .and_then(|manifest| manifest.get("package")?.get("name")?.as_str().map(str::to_string));
+        push_package(&mut packages, root, &dir, name);
+    }
+    for dir in node_members(root) {
+        let name = read_json(&dir.join("package.json"))
+            .and_then(|manifest| manifest.get("name")?.as_str().map(str::to_string))
+            // `@acme/api` is scoped as `api`
+            .map(|name| name.rsplit('/').next().unwrap_or(&name).to_string());
+        push_package(&mut packages, root, &dir, name);
+    }
+    for path in submodule_paths(root) {
+        let name = path.rsplit('/').next().map(str::to_string);
+        push_package(&mut packages, root, &root.join(&path), name);
+    }
+    packages
+}
+
+/// Names of the packages touched by the files in `diff`, in name order.
+/// Files outside every package (lockfiles, CI config) don't count.
+pub fn changed_packages(packages: &[Package], diff: &str) -> Vec<String> {
+    let mut names = BTreeSet::new();
+    for file in parse_diff(diff) {
+        for path in [&file.old_path, &file.new_path].into_iter().flatten() {
+            if let Some(package) = package_for(packages, path) {
+                names.insert(package.name.clone());
+            }
+        }
+    }
+    names.into_iter().collect()
+}
+
+/// The innermost package containing `path`
+fn package_for<'a>(packages: &'a [Package], path: &str) -> Option<&'a Package> {
+    packages
+        .iter()
+        .filter(|package| path == package.path || path.starts_with(&format!("{}/", package.path)))
+        .max_by_key(|package| package.path.len())
+}
+
+/// Guidance for the model about which package the changes belong to, empty if there is none
+pub fn scope_hint(packages: &[String]) -> String {
+    match packages {
+        [] => String::new(),
+        [package] => format!(
+            "Scope: the changed files belong to the package `{0}`. Use it as the scope, e.g. `feat({0}): ...`.\n",
+            package
+        ),
+        _ => format!(
+            "Scope: the changes span several packages ({}). Use the main one as the scope, or leave the scope out.\n",
+            packages.join(", ")
+        ),
+    }
+}
+
+/// Put `scope` into a Conventional Commits subject line, replacing any other scope.
+/// Messages that don't start with `type:` or `type(scope):` are returned unchanged.
+pub fn apply_scope(message: &str, scope: &str) -> String {
+    let (first_line, rest) = match message.split_once('\n') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (message, None),
+    };
+    // Keep a gitmoji prefix where it is
+    let (emoji, subject) = match split_emoji(first_line) {
+        Some((emoji, subject)) => (format!("{} ", emoji), subject),
+        None => (String::new(), first_line),
+    };
+    let Some(header) = parse_header(subject) else {
+        return message.to_string();
+    };
+    let breaking = if header.breaking { "!" } else { "" };
+
+    let mut scoped = format!("{}{}({}){}: {}", emoji, header.kind, scope, breaking, header.description);
+    if let Some(rest) = rest {
+        scoped.push('\n');
+        scoped.push_str(rest);
+    }
+    scoped
+}
+
+fn push_package(packages: &mut Vec<Package>, root: &Path, dir: &Path, name: Option<String>) {
+    let Ok(relative) = dir.strip_prefix(root) else {
+        return;
+    };
+    let path = relative.to_string_lossy().replace('\\', "/");
+    // The root itself isn't a member; it would claim every file
+    if path.is_empty() || packages.iter().any(|package| package.path == path) {
+        return;
+    }
+    let name = name.unwrap_or_else(|| path.rsplit('/').next().unwrap_or(&path).to_string());
+    packages.push(Package { name, path });
+}
+
+/// Member directories of a Cargo workspace
+fn cargo_members(root: &Path) -> Vec<PathBuf> {
+    let Some(manifest) = read_toml(&root.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+    let Some(workspace) = manifest.get("workspace") else {
+        return Vec::new();
+    };
+    let patterns = |key: &str| -> Vec<String> {
+        workspace
+            .get(key)
+            .and_then(|value| value.as_array())
+            .map(|values| values.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+            .unwrap_or_default()
+    };
+    expand_members(root, &patterns("members"), &patterns("exclude"), "Cargo.toml")
+}
+
+/// Package directories of an npm, yarn or pnpm workspace
+fn node_members(root: &Path) -> Vec<PathBuf> {
+    let mut patterns: Vec<String> = Vec::new();
+    if let Some(manifest) = read_json(&root.join("package.json")) {
+        // Either `"workspaces": [...]` or `"workspaces": {"packages": [...]}`
+        let workspaces = manifest.get("workspaces");
+        let list = workspaces.and_then(|w| w.as_array().or_else(|| w.get("packages")?.as_array()));
+        patterns.extend(list.into_iter().flatten().filter_map(|v| v.as_str().map(str::to_string)));
+    }
+    if let Ok(content) = fs::read_to_string(root.join("pnpm-workspace.yaml")) {
+        patterns.extend(pnpm_packages(&content));
+    }
+
+    let (excluded, included): (Vec<String>, Vec<String>) =
+        patterns.into_iter().partition(|pattern| pattern.starts_with('!'));
+    let excluded: Vec<String> = excluded.iter().map(|p| p.trim_start_matches('!').to_string()).collect();
+    expand_members(root, &included, &excluded, "package.json")
+}
+
+/// Entries of the `packages:` list in `pnpm-workspace.yaml`
+fn pnpm_packages(content: &str) -> Vec<String> {
+    let mut packages = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t', '-']) {
+            in_packages = trimmed == "packages:";
+            continue;
+        }
+        if let Some(item) = trimmed.strip_prefix('-').filter(|_| in_packages) {
+            packages.push(item.trim().trim_matches(['"', '\'']).to_string());
+        }
+    }
+    packages
+}
+
+/// Paths of all submodules listed in `.gitmodules`
+fn submodule_paths(root: &Path) -> Vec<String> {
+    let Ok(content) = fs::read_to_string(root.join(".gitmodules")) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "path").then(|| value.trim().trim_end_matches('/').to_string())
+        })
+        .collect()
+}
+
+/// Directories matching any of `patterns` (but none of `excluded`) that contain `manifest`
+fn expand_members(root: &Path, patterns: &[String], excluded: &[String], manifest: &str) -> Vec<PathBuf> {
+    let excluded: Vec<PathBuf> = excluded.iter().flat_map(|pattern| expand_glob(root, pattern)).collect();
+    let mut members: Vec<PathBuf> = patterns
+        .iter()
+        .flat_map(|pattern| expand_glob(root, pattern))
+        .filter(|dir| dir.join(manifest).is_file() && !excluded.contains(dir))
+        .collect();
+    members.sort();
+    members.dedup();
+    members
+}
+
+/// Directories matching a workspace pattern such as `crates/*` or `packages/**`
+fn expand_glob(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let mut current = vec![root.to_path_buf()];
+    for segment in pattern.trim_start_matches("./").trim_end_matches('/').split('/') {
+        let mut next = Vec::new();
+        for dir in &current {
+            match segment {
+                "" | "." => next.push(dir.clone()),
+                "**" => collect_dirs(dir, MAX_GLOB_DEPTH, &mut next),
+                _ if segment.contains(['*', '?']) => {
+                    next.extend(subdirs(dir).into_iter().filter(|sub| {
+                        sub.file_name().is_some_and(|name| wildcard_match(segment, &name.to_string_lossy()))
+                    }));
+                }
+                _ => {
+                    let sub = dir.join(segment);
+                    if sub.is_dir() {
+                        next.push(sub);
+                    }
+                }
+            }
+        }
+        current = next;
+    }
+    current
+}
+
+/// `dir` and all directories below it, up to `depth` levels
+fn collect_dirs(dir: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+    found.push(dir.to_path_buf());
+    if depth == 0 {
+        return;
+    }
+    for sub in subdirs(dir) {
+        collect_dirs(&sub, depth - 1, found);
+    }
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .filter(|path| !path.file_name().is_some_and(|name| SKIPPED_DIRS.contains(&name.to_string_lossy().as_ref())))
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Match `name` against a pattern with `*` and `?` wildcards
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    fn matches(pattern: &[char], name: &[char]) -> bool {
+        match (pattern.first(), name.first()) {
+            (None, None) => true,
+            (Some('*'), _) => matches(&pattern[1..], name) || (!name.is_empty() && matches(pattern, &name[1..])),
+            (Some('?'), Some(_)) => matches(&pattern[1..], &name[1..]),
+            (Some(p), Some(n)) if p == n => matches(&pattern[1..], &name[1..]),
+            _ => false,
+        }
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    matches(&pattern, &name)
+}
+
+fn read_toml(path: &Path) -> Option<toml::Table> {
+    fs::read_to_string(path).ok()?.parse().ok()
+}
+
+fn read_json(path: &Path) -> Option<serde_json::Value> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_discover_packages() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(root, "Cargo.toml", "[workspace]\nmembers = [\"crates/*\", \"tools/cli\"]\nexclude = [\"crates/old\"]\n");
+        write(root, "crates/core/Cargo.toml", "[package]\nname = \"acme-core\"\n");
+        write(root, "crates/old/Cargo.toml", "[package]\nname = \"old\"\n");
+        write(root, "tools/cli/Cargo.toml", "[package]\nname = \"cli\"\n");
+        write(root, "package.json", "{\"workspaces\": [\"web/*\"]}");
+        write(root, "web/app/package.json", "{\"name\": \"@acme/app\"}");
+        write(root, "pnpm-workspace.yaml", "packages:\n  - 'docs/**'\n  - '!docs/drafts'\n");
+        write(root, "docs/site/package.json", "{\"name\": \"site\"}");
+        write(root, "docs/drafts/package.json", "{\"name\": \"drafts\"}");
+        write(root, ".gitmodules", "[submodule \"vendor/lib\"]\n\tpath = vendor/lib\n\turl = https://example.com/lib.git\n");
+
+        let packages = discover_packages(root);
+        let names: Vec<(&str, &str)> = packages.iter().map(|p| (p.name.as_str(), p.path.as_str())).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("acme-core", "crates/core"),
+                ("cli", "tools/cli"),
+                ("site", "docs/site"),
+                ("app", "web/app"),
+                ("lib", "vendor/lib"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_changed_packages() {
+        let packages = vec![
+            Package { name: "api".to_string(), path: "crates/api".to_string() },
+            Package { name: "api-macros".to_string(), path: "crates/api/macros".to_string() },
+            Package { name: "web".to_string(), path: "web".to_string() },
+        ];
+        let diff = "diff --git a/crates/api/macros/src/lib.rs b/crates/api/macros/src/lib.rs\ndiff --git a/Cargo.lock b/Cargo.lock\n";
+        assert_eq!(changed_packages(&packages, diff), vec!["api-macros"]);
+
+        let diff = "diff --git a/crates/api/src/lib.rs b/crates/api/src/lib.rs\ndiff --git a/web/index.ts b/web/index.ts\n";
+        assert_eq!(changed_packages(&packages, diff), vec!["api", "web"]);
+    }
+
+    #[test]
+    fn test_apply_scope() {
+        assert_eq!(apply_scope("feat: add login", "api"), "feat(api): add login");
+        assert_eq!(apply_scope("fix(web)!: drop v1\n\nBody", "api"), "fix(api)!: drop v1\n\nBody");
+        assert_eq!(apply_scope("Add login", "api"), "Add login");
+        assert_eq!(apply_scope("Note: something", "api"), "Note: something");
+        assert_eq!(apply_scope("see http://x: y", "api"), "see http://x: y");
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("pkg-?", "pkg-a"));
+        assert!(!wildcard_match("pkg-*", "lib-a"));
+    }
+}
@@ -0,0 +1,381 @@
+//! `committo split`: turn one pile of staged changes into several commits
+//!
+//! The provider groups the staged hunks into logical changes. After review,
+//! each group is staged on top of HEAD with `git apply --cached` and committed
+//! in turn. The original index is snapshotted with `git write-tree` so that it
+//! can be restored exactly if anything fails along the way.
+
+use inquire::{Select, Text};
+use serde::Deserialize;
+use std::io;
+
+use crate::api::{ChatMessage, LlmProvider};
+use crate::commit::{self, CommitOptions};
+use crate::convention::Convention;
+use crate::diff::{parse_diff, FileChange, FileDiff};
+use crate::error::RunError;
+use crate::git;
+use crate::prompt::{convention_guidance, Prompt};
+
+/// Message used for hunks the provider didn't assign to any group
+const REMAINING_CHANGES_MESSAGE: &str = "chore: remaining changes";
+
+/// A hunk, or a whole file when the change has no hunks (binary, rename, mode change)
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Unit {
+    file: usize,
+    hunk: Option<usize>,
+}
+
+/// One proposed commit; `hunks` are 1-based unit numbers as shown to the provider
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SplitGroup {
+    pub message: String,
+    pub hunks: Vec<usize>,
+}
+
+/// Staged changes together with the proposed grouping
+struct SplitPlan {
+    files: Vec<FileDiff>,
+    units: Vec<Unit>,
+    groups: Vec<SplitGroup>,
+}
+
+impl SplitPlan {
+    /// Short label for a unit, e.g. `[3] src/api.rs @@ -10,4 +10,6 @@ fn run()`
+    fn unit_label(&self, number: usize) -> String {
+        let unit = self.units[number - 1];
+        let file = &self.files[unit.file];
+        match unit.hunk {
+            Some(hunk) => format!("[{}] {} {}", number, file.path(), file.hunks[hunk].header),
+            None => format!("[{}] {} (whole file)", number, file.path()),
+        }
+    }
+
+    /// Render the plan for review
+    fn render(&self) -> String {
+        let mut output = String::new();
+        for (i, group) in self.groups.iter().enumerate() {
+            output.push_str(&format!("Commit {}: {}\n", i + 1, group.message));
+            for &number in &group.hunks {
+                output.push_str(&format!("    {}\n", self.unit_label(number)));
+            }
+        }
+        output
+    }
+
+    /// Patch containing only the hunks of `group`
+    fn group_patch(&self, group: &SplitGroup) -> String {
+        let mut patch = String::new();
+        for (file_index, file) in self.files.iter().enumerate() {
+            let selected: Vec<&Unit> = group
+                .hunks
+                .iter()
+                .map(|&number| &self.units[number - 1])
+                .filter(|unit| unit.file == file_index)
+                .collect();
+            if selected.is_empty() {
+                continue;
+            }
+            if selected.iter().any(|unit| unit.hunk.is_none()) {
+                patch.push_str(&file.render(None));
+            } else {
+                let hunks: Vec<usize> = selected.iter().filter_map(|unit| unit.hunk).collect();
+                patch.push_str(&file.render(Some(&hunks)));
+            }
+        }
+        patch
+    }
+
+    /// Drop groups that no longer contain any hunks
+    fn remove_empty_groups(&mut self) {
+        self.groups.retain(|group| !group.hunks.is_empty());
+    }
+}
+
+/// Header lines after which a file's hunks can't be applied separately
+const WHOLE_FILE_MARKERS: &[&str] = &["new file mode", "deleted file mode", "rename from", "copy from", "old mode"];
+
+/// Split every file into independently committable units
+fn collect_units(files: &[FileDiff]) -> Vec<Unit> {
+    let mut units = Vec::new();
+    for (file, diff) in files.iter().enumerate() {
+        let whole_file = diff
+            .header
+            .iter()
+            .any(|line| WHOLE_FILE_MARKERS.iter().any(|marker| line.starts_with(marker)));
+        if diff.hunks.is_empty() || whole_file {
+            units.push(Unit { file, hunk: None });
+        } else {
+            units.extend((0..diff.hunks.len()).map(|hunk| Unit { file, hunk: Some(hunk) }));
+        }
+    }
+    units
+}
+
+/// Numbered listing of all units, sent to the provider as the user message
+fn describe_units(files: &[FileDiff], units: &[Unit]) -> String {
+    let mut output = String::new();
+    for (i, unit) in units.iter().enumerate() {
+        let file = &files[unit.file];
+        let section = unit.hunk.and_then(|hunk| file.hunks[hunk].section());
+        match section {
+            Some(section) => output.push_str(&format!("### Hunk {}: {} (in {})\n", i + 1, file.path(), section)),
+            None => output.push_str(&format!("### Hunk {}: {}\n", i + 1, file.path())),
+        }
+        let hunks = match unit.hunk {
+            Some(hunk) => vec![&file.hunks[hunk]],
+            None => {
+                // Describe the header in words, never the binary patch payload
+                output.push_str(&FileChange::from_diff(file).describe());
+                output.push('\n');
+                file.hunks.iter().collect()
+            }
+        };
+        for hunk in hunks {
+            output.push_str(&hunk.header);
+            output.push('\n');
+            for line in &hunk.lines {
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// System prompt asking the provider to group hunks into commits
+fn split_system_prompt(convention: &Convention) -> String {
+    let mut prompt = "You are an AI assistant that helps programmers split a large set of staged changes into small, atomic commits. \
+The user lists numbered hunks of a git diff. Group hunks that belong to the same logical change and write a concise, informative commit message for each group.\n\n\
+Reply with only a JSON array and nothing else, in this form:\n\
+[{\"message\": \"<commit message>\", \"hunks\": [1, 2]}, {\"message\": \"<commit message>\", \"hunks\": [3]}]\n\
+Every hunk number must appear in exactly one group. Order the groups so that each commit makes sense on top of the previous ones."
+        .to_string();
+
+    let conventions = convention_guidance(convention);
+    if !conventions.is_empty() {
+        prompt = format!("{}\n\n{}", prompt, conventions);
+    }
+    prompt
+}
+
+/// Parse the provider's grouping, repairing what can be repaired:
+/// out-of-range and repeated hunk numbers are dropped, and hunks left
+/// out entirely are collected into a final group.
+pub fn parse_groups(response: &str, unit_count: usize) -> Result<Vec<SplitGroup>, String> {
+    let start = response.find('[').ok_or("response contains no JSON array")?;
+    let end = response.rfind(']').ok_or("response contains no JSON array")?;
+    let mut groups: Vec<SplitGroup> = serde_json::from_str(&response[start..=end])
+        .map_err(|e| format!("invalid grouping JSON: {}", e))?;
+
+    let mut assigned = vec![false; unit_count];
+    for group in &mut groups {
+        group.message = group.message.trim().to_string();
+        group.hunks.retain(|&number| {
+            let valid = (1..=unit_count).contains(&number) && !assigned[number - 1];
+            if valid {
+                assigned[number - 1] = true;
+            }
+            valid
+        });
+    }
+    groups.retain(|group| !group.hunks.is_empty());
+
+    let remaining: Vec<usize> = (1..=unit_count).filter(|&n| !assigned[n - 1]).collect();
+    if !remaining.is_empty() {
+        groups.push(SplitGroup {
+            message: REMAINING_CHANGES_MESSAGE.to_string(),
+            hunks: remaining,
+        });
+    }
+    Ok(groups)
+}
+
+/// Run `committo split`
+pub async fn run_split(
+    provider: &dyn LlmProvider,
+    dry_run: bool,
+    assume_yes: bool,
+    interactive: bool,
+    options: &CommitOptions,
+) -> Result<(), RunError> {
+    let patch = git::get_staged_patch()?;
+    let convention = Convention::for_changes(&patch)?.with_config(provider.get_app_config())?;
+    let files = parse_diff(&patch);
+    if files.is_empty() && !dry_run {
+        return Err(RunError::NoStagedChanges);
+    }
+    if !dry_run && !assume_yes && !interactive {
+        return Err(RunError::NotInteractive(
+            "stdin is not a terminal; use --yes to accept the proposed split without review".to_string(),
+        ));
+    }
+
+    let units = collect_units(&files);
+    let system_prompt = split_system_prompt(&convention);
+    let listing = describe_units(&files, &units);
+
+    // Always check API key first, even for dry run
+    provider.get_api_key()?;
+    if dry_run {
+        provider.print_dry_run_info(&Prompt { convention, ..Default::default() }, &system_prompt, &listing);
+        return Ok(());
+    }
+
+    let messages = [ChatMessage::system(system_prompt), ChatMessage::user(listing)];
+    let completion = provider.continue_conversation(&messages).await?;
+    let groups = parse_groups(&completion.content, units.len()).map_err(|e| {
+        RunError::Io(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Could not understand the proposed split: {}", e),
+        ))
+    })?;
+
+    let mut plan = SplitPlan { files, units, groups };
+    if !assume_yes && !review(&mut plan)? {
+        println!("Split cancelled; the index is unchanged.");
+        return Ok(());
+    }
+
+    commit_plan(&plan, options)
+}
+
+const REVIEW_ACCEPT: &str = "✅ Create these commits";
+const REVIEW_EDIT: &str = "✏️  Edit a commit message";
+const REVIEW_MOVE: &str = "🔀 Move a hunk to another commit";
+const REVIEW_ABORT: &str = "❌ Abort";
+
+/// Let the user adjust the plan. Returns `false` if they aborted.
+fn review(plan: &mut SplitPlan) -> Result<bool, RunError> {
+    loop {
+        println!("\n{}", plan.render());
+        let action = Select::new(
+            "Proposed commits:",
+            vec![REVIEW_ACCEPT, REVIEW_EDIT, REVIEW_MOVE, REVIEW_ABORT],
+        )
+        .prompt()?;
+
+        match action {
+            REVIEW_ACCEPT => return Ok(true),
+            REVIEW_EDIT => {
+                let Some(index) = choose_group(plan, "Which commit?", false)? else {
+                    continue;
+                };
+                let edited = Text::new("Commit message:")
+                    .with_initial_value(&plan.groups[index].message)
+                    .prompt_skippable()?;
+                if let Some(edited) = edited.filter(|m| !m.trim().is_empty()) {
+                    plan.groups[index].message = edited.trim().to_string();
+                }
+            }
+            REVIEW_MOVE => {
+                let labels: Vec<String> = (1..=plan.units.len()).map(|n| plan.unit_label(n)).collect();
+                let Some(hunk) = prompt_index("Which hunk?", labels)? else {
+                    continue;
+                };
+                let number = hunk + 1;
+                let Some(target) = choose_group(plan, "Move it to:", true)? else {
+                    continue;
+                };
+
+                for group in &mut plan.groups {
+                    group.hunks.retain(|&n| n != number);
+                }
+                if target == plan.groups.len() {
+                    let message = Text::new("Message for the new commit:").prompt()?;
+                    plan.groups.push(SplitGroup { message: message.trim().to_string(), hunks: vec![number] });
+                } else {
+                    plan.groups[target].hunks.push(number);
+                    plan.groups[target].hunks.sort_unstable();
+                }
+                plan.remove_empty_groups();
+            }
+            _ => return Ok(false),
+        }
+    }
+}
+
+/// Ask for a group; with `allow_new`, the last option (index == groups.len()) creates one
+fn choose_group(plan: &SplitPlan, prompt: &str, allow_new: bool) -> Result<Option<usize>, RunError> {
+    let mut options: Vec<String> = plan
+        .groups
+        .iter()
+        .enumerate()
+        .map(|(i, group)| format!("Commit {}: {}", i + 1, group.message))
+        .collect();
+    if allow_new {
+        options.push("➕ New commit".to_string());
+    }
+    prompt_index(prompt, options)
+}
+
+/// Show a menu and return the chosen index, or `None` if the user pressed Esc
+fn prompt_index(prompt: &str, options: Vec<String>) -> Result<Option<usize>, RunError> {
+    match Select::new(prompt, options).raw_prompt() {
+        Ok(option) => Ok(Some(option.index)),
+        Err(inquire::InquireError::OperationCanceled) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Create one commit per group, restoring HEAD and the index if anything fails
+fn commit_plan(plan: &SplitPlan, options: &CommitOptions) -> Result<(), RunError> {
+    let original_head = git::head_commit()?;
+    let original_tree = git::write_index_tree()?;
+
+    let result = (|| -> io::Result<()> {
+        // Start from HEAD and stage each group in turn
+        git::read_index_tree(original_head.as_deref())?;
+        for (i, group) in plan.groups.iter().enumerate() {
+            git::apply_to_index(&plan.group_patch(group)).map_err(|e| {
+                io::Error::other(format!("Could not stage commit {} ({}): {}", i + 1, group.message, e))
+            })?;
+            commit::execute_git_commit(&group.message, options)?;
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        eprintln!("Split failed, restoring the original HEAD and index...");
+        git::reset_head(original_head.as_deref())?;
+        git::read_index_tree(Some(&original_tree))?;
+        return Err(e.into());
+    }
+
+    // Anything not covered by the groups stays staged
+    if git::write_index_tree()? != original_tree {
+        git::read_index_tree(Some(&original_tree))?;
+        println!("Some staged changes were not part of any commit and are still staged.");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_groups_from_fenced_json() {
+        let response = "```json\n[{\"message\": \"feat: a\", \"hunks\": [1, 3]}, {\"message\": \"fix: b\", \"hunks\": [2]}]\n```";
+        let groups = parse_groups(response, 3).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0], SplitGroup { message: "feat: a".to_string(), hunks: vec![1, 3] });
+        assert_eq!(groups[1].hunks, vec![2]);
+    }
+
+    #[test]
+    fn test_parse_groups_repairs_assignment() {
+        let response = "[{\"message\": \"feat: a\", \"hunks\": [1, 1, 9]}, {\"message\": \"fix: b\", \"hunks\": [1]}]";
+        let groups = parse_groups(response, 3).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].hunks, vec![1]);
+        assert_eq!(groups[1], SplitGroup { message: REMAINING_CHANGES_MESSAGE.to_string(), hunks: vec![2, 3] });
+    }
+
+    #[test]
+    fn test_parse_groups_rejects_garbage() {
+        assert!(parse_groups("I cannot do that", 2).is_err());
+    }
+}
@@ -0,0 +1,411 @@
+//! Structural change summaries
+//!
+//! Parses the old and new versions of changed Rust, TypeScript, Python and Go
+//! files with tree-sitter and lists the functions, types and public signatures
+//! that were added, removed or modified. Line diffs hide this, especially once
+//! they are long.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use tree_sitter::{Language, Node, Parser};
+
+use crate::diff::parse_diff;
+use crate::git::{self, ChangeSet};
+
+/// Entries listed before the rest are only counted
+const MAX_ENTRIES: usize = 40;
+
+/// Larger files are skipped rather than parsed
+const MAX_FILE_BYTES: usize = 512 * 1024;
+
+/// Longest signature shown before it is cut off
+const MAX_SIGNATURE_CHARS: usize = 160;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lang {
+    Rust,
+    TypeScript,
+    Tsx,
+    Python,
+    Go,
+}
+
+impl Lang {
+    fn from_path(path: &str) -> Option<Self> {
+        match Path::new(path).extension()?.to_str()? {
+            "rs" => Some(Lang::Rust),
+            "ts" | "mts" | "cts" => Some(Lang::TypeScript),
+            "tsx" => Some(Lang::Tsx),
+            "py" | "pyi" => Some(Lang::Python),
+            "go" => Some(Lang::Go),
+            _ => None,
+        }
+    }
+
+    fn language(self) -> Language {
+        match self {
+            Lang::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Lang::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Lang::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Lang::Python => tree_sitter_python::LANGUAGE.into(),
+            Lang::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+
+    /// The kind of symbol a syntax node defines, if it defines one
+    fn symbol_kind(self, node_kind: &str) -> Option<&'static str> {
+        let kind = match (self, node_kind) {
+            (Lang::Rust, "function_item" | "function_signature_item") => "fn",
+            (Lang::Rust, "struct_item") => "struct",
+            (Lang::Rust, "enum_item") => "enum",
+            (Lang::Rust, "trait_item") => "trait",
+            (Lang::Rust, "type_item") => "type",
+            (Lang::Rust, "const_item") => "const",
+            (Lang::Rust, "static_item") => "static",
+            (Lang::Rust, "macro_definition") => "macro",
+            (Lang::TypeScript | Lang::Tsx, "function_declaration" | "generator_function_declaration") => "function",
+            (Lang::TypeScript | Lang::Tsx, "method_definition" | "method_signature" | "abstract_method_signature") => "method",
+            (Lang::TypeScript | Lang::Tsx, "class_declaration" | "abstract_class_declaration") => "class",
+            (Lang::TypeScript | Lang::Tsx, "interface_declaration") => "interface",
+            (Lang::TypeScript | Lang::Tsx, "type_alias_declaration") => "type",
+            (Lang::TypeScript | Lang::Tsx, "enum_declaration") => "enum",
+            (Lang::Python, "function_definition") => "def",
+            (Lang::Python, "class_definition") => "class",
+            (Lang::Go, "function_declaration") => "func",
+            (Lang::Go, "method_declaration") => "method",
+            (Lang::Go, "type_spec") => "type",
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    /// Whether members of this symbol are listed as symbols of their own
+    fn is_container(self, symbol_kind: &str) -> bool {
+        matches!(symbol_kind, "class" | "trait" | "interface")
+    }
+
+    fn separator(self) -> &'static str {
+        match self {
+            Lang::Rust => "::",
+            _ => ".",
+        }
+    }
+}
+
+/// A function or type found in one version of a file
+#[derive(Debug, Clone)]
+struct Symbol {
+    kind: &'static str,
+    name: String,
+    signature: String,
+    /// Whole definition with whitespace collapsed, so reformatting isn't a change
+    text: String,
+    public: bool,
+    container: bool,
+}
+
+impl Symbol {
+    fn describe(&self) -> String {
+        let visibility = if self.public { "public " } else { "" };
+        format!("{}{} `{}`", visibility, self.kind, self.name)
+    }
+}
+
+/// Prepend the structural summary of `changes` to the diff sent to the provider
+pub fn with_summary(changes: &ChangeSet, diff: String) -> String {
+    let summary = summarize(changes);
+    if summary.is_empty() {
+        diff
+    } else {
+        format!("{}\n{}", summary, diff)
+    }
+}
+
+/// Summary of structural changes in the changed source files, empty if there are none
+pub fn summarize(changes: &ChangeSet) -> String {
+    let mut lines = Vec::new();
+    let mut entry_count = 0;
+    let mut omitted = 0;
+
+    for file in parse_diff(&changes.diff) {
+        let Some(lang) = Lang::from_path(file.path()) else {
+            continue;
+        };
+        let old = file.old_path.as_deref().and_then(git::head_file);
+        let new = file.new_path.as_deref().and_then(|path| changes.staged_file(path));
+        let entries = compare(lang, old.as_deref(), new.as_deref());
+        if entries.is_empty() {
+            continue;
+        }
+
+        let room = MAX_ENTRIES.saturating_sub(entry_count);
+        omitted += entries.len().saturating_sub(room);
+        if room == 0 {
+            continue;
+        }
+        lines.push(file.path().to_string());
+        for entry in entries.into_iter().take(room) {
+            lines.push(format!("  {}", entry));
+            entry_count += 1;
+        }
+    }
+
+    if lines.is_empty() {
+        return String::new();
+    }
+    if omitted > 0 {
+        lines.push(format!("  ... and {} more", omitted));
+    }
+    format!("Structural changes (from parsing the changed files):\n{}\n", lines.join("\n"))
+}
+
+/// Describe how the symbols of one file changed between two versions
+fn compare(lang: Lang, old: Option<&str>, new: Option<&str>) -> Vec<String> {
+    let old_symbols = old.map(|source| symbols(lang, source)).unwrap_or_default();
+    let new_symbols = new.map(|source| symbols(lang, source)).unwrap_or_default();
+    let mut entries = Vec::new();
+
+    for (key, symbol) in &new_symbols {
+        match old_symbols.get(key) {
+            None => entries.push(format!("added {}: `{}`", symbol.describe(), symbol.signature)),
+            Some(previous) if previous.signature != symbol.signature => entries.push(format!(
+                "changed signature of {}: `{}` -> `{}`",
+                symbol.describe(),
+                previous.signature,
+                symbol.signature
+            )),
+            // Changes inside a class are reported for its members
+            Some(previous) if previous.text != symbol.text && !symbol.container => {
+                entries.push(format!("changed body of {}", symbol.describe()))
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, symbol) in &old_symbols {
+        if !new_symbols.contains_key(key) {
+            entries.push(format!("removed {}", symbol.describe()));
+        }
+    }
+    entries
+}
+
+/// All symbols in `source`, keyed by kind and qualified name
+fn symbols(lang: Lang, source: &str) -> BTreeMap<String, Symbol> {
+    let mut found = BTreeMap::new();
+    if source.len() > MAX_FILE_BYTES {
+        return found;
+    }
+    let mut parser = Parser::new();
+    if parser.set_language(&lang.language()).is_err() {
+        return found;
+    }
+    let Some(tree) = parser.parse(source, None) else {
+        return found;
+    };
+    collect(lang, source, tree.root_node(), &mut Vec::new(), &mut found);
+    found
+}
+
+/// Walk `node`, recording definitions; `scope` holds enclosing type names and whether they are public
+fn collect(
+    lang: Lang,
+    source: &str,
+    node: Node,
+    scope: &mut Vec<(String, bool)>,
+    found: &mut BTreeMap<String, Symbol>,
+) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if let Some(kind) = lang.symbol_kind(child.kind()) {
+            let Some(name) = symbol_name(source, child) else {
+                continue;
+            };
+            let qualifier = match (lang, child.kind()) {
+                (Lang::Go, "method_declaration") => go_receiver(source, child),
+                _ => None,
+            };
+            let mut path: Vec<&str> = scope.iter().map(|(name, _)| name.as_str()).collect();
+            path.extend(qualifier.as_deref());
+            path.push(&name);
+
+            let container = lang.is_container(kind);
+            let public = is_public(lang, source, child, &name, scope);
+            let symbol = Symbol {
+                kind,
+                name: path.join(lang.separator()),
+                signature: signature(source, child),
+                text: collapse_whitespace(node_text(source, child)),
+                public,
+                container,
+            };
+            found.insert(format!("{} {}", kind, symbol.name), symbol);
+
+            if container {
+                // Go straight to the body, which may look like a function body (Python's `block`)
+                let body = child.child_by_field_name("body").unwrap_or(child);
+                scope.push((name, public));
+                collect(lang, source, body, scope, found);
+                scope.pop();
+            }
+        } else if lang == Lang::Rust && matches!(child.kind(), "impl_item" | "mod_item") {
+            let field = if child.kind() == "impl_item" { "type" } else { "name" };
+            let Some(name) = child.child_by_field_name(field).map(|n| strip_generics(node_text(source, n))) else {
+                continue;
+            };
+            // Inherent methods carry their own `pub`; trait members follow the trait
+            scope.push((name, true));
+            collect(lang, source, child, scope, found);
+            scope.pop();
+        } else if !is_function_body(child.kind()) {
+            // Pass through wrappers like `export`, decorators and declaration lists
+            collect(lang, source, child, scope, found);
+        }
+    }
+}
+
+/// Blocks of code that can only contain local definitions
+fn is_function_body(kind: &str) -> bool {
+    matches!(kind, "block" | "statement_block" | "function_body" | "closure_expression" | "arrow_function")
+}
+
+fn symbol_name(source: &str, node: Node) -> Option<String> {
+    let name = node_text(source, node.child_by_field_name("name")?);
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Receiver type of a Go method, e.g. `Server` for `func (s *Server) Run()`
+fn go_receiver(source: &str, node: Node) -> Option<String> {
+    let receiver = node_text(source, node.child_by_field_name("receiver")?);
+    let receiver = receiver.trim_matches(|c| c == '(' || c == ')');
+    let type_name = receiver.split_whitespace().last()?.trim_start_matches('*');
+    Some(strip_generics(type_name))
+}
+
+fn is_public(lang: Lang, source: &str, node: Node, name: &str, scope: &[(String, bool)]) -> bool {
+    let scope_public = scope.last().is_none_or(|(_, public)| *public);
+    match lang {
+        Lang::Rust => {
+            let mut cursor = node.walk();
+            let has_pub = node
+                .children(&mut cursor)
+                .any(|child| child.kind() == "visibility_modifier" && node_text(source, child).starts_with("pub"));
+            // Trait members have no modifier of their own
+            has_pub || (scope_public && is_trait_member(node))
+        }
+        Lang::TypeScript | Lang::Tsx => {
+            if scope.is_empty() {
+                node.parent().is_some_and(|parent| parent.kind() == "export_statement")
+            } else {
+                let text = node_text(source, node);
+                scope_public && !text.starts_with("private") && !text.starts_with("protected") && !name.starts_with('#')
+            }
+        }
+        Lang::Python => scope_public && (!name.starts_with('_') || (name.starts_with("__") && name.ends_with("__"))),
+        Lang::Go => name.starts_with(|c: char| c.is_uppercase()),
+    }
+}
+
+/// Whether a Rust item sits directly inside a trait (or trait impl) body
+fn is_trait_member(node: Node) -> bool {
+    node.parent()
+        .and_then(|list| list.parent())
+        .is_some_and(|owner| owner.kind() == "trait_item" || owner.child_by_field_name("trait").is_some())
+}
+
+/// Definition header: everything before the body, whitespace collapsed
+fn signature(source: &str, node: Node) -> String {
+    let end = node
+        .child_by_field_name("body")
+        .map(|body| body.start_byte())
+        .unwrap_or(node.end_byte());
+    let header = collapse_whitespace(&source[node.start_byte()..end]);
+    let header = header.trim_end_matches([':', '{', ';', ' ']).to_string();
+    if header.chars().count() > MAX_SIGNATURE_CHARS {
+        let cut: String = header.chars().take(MAX_SIGNATURE_CHARS).collect();
+        format!("{}…", cut)
+    } else {
+        header
+    }
+}
+
+fn node_text<'a>(source: &'a str, node: Node) -> &'a str {
+    &source[node.byte_range()]
+}
+
+fn strip_generics(name: &str) -> String {
+    name.split(['<', '[']).next().unwrap_or(name).trim().to_string()
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rust_added_changed_and_removed() {
+        let old = "pub struct ProviderFactory;\nimpl ProviderFactory {\n    pub fn create() {}\n    fn helper() { let a = 1; }\n}\nfn gone() {}\n";
+        let new = "pub struct ProviderFactory;\nimpl ProviderFactory {\n    pub fn create(name: &str) {}\n    fn helper() { let a = 2; }\n    pub fn register(name: &str) -> Self { todo!() }\n}\n";
+        let entries = compare(Lang::Rust, Some(old), Some(new));
+        assert_eq!(
+            entries,
+            vec![
+                "changed signature of public fn `ProviderFactory::create`: `pub fn create()` -> `pub fn create(name: &str)`",
+                "changed body of fn `ProviderFactory::helper`",
+                "added public fn `ProviderFactory::register`: `pub fn register(name: &str) -> Self`",
+                "removed fn `gone`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reformatting_is_not_a_change() {
+        let old = "fn run() { let a = 1; }\n";
+        let new = "fn run() {\n    let a = 1;\n}\n";
+        assert!(compare(Lang::Rust, Some(old), Some(new)).is_empty());
+    }
+
+    #[test]
+    fn test_python_methods_and_privacy() {
+        let new = "class Client:\n    def send(self, data):\n        pass\n    def _retry(self):\n        pass\n";
+        let entries = compare(Lang::Python, None, Some(new));
+        assert_eq!(
+            entries,
+            vec![
+                "added public class `Client`: `class Client`",
+                "added def `Client._retry`: `def _retry(self)`",
+                "added public def `Client.send`: `def send(self, data)`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_go_methods_use_receiver() {
+        let new = "package main\ntype Server struct{}\nfunc (s *Server) Run() error { return nil }\nfunc helper() {}\n";
+        let entries = compare(Lang::Go, None, Some(new));
+        assert!(entries.contains(&"added public method `Server.Run`: `func (s *Server) Run() error`".to_string()));
+        assert!(entries.contains(&"added func `helper`: `func helper()`".to_string()));
+    }
+
+    #[test]
+    fn test_typescript_exports() {
+        let old = "export function load(path: string) {}\nfunction local() {}\n";
+        let new = "export function load(path: string, strict: boolean) {}\nfunction local() {}\nexport interface Options { strict: boolean }\n";
+        let entries = compare(Lang::TypeScript, Some(old), Some(new));
+        assert_eq!(
+            entries,
+            vec![
+                "changed signature of public function `load`: `function load(path: string)` -> `function load(path: string, strict: boolean)`",
+                "added public interface `Options`: `interface Options`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsupported_files_are_ignored() {
+        assert_eq!(Lang::from_path("README.md"), None);
+        assert_eq!(Lang::from_path("src/main.rs"), Some(Lang::Rust));
+    }
+}
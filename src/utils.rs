@@ -1,6 +1,10 @@
+/// Line between candidates that have a body, as asked for by the system prompt
+pub const CANDIDATE_SEPARATOR: &str = "---";
+
 /// Parse commit message response into candidates
 /// If response contains multiple lines, split them into separate candidates
-/// Otherwise, return the single response as one candidate
+/// Otherwise, return the single response as one candidate.
+/// Candidates separated by [`CANDIDATE_SEPARATOR`] lines keep their bodies.
 pub fn parse_commit_message_candidates(response: &str, expected_count: u32) -> Vec<String> {
     let trimmed = response.trim();
     
@@ -8,17 +12,21 @@ pub fn parse_commit_message_candidates(response: &str, expected_count: u32) -> V
     if expected_count == 1 {
         return vec![trimmed.to_string()];
     }
+
+    if trimmed.lines().any(|line| line.trim() == CANDIDATE_SEPARATOR) {
+        return trimmed
+            .lines()
+            .collect::<Vec<_>>()
+            .split(|line| line.trim() == CANDIDATE_SEPARATOR)
+            .map(|block| strip_numbering(block.join("\n").trim()).to_string())
+            .filter(|candidate| !candidate.is_empty())
+            .collect();
+    }
     
     // Split by lines and filter out empty lines
     let lines: Vec<String> = trimmed
         .lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty())
-        .map(|line| {
-            // Remove common numbering patterns like "1. ", "- ", etc.
-            let line = line.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.' || c == ')' || c == '-' || c == '*' || c == ' ');
-            line.trim().to_string()
-        })
+        .map(|line| strip_numbering(line.trim()).to_string())
         .filter(|line| !line.is_empty())
         .collect();
     
@@ -31,6 +39,21 @@ pub fn parse_commit_message_candidates(response: &str, expected_count: u32) -> V
     }
 }
 
+/// Join candidates into a response that [`parse_commit_message_candidates`] splits the same way
+pub fn join_candidates(candidates: &[String]) -> String {
+    if candidates.iter().any(|candidate| candidate.contains('\n')) {
+        candidates.join(&format!("\n{}\n", CANDIDATE_SEPARATOR))
+    } else {
+        candidates.join("\n")
+    }
+}
+
+/// Remove common numbering patterns like "1. ", "- ", etc.
+fn strip_numbering(line: &str) -> &str {
+    line.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.' || c == ')' || c == '-' || c == '*' || c == ' ')
+        .trim()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -90,4 +113,18 @@ mod tests {
         assert_eq!(candidates[1], "feat: add login validation");
         assert_eq!(candidates[2], "refactor: improve auth flow");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_separated_candidates_keep_their_bodies() {
+        let response = "1. feat: add login\n\n로그인 화면을 추가합니다.\n---\n2. feat: add sign-in screen\n\n로그인 화면을 추가하고\n세션을 저장합니다.";
+        let candidates = parse_commit_message_candidates(response, 2);
+        assert_eq!(
+            candidates,
+            vec!["feat: add login\n\n로그인 화면을 추가합니다.", "feat: add sign-in screen\n\n로그인 화면을 추가하고\n세션을 저장합니다."]
+        );
+        assert_eq!(parse_commit_message_candidates(&join_candidates(&candidates), 2), candidates);
+
+        let subjects = vec!["feat: a".to_string(), "fix: b".to_string()];
+        assert_eq!(join_candidates(&subjects), "feat: a\nfix: b");
+    }
+}
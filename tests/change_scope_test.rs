@@ -0,0 +1,75 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+use committo::commit::{execute_git_commit, CommitOptions};
+use committo::git::{prepare_changes, ChangeScope, DiffOptions};
+
+fn git(repo: &Path, args: &[&str]) -> String {
+    let output = Command::new("git").current_dir(repo).args(args).output().unwrap();
+    assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Repo with two committed files; `a.txt` staged, `b.txt` modified but unstaged, `c.txt` untracked
+fn setup_repo(repo: &Path) {
+    git(repo, &["init", "-q"]);
+    git(repo, &["config", "user.name", "Test User"]);
+    git(repo, &["config", "user.email", "test@example.com"]);
+    fs::write(repo.join("a.txt"), "a\n").unwrap();
+    fs::write(repo.join("b.txt"), "b\n").unwrap();
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "-q", "-m", "initial"]);
+}
+
+fn change_files(repo: &Path) {
+    fs::write(repo.join("a.txt"), "a changed\n").unwrap();
+    git(repo, &["add", "a.txt"]);
+    fs::write(repo.join("b.txt"), "b changed\n").unwrap();
+    fs::write(repo.join("c.txt"), "c\n").unwrap();
+}
+
+fn commit(scope: &ChangeScope, message: &str) -> Vec<String> {
+    let changes = prepare_changes(scope, &DiffOptions::default()).unwrap();
+    let options = CommitOptions { run_edit: false, index_file: changes.index_file.clone(), ..Default::default() };
+    execute_git_commit(message, &options).unwrap();
+    changes.finish_commit().unwrap();
+    changes.files.clone()
+}
+
+// A single test, since it changes the process working directory
+#[test]
+fn test_change_scopes_commit_exactly_what_was_described() {
+    let temp_dir = tempdir().unwrap();
+    let repo = temp_dir.path();
+    setup_repo(repo);
+    env::set_current_dir(repo).unwrap();
+
+    // Staged only: the default
+    change_files(repo);
+    let changes = prepare_changes(&ChangeScope::default(), &DiffOptions::default()).unwrap();
+    assert!(changes.index_file.is_none());
+    assert_eq!(changes.files, vec!["M  a.txt"]);
+    drop(changes);
+
+    // Paths: only b.txt is committed, the staged a.txt stays staged
+    let files = commit(&ChangeScope { paths: vec!["b.txt".to_string()], ..Default::default() }, "update b");
+    assert_eq!(files, vec!["M  b.txt"]);
+    assert_eq!(git(repo, &["show", "--name-only", "--format=", "HEAD"]), "b.txt");
+    assert_eq!(git(repo, &["diff", "--staged", "--name-only"]), "a.txt");
+    assert_eq!(git(repo, &["status", "--porcelain", "b.txt"]), "");
+
+    // --all picks up tracked changes but leaves untracked files alone
+    fs::write(repo.join("b.txt"), "b again\n").unwrap();
+    let files = commit(&ChangeScope { all: true, ..Default::default() }, "update a and b");
+    assert_eq!(files, vec!["M  a.txt", "M  b.txt"]);
+    assert_eq!(git(repo, &["status", "--porcelain"]), "?? c.txt");
+
+    // --include-untracked adds new files
+    let files = commit(&ChangeScope { include_untracked: true, ..Default::default() }, "add c");
+    assert_eq!(files, vec!["A  c.txt"]);
+    assert_eq!(git(repo, &["status", "--porcelain"]), "");
+
+    assert!(!repo.join(".git/committo-index").exists());
+}
@@ -1,7 +1,7 @@
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
 use std::fs;
-use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
 use std::process::Command;
 use tempfile::tempdir;
 use committo::config::{CONFIG_FILE_NAME, CONVENTION_FILE_NAME};
@@ -24,29 +24,41 @@ fn test_set_command() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Git repository in `dir` with `paths` created and staged
+fn init_repo(dir: &Path, paths: &[&str]) {
+    let git = |args: &[&str]| {
+        let status = Command::new("git").current_dir(dir).args(args).output().unwrap().status;
+        assert!(status.success(), "git {:?} failed", args);
+    };
+    fs::create_dir_all(dir).unwrap();
+    git(&["init", "-q"]);
+    for path in paths {
+        let file = dir.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, "content\n").unwrap();
+        git(&["add", path]);
+    }
+}
+
 #[test]
 fn test_generate_dry_run_with_convention_files() -> Result<(), Box<dyn std::error::Error>> {
     let temp_dir = tempdir()?;
-    let project_root = temp_dir.path();
+    let project_root = temp_dir.path().join("project");
     let sub_dir = project_root.join("subdir");
-    fs::create_dir(&sub_dir)?;
+    init_repo(&project_root, &["subdir/file.txt"]);
 
-    // Create convention files
+    // Create convention files; the one above the repository is ignored
+    fs::write(temp_dir.path().join(CONVENTION_FILE_NAME), "Outside convention")?;
     fs::write(project_root.join(CONVENTION_FILE_NAME), "Root convention")?;
     fs::write(sub_dir.join(CONVENTION_FILE_NAME), "Subdir convention")?;
 
-    // Mock git diff by creating a dummy script
-    let script_path = temp_dir.path().join("git");
-    fs::write(&script_path, "#!/bin/sh\necho \"diff --git a/file.txt b/file.txt\"\nexit 0")?;
-    fs::set_permissions(&script_path, fs::Permissions::from_mode(0o755))?;
-
     // Create config file with API key
-    fs::write(project_root.join(".committo.toml"), "api-key = \"test_key_for_dry_run\"")?;
+    fs::write(temp_dir.path().join(".committo.toml"), "api-key = \"test_key_for_dry_run\"")?;
 
     let mut cmd = Command::cargo_bin("committo")?;
-    cmd.current_dir(&sub_dir);
-    cmd.env("PATH", format!("{}:{}", temp_dir.path().to_str().unwrap(), std::env::var("PATH").unwrap())); // Prepend the temp dir to PATH
-    cmd.env("HOME", project_root); // Set HOME to project root so it finds our config
+    cmd.current_dir(&project_root);
+    cmd.env("HOME", temp_dir.path()); // Set HOME so it finds our config
+    cmd.env("XDG_CONFIG_HOME", temp_dir.path().join("xdg"));
     cmd.arg("generate").arg("--dry-run");
 
     cmd.assert()
@@ -56,11 +68,41 @@ fn test_generate_dry_run_with_convention_files() -> Result<(), Box<dyn std::erro
         .stdout(predicate::str::contains("Api Key :"))
         .stdout(predicate::str::contains("(masked)"))
         .stdout(predicate::str::contains("--- Prompt ---"))
-        .stdout(predicate::str::contains("1. Root convention"))
-        .stdout(predicate::str::contains("2. Subdir convention"))
+        .stdout(predicate::str::contains("1. Subdir convention"))
+        .stdout(predicate::str::contains("2. Root convention"))
+        .stdout(predicate::str::contains("Outside convention").not())
         .stdout(predicate::str::contains("**IMPORTANT PRIORITY RULES:**"))
         .stdout(predicate::str::contains("--- Git Diff ---"))
-        .stdout(predicate::str::contains("diff --git a/file.txt b/file.txt"));
+        .stdout(predicate::str::contains("Files changed:\n  added subdir/file.txt"));
+
+    Ok(())
+}
+
+#[test]
+fn test_conventions_follow_the_staged_files() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let project_root = temp_dir.path().join("project");
+    init_repo(&project_root, &["backend/main.rs"]);
+    fs::create_dir_all(project_root.join("frontend"))?;
+    fs::write(project_root.join(CONVENTION_FILE_NAME), "Root convention")?;
+    fs::write(project_root.join("frontend").join(CONVENTION_FILE_NAME), "Frontend convention")?;
+    let global_dir = temp_dir.path().join("xdg").join("committo");
+    fs::create_dir_all(&global_dir)?;
+    fs::write(global_dir.join("convention"), "Global convention")?;
+    fs::write(temp_dir.path().join(".committo.toml"), "api-key = \"test_key_for_dry_run\"")?;
+
+    // Run from the frontend directory: only backend files are staged
+    let mut cmd = Command::cargo_bin("committo")?;
+    cmd.current_dir(project_root.join("frontend"));
+    cmd.env("HOME", temp_dir.path());
+    cmd.env("XDG_CONFIG_HOME", temp_dir.path().join("xdg"));
+    cmd.arg("--dry-run");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("1. Root convention"))
+        .stdout(predicate::str::contains("2. Global convention"))
+        .stdout(predicate::str::contains("Frontend convention").not());
 
     Ok(())
 }
@@ -92,7 +134,118 @@ fn test_show_command_without_config_file() -> Result<(), Box<dyn std::error::Err
 
     cmd.assert()
         .failure()
-        .stderr(predicate::str::contains("Error: IO error: Failed to initialize input reader"));
+        .stderr(predicate::str::contains("Error: No configuration file found at:"))
+        .stderr(predicate::str::contains("committo set api-key <key>"));
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_dry_run_renders_convention_frontmatter() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let project_root = temp_dir.path();
+    init_repo(project_root, &["file.txt"]);
+
+    fs::write(
+        project_root.join(CONVENTION_FILE_NAME),
+        "+++\ntypes = [\"feat\", \"fix\"]\nmax-subject-length = 50\n+++\nDescribe the user-visible effect\n",
+    )?;
+    fs::write(project_root.join(".committo.toml"), "api-key = \"test_key_for_dry_run\"")?;
+
+    let mut cmd = Command::cargo_bin("committo")?;
+    cmd.current_dir(project_root);
+    cmd.env("HOME", project_root);
+    cmd.arg("--dry-run");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("1. Describe the user-visible effect"))
+        .stdout(predicate::str::contains("where type is one of: feat, fix"))
+        .stdout(predicate::str::contains("Keep the subject line at most 50 characters"))
+        .stdout(predicate::str::contains("types = ").not());
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_convention_frontmatter_is_reported() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let project_root = temp_dir.path();
+    init_repo(project_root, &["file.txt"]);
+
+    fs::write(project_root.join(CONVENTION_FILE_NAME), "+++\nmax-subject-length = \"short\"\n+++\n")?;
+    fs::write(project_root.join(".committo.toml"), "api-key = \"test_key_for_dry_run\"")?;
+
+    let mut cmd = Command::cargo_bin("committo")?;
+    cmd.current_dir(project_root);
+    cmd.env("HOME", project_root);
+    cmd.arg("--dry-run");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid TOML frontmatter"));
+
+    Ok(())
+}
+
+#[test]
+fn test_dry_run_shows_resolved_presets() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let project_root = temp_dir.path();
+    init_repo(project_root, &["file.txt"]);
+
+    fs::write(project_root.join(CONVENTION_FILE_NAME), "+++\nextends = \"gitmoji\"\n+++\nProject rules\n")?;
+    fs::write(project_root.join(".committo.toml"), "api-key = \"test_key_for_dry_run\"")?;
+
+    let mut cmd = Command::cargo_bin("committo")?;
+    cmd.current_dir(project_root);
+    cmd.env("HOME", project_root);
+    cmd.arg("--dry-run");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("--- Convention ---"))
+        .stdout(predicate::str::contains("  preset `gitmoji`"))
+        .stdout(predicate::str::contains("1. Project rules"))
+        .stdout(predicate::str::contains("2. Start the subject with the gitmoji"));
+
+    Ok(())
+}
+
+#[test]
+fn test_dry_run_renders_prompt_templates() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let project_root = temp_dir.path().join("project");
+    init_repo(&project_root, &["src/app.rs"]);
+    fs::write(temp_dir.path().join(".committo.toml"), "api-key = \"test_key_for_dry_run\"")?;
+    let prompts_dir = temp_dir.path().join("xdg").join("committo").join("prompts");
+    fs::create_dir_all(&prompts_dir)?;
+    fs::write(prompts_dir.join("system.jinja"), "Write {{ candidate_count }} message(s) for branch {{ branch or \"none\" }}.")?;
+    fs::write(prompts_dir.join("user.jinja"), "Changed: {{ files | join(\", \") }}\n{{ diff }}")?;
+
+    let mut cmd = Command::cargo_bin("committo")?;
+    cmd.current_dir(&project_root);
+    cmd.env("HOME", temp_dir.path());
+    cmd.env("XDG_CONFIG_HOME", temp_dir.path().join("xdg"));
+    cmd.arg("--dry-run");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("--- Prompt Templates ---"))
+        .stdout(predicate::str::contains("system.jinja"))
+        .stdout(predicate::str::contains("--- Prompt ---\nWrite 1 message(s) for branch "))
+        .stdout(predicate::str::contains("--- Git Diff ---\nChanged: src/app.rs\nFiles changed:"));
+
+    // Templates are checked before anything is sent
+    fs::write(prompts_dir.join("user.jinja"), "{{ ticket }}")?;
+    let mut cmd = Command::cargo_bin("committo")?;
+    cmd.current_dir(&project_root);
+    cmd.env("HOME", temp_dir.path());
+    cmd.env("XDG_CONFIG_HOME", temp_dir.path().join("xdg"));
+    cmd.arg("--dry-run");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("user.jinja"))
+        .stderr(predicate::str::contains("undefined"));
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Mutex, MutexGuard};
+use tempfile::{tempdir, TempDir};
+use committo::commit::{execute_git_commit, CommitOptions};
+use committo::error::RunError;
+
+/// Held while a test works in its repository, since the working directory is per process
+static CWD: Mutex<()> = Mutex::new(());
+
+fn git(repo: &Path, args: &[&str]) -> String {
+    let output = Command::new("git").current_dir(repo).args(args).output().unwrap();
+    assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Repository with one commit and a staged change, made the working directory
+fn in_repo() -> (MutexGuard<'static, ()>, TempDir) {
+    let guard = CWD.lock().unwrap_or_else(|e| e.into_inner());
+    let temp_dir = tempdir().unwrap();
+    let repo = temp_dir.path();
+    git(repo, &["init", "-q"]);
+    git(repo, &["config", "user.name", "Test User"]);
+    git(repo, &["config", "user.email", "test@example.com"]);
+    fs::write(repo.join("a.txt"), "a\n").unwrap();
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "-q", "-m", "initial"]);
+    fs::write(repo.join("a.txt"), "a changed\n").unwrap();
+    git(repo, &["add", "."]);
+    env::set_current_dir(repo).unwrap();
+    (guard, temp_dir)
+}
+
+#[test]
+fn test_extra_args_are_passed_to_git_commit() {
+    let (_guard, repo) = in_repo();
+    let options = CommitOptions {
+        extra_args: vec!["--author=Bob Kim <bob@example.org>".to_string(), "--no-verify".to_string()],
+        ..Default::default()
+    };
+    execute_git_commit("fix: change a", &options).unwrap();
+    assert_eq!(git(repo.path(), &["log", "-1", "--format=%an <%ae>"]), "Bob Kim <bob@example.org>");
+}
+
+#[test]
+fn test_rejecting_hook_fails_the_commit() {
+    let (_guard, repo) = in_repo();
+    let hook = repo.path().join(".git/hooks/pre-commit");
+    fs::write(&hook, "#!/bin/sh\necho 'lint failed' >&2\nexit 1\n").unwrap();
+    Command::new("chmod").args(["+x"]).arg(&hook).status().unwrap();
+
+    let error = RunError::from(execute_git_commit("fix: rejected", &CommitOptions::default()).unwrap_err());
+    assert_eq!(error.code(), "commit_failed");
+    assert_eq!(error.exit_code(), 1);
+    assert!(error.to_string().contains("--no-verify"), "{}", error);
+
+    let options = CommitOptions { extra_args: vec!["--no-verify".to_string()], ..Default::default() };
+    execute_git_commit("fix: skip the hook", &options).unwrap();
+    assert_eq!(git(repo.path(), &["log", "-1", "--format=%s"]), "fix: skip the hook");
+}
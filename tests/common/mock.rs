@@ -1,6 +1,8 @@
 use async_trait::async_trait;
+use std::sync::Mutex;
+use std::time::Duration;
 use committo::config::Config;
-use committo::api::{LlmConfig, LlmError, LlmProvider};
+use committo::api::{ChatMessage, LlmConfig, LlmError, LlmProvider, Role};
 
 /// Mock provider for testing
 pub struct MockProvider {
@@ -8,6 +10,9 @@ pub struct MockProvider {
     app_config: Config,
     response: String,
     should_fail: bool,
+    error: Option<fn() -> LlmError>,
+    delay: Duration,
+    last_messages: Mutex<Vec<ChatMessage>>,
 }
 
 impl MockProvider {
@@ -24,12 +29,17 @@ impl MockProvider {
                 llm_provider: Some("mock".to_string()),
                 llm_model: Some("mock-model".to_string()),
                 run_edit: Some(true),
+                ..Default::default()
             },
             response: "Mock commit message".to_string(),
             should_fail: false,
+            error: None,
+            delay: Duration::ZERO,
+            last_messages: Mutex::new(Vec::new()),
         }
     }
     
+    #[allow(dead_code)]
     pub fn with_response(response: &str) -> Self {
         Self {
             config: LlmConfig {
@@ -42,12 +52,17 @@ impl MockProvider {
                 llm_provider: Some("mock".to_string()),
                 llm_model: Some("mock-model".to_string()),
                 run_edit: Some(true),
+                ..Default::default()
             },
             response: response.to_string(),
             should_fail: false,
+            error: None,
+            delay: Duration::ZERO,
+            last_messages: Mutex::new(Vec::new()),
         }
     }
     
+    #[allow(dead_code)]
     pub fn with_failure() -> Self {
         Self {
             config: LlmConfig {
@@ -60,9 +75,13 @@ impl MockProvider {
                 llm_provider: Some("mock".to_string()),
                 llm_model: Some("mock-model".to_string()),
                 run_edit: Some(true),
+                ..Default::default()
             },
             response: String::new(),
             should_fail: true,
+            error: None,
+            delay: Duration::ZERO,
+            last_messages: Mutex::new(Vec::new()),
         }
     }
     
@@ -76,8 +95,38 @@ impl MockProvider {
             app_config: config,
             response: "Mock commit message".to_string(),
             should_fail: false,
+            error: None,
+            delay: Duration::ZERO,
+            last_messages: Mutex::new(Vec::new()),
         }
     }
+
+    /// Report `model` instead of "mock-model"
+    #[allow(dead_code)]
+    pub fn with_model(mut self, model: &str) -> Self {
+        self.config.model = model.to_string();
+        self
+    }
+
+    /// Fail every request with the error `error` builds
+    #[allow(dead_code)]
+    pub fn failing_with(mut self, error: fn() -> LlmError) -> Self {
+        self.error = Some(error);
+        self
+    }
+
+    /// Take `delay` to answer
+    #[allow(dead_code)]
+    pub fn delayed(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Messages sent in the most recent request
+    #[allow(dead_code)]
+    pub fn last_messages(&self) -> Vec<ChatMessage> {
+        self.last_messages.lock().unwrap().clone()
+    }
 }
 
 #[async_trait]
@@ -90,24 +139,39 @@ impl LlmProvider for MockProvider {
         self.app_config.llm_provider.clone().unwrap_or_else(|| "Mock".to_string())
     }
 
-    async fn generate_commit_message_impl(&self, system_prompt: &str, _diff: &str) -> Result<String, LlmError> {
+    async fn generate_commit_message_impl(&self, messages: &[ChatMessage]) -> Result<String, LlmError> {
         // Check for API key availability (consistent with other providers)
         let _api_key = self.get_api_key()?;
+        *self.last_messages.lock().unwrap() = messages.to_vec();
+        tokio::time::sleep(self.delay).await;
 
-        if self.should_fail {
+        if let Some(error) = self.error {
+            Err(error())
+        } else if self.should_fail {
             Err(LlmError::ApiError("Mock API error".to_string()))
         } else {
-            // Check if multiple candidates are requested based on the system prompt
-            if system_prompt.contains("different commit message options") {
+            // Follow-up turns state the expected count themselves; the first turn relies on the system prompt
+            let instructions = if messages.len() > 2 {
+                messages.last().map(|message| message.content.as_str()).unwrap_or_default()
+            } else {
+                messages
+                    .iter()
+                    .find(|message| message.role == Role::System)
+                    .map(|message| message.content.as_str())
+                    .unwrap_or_default()
+            };
+
+            // Check if multiple candidates are requested
+            if instructions.contains("different commit message options") {
                 // Extract the number from the prompt
-                if let Some(count_str) = system_prompt.split("Generate ").nth(1).and_then(|s| s.split(" different").next()) {
-                    if let Ok(count) = count_str.parse::<u32>() {
-                        let mut candidates = Vec::new();
-                        for i in 1..=count {
-                            candidates.push(format!("{} #{}", self.response, i));
-                        }
-                        return Ok(candidates.join("\n"));
+                if let Some(count_str) = instructions.split("Generate ").nth(1).and_then(|s| s.split(" different").next())
+                    && let Ok(count) = count_str.parse::<u32>()
+                {
+                    let mut candidates = Vec::new();
+                    for i in 1..=count {
+                        candidates.push(format!("{} #{}", self.response, i));
                     }
+                    return Ok(candidates.join("\n"));
                 }
             }
             Ok(self.response.clone())
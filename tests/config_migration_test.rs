@@ -0,0 +1,110 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+
+#[test]
+fn test_old_config_is_migrated_with_backup() {
+    let temp_home = TempDir::new().unwrap();
+    let config_path = temp_home.path().join(".committo.toml");
+    let original = "api-key = \"secret123\"\ncandidate-count = 3\n";
+    fs::write(&config_path, original).unwrap();
+
+    let mut cmd = Command::cargo_bin("committo").unwrap();
+    cmd.env("HOME", temp_home.path())
+        .arg("show");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Api Key : \"secre****\" (masked)"))
+        .stdout(predicate::str::contains("Candidate Count : 3"))
+        .stderr(predicate::str::contains("Upgraded config"));
+
+    let content = fs::read_to_string(&config_path).unwrap();
+    assert!(content.contains("config-version = 1"));
+    assert!(content.contains("api-key = \"secret123\""));
+
+    let backup = fs::read_to_string(temp_home.path().join(".committo.toml.v0.bak")).unwrap();
+    assert_eq!(backup, original);
+}
+
+#[test]
+fn test_current_config_is_not_rewritten() {
+    let temp_home = TempDir::new().unwrap();
+    let config_path = temp_home.path().join(".committo.toml");
+    fs::write(&config_path, "config-version = 1\napi-key = \"test\"\n").unwrap();
+
+    Command::cargo_bin("committo").unwrap()
+        .env("HOME", temp_home.path())
+        .arg("show")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Upgraded config").not());
+
+    assert!(!temp_home.path().join(".committo.toml.v0.bak").exists());
+}
+
+#[test]
+fn test_unknown_keys_warn_and_survive_set() {
+    let temp_home = TempDir::new().unwrap();
+    let config_path = temp_home.path().join(".committo.toml");
+    fs::write(&config_path, "config-version = 1\napi-key = \"test\"\ntemprature = 0.5\n").unwrap();
+
+    Command::cargo_bin("committo").unwrap()
+        .env("HOME", temp_home.path())
+        .arg("set").arg("candidate-count").arg("2")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("unknown config key 'temprature'"));
+
+    let content = fs::read_to_string(&config_path).unwrap();
+    assert!(content.contains("temprature = 0.5"));
+    assert!(content.contains("candidate-count = 2"));
+}
+
+#[test]
+fn test_newer_config_version_is_rejected() {
+    let temp_home = TempDir::new().unwrap();
+    fs::write(temp_home.path().join(".committo.toml"), "config-version = 99\napi-key = \"test\"\n").unwrap();
+
+    Command::cargo_bin("committo").unwrap()
+        .env("HOME", temp_home.path())
+        .arg("show")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("newer than supported"));
+}
+
+#[test]
+fn test_config_doctor_reports_problems() {
+    let temp_home = TempDir::new().unwrap();
+    let config_path = temp_home.path().join(".committo.toml");
+    let original = "api-key = \"test\"\ncandidate-count = 0\nllm-provider = \"unknown\"\nfoo = \"bar\"\n";
+    fs::write(&config_path, original).unwrap();
+
+    Command::cargo_bin("committo").unwrap()
+        .env("HOME", temp_home.path())
+        .arg("config").arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Schema version : 0 (current: 1)"))
+        .stdout(predicate::str::contains("candidate-count: must be at least 1"))
+        .stdout(predicate::str::contains("unsupported provider 'unknown'"))
+        .stdout(predicate::str::contains("  - foo"));
+
+    // Doctor is read-only
+    assert_eq!(fs::read_to_string(&config_path).unwrap(), original);
+}
+
+#[test]
+fn test_config_doctor_healthy() {
+    let temp_home = TempDir::new().unwrap();
+    fs::write(temp_home.path().join(".committo.toml"), "config-version = 1\napi-key = \"test\"\nrun-edit = false\n").unwrap();
+
+    Command::cargo_bin("committo").unwrap()
+        .env("HOME", temp_home.path())
+        .arg("config").arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No problems found."));
+}
@@ -0,0 +1,192 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::{tempdir, TempDir};
+use committo::config::CONVENTION_FILE_NAME;
+
+fn git(repo: &Path, args: &[&str]) {
+    let output = Command::new("git").current_dir(repo).args(args).output().unwrap();
+    assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+}
+
+/// Empty repository with a hermetic HOME inside it
+fn setup_repo() -> TempDir {
+    let dir = tempdir().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    git(dir.path(), &["config", "user.name", "Test User"]);
+    git(dir.path(), &["config", "user.email", "test@example.com"]);
+    dir
+}
+
+fn committo(dir: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("committo").unwrap();
+    cmd.current_dir(dir);
+    cmd.env("HOME", dir);
+    cmd.env("XDG_CONFIG_HOME", dir.join(".config"));
+    cmd.arg("convention");
+    cmd
+}
+
+#[test]
+fn test_init_from_preset() {
+    let repo = setup_repo();
+    fs::create_dir(repo.path().join("sub")).unwrap();
+
+    committo(&repo.path().join("sub"))
+        .args(["init", "--preset", "angular"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created"));
+    let content = fs::read_to_string(repo.path().join(CONVENTION_FILE_NAME)).unwrap();
+    assert_eq!(content, "+++\nextends = \"angular\"\n+++\n");
+
+    committo(repo.path())
+        .args(["init", "--preset", "kernel"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+    committo(repo.path()).args(["init", "--preset", "kernel", "--force"]).assert().success();
+
+    committo(repo.path())
+        .args(["init", "--preset", "nope", "--force"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown preset `nope`"));
+}
+
+#[test]
+fn test_init_from_history() {
+    let repo = setup_repo_with_history();
+
+    committo(repo.path())
+        .args(["init", "--from-history"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Conventional Commits with types: feat, fix, docs"))
+        .stdout(predicate::str::contains("scopes: api"));
+    let content = fs::read_to_string(repo.path().join(CONVENTION_FILE_NAME)).unwrap();
+    assert!(content.contains("types = [\"feat\", \"fix\", \"docs\"]"), "{}", content);
+}
+
+#[test]
+fn test_init_needs_a_choice_without_a_terminal() {
+    let repo = setup_repo();
+    committo(repo.path()).arg("init").assert().code(5);
+}
+
+#[test]
+fn test_show_and_check() {
+    let repo = setup_repo();
+    fs::write(
+        repo.path().join(CONVENTION_FILE_NAME),
+        "+++\nextends = \"conventional\"\nmax-subject-length = 30\n+++\nProject rules\n",
+    )
+    .unwrap();
+
+    committo(repo.path())
+        .arg("show")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("  preset `conventional`"))
+        .stdout(predicate::str::contains("**IMPORTANT PRIORITY RULES:**"))
+        .stdout(predicate::str::contains("1. Project rules"))
+        .stdout(predicate::str::contains("2. Follow Conventional Commits"))
+        .stdout(predicate::str::contains("- Keep the subject line at most 30 characters"));
+
+    committo(repo.path()).args(["check", "fix: handle empty input"]).assert().success();
+    assert_cmd::Command::from_std(committo(repo.path()))
+        .args(["check", "-"])
+        .write_stdin("Update the README with a much longer subject\n")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("- subject doesn't start with `<type>: `"))
+        .stdout(predicate::str::contains("- subject is 44 characters, longer than 30"))
+        .stderr(predicate::str::contains("breaks 2 convention rule(s)"));
+}
+
+#[test]
+fn test_show_uses_the_system_template() {
+    let repo = setup_repo();
+    fs::write(repo.path().join(CONVENTION_FILE_NAME), "Project rules\n").unwrap();
+    let prompts = repo.path().join(".config").join("committo").join("prompts");
+    fs::create_dir_all(&prompts).unwrap();
+    fs::write(prompts.join("system.jinja"), "Team prompt.\n{{ convention }}").unwrap();
+
+    committo(repo.path())
+        .arg("show")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Team prompt.\n1. Project rules"))
+        .stdout(predicate::str::contains("PRIORITY RULES").not());
+}
+
+/// Repository whose history follows a consistent style, with one more change staged
+fn setup_repo_with_history() -> TempDir {
+    let repo = setup_repo();
+    let subjects = ["feat(api): add login", "fix(api): check tokens", "docs: setup guide", "feat: dark mode", "fix(api): typo"];
+    for (i, subject) in subjects.iter().enumerate() {
+        fs::write(repo.path().join("file.txt"), i.to_string()).unwrap();
+        git(repo.path(), &["add", "file.txt"]);
+        git(repo.path(), &["commit", "-q", "-m", subject]);
+    }
+    fs::write(repo.path().join("file.txt"), "staged").unwrap();
+    git(repo.path(), &["add", "file.txt"]);
+    repo
+}
+
+#[test]
+fn test_analyze_reports_the_history() {
+    let repo = setup_repo_with_history();
+    committo(repo.path())
+        .arg("analyze")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Analyzed the last 5 commits:"))
+        .stdout(predicate::str::contains("  Conventional Commits: 5 (100%)"))
+        .stdout(predicate::str::contains("    scoped: 3 (60%): api 3"))
+        .stdout(predicate::str::contains("1. Match the style of this repository's last 5 commits:"))
+        .stdout(predicate::str::contains("- Start the description with a lowercase letter"));
+}
+
+#[test]
+fn test_dry_run_falls_back_to_the_inferred_convention() {
+    let repo = setup_repo_with_history();
+    fs::write(repo.path().join(".committo.toml"), "api-key = \"test_key_for_dry_run\"").unwrap();
+
+    let mut cmd = Command::cargo_bin("committo").unwrap();
+    cmd.current_dir(repo.path()).env("HOME", repo.path()).env("XDG_CONFIG_HOME", repo.path().join(".config"));
+    cmd.arg("--dry-run")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--- Convention ---\ninferred from the last 5 commits"))
+        .stdout(predicate::str::contains("where type is one of: feat, fix, docs"));
+
+    fs::write(repo.path().join(".committo.toml"), "api-key = \"test_key_for_dry_run\"\ninfer-convention = false").unwrap();
+    let mut cmd = Command::cargo_bin("committo").unwrap();
+    cmd.current_dir(repo.path()).env("HOME", repo.path()).env("XDG_CONFIG_HOME", repo.path().join(".config"));
+    cmd.arg("--dry-run")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("inferred from").not());
+}
+
+#[test]
+fn test_show_and_check_use_the_config_and_history() {
+    let repo = setup_repo_with_history();
+    fs::write(repo.path().join(".committo.toml"), "api-key = \"test_key\"\nlanguage = \"Korean\"\n").unwrap();
+
+    committo(repo.path())
+        .arg("show")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("inferred from the last 5 commits"))
+        .stdout(predicate::str::contains("1. Match the style of this repository's last 5 commits:"));
+
+    committo(repo.path())
+        .args(["check", "feat: add login"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Korean"));
+}
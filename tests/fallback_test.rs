@@ -0,0 +1,114 @@
+use std::time::Duration;
+use committo::api::{ChatMessage, LlmError, LlmProvider};
+use committo::config::Config;
+use committo::providers::{FallbackProvider, ProviderFactory};
+
+#[path = "common/mock.rs"]
+mod mock;
+use mock::MockProvider;
+
+fn rate_limited() -> LlmError {
+    LlmError::RateLimited { message: "Slow down".to_string(), retry_after: None }
+}
+
+fn unavailable() -> LlmError {
+    LlmError::Unavailable("Bad gateway (HTTP 502)".to_string())
+}
+
+fn auth() -> LlmError {
+    LlmError::Auth("Incorrect API key".to_string())
+}
+
+fn messages() -> Vec<ChatMessage> {
+    vec![ChatMessage::system("prompt"), ChatMessage::user("diff")]
+}
+
+#[tokio::test]
+async fn test_falls_back_on_retryable_errors() {
+    let chain = FallbackProvider::new(
+        vec![
+            Box::new(MockProvider::new().with_model("primary").failing_with(rate_limited)),
+            Box::new(MockProvider::new().with_model("second").failing_with(unavailable)),
+            Box::new(MockProvider::with_response("feat: add login").with_model("third")),
+        ],
+        None,
+    );
+    assert_eq!(chain.get_config().model, "primary");
+
+    let completion = chain.generate_completion_impl(&messages()).await.unwrap();
+    assert_eq!(completion.content, "feat: add login");
+    // The answering provider is reported, e.g. in `--format json`
+    assert_eq!(chain.get_config().model, "third");
+}
+
+#[tokio::test]
+async fn test_stops_on_errors_another_provider_wont_fix() {
+    let second = MockProvider::new().with_model("second");
+    let chain = FallbackProvider::new(
+        vec![Box::new(MockProvider::new().with_model("primary").failing_with(auth)), Box::new(second)],
+        None,
+    );
+
+    let error = chain.generate_completion_impl(&messages()).await.unwrap_err();
+    assert_eq!(error.code(), "auth_error");
+}
+
+#[tokio::test]
+async fn test_reports_the_last_error_when_every_provider_fails() {
+    let chain = FallbackProvider::new(
+        vec![
+            Box::new(MockProvider::new().failing_with(unavailable)),
+            Box::new(MockProvider::new().failing_with(rate_limited)),
+        ],
+        None,
+    );
+
+    let error = chain.generate_completion_impl(&messages()).await.unwrap_err();
+    assert_eq!(error.code(), "rate_limited");
+}
+
+#[tokio::test]
+async fn test_budget_covers_the_whole_chain() {
+    let budget = Duration::from_millis(100);
+    let chain = FallbackProvider::new(
+        vec![
+            Box::new(MockProvider::new().delayed(Duration::from_millis(60)).failing_with(unavailable)),
+            Box::new(MockProvider::new().delayed(Duration::from_secs(5))),
+        ],
+        Some(budget),
+    );
+
+    let started = std::time::Instant::now();
+    let error = chain.generate_completion_impl(&messages()).await.unwrap_err();
+    assert!(matches!(error, LlmError::Timeout(limit) if limit == budget), "{}", error);
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[tokio::test]
+async fn test_provider_timeout_equal_to_the_budget_falls_back() {
+    let chain = FallbackProvider::new(
+        vec![
+            Box::new(MockProvider::new().failing_with(|| LlmError::Timeout(Duration::from_secs(60)))),
+            Box::new(MockProvider::with_response("feat: add login")),
+        ],
+        Some(Duration::from_secs(60)),
+    );
+
+    let completion = chain.generate_completion_impl(&messages()).await.unwrap();
+    assert_eq!(completion.content, "feat: add login");
+}
+
+#[test]
+fn test_factory_builds_a_chain_from_config() {
+    let config = Config {
+        api_key: Some("key".to_string()),
+        llm_model: Some("gpt-4o".to_string()),
+        fallback: Some(vec!["openai:gpt-4o-mini".to_string()]),
+        ..Default::default()
+    };
+    let provider = ProviderFactory::create_chain(config).unwrap();
+    assert_eq!(provider.get_config().model, "gpt-4o");
+
+    let config = Config { fallback: Some(vec!["carrier-pigeon".to_string()]), ..Default::default() };
+    assert!(ProviderFactory::create_chain(config).is_err());
+}
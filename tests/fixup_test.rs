@@ -0,0 +1,67 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+use committo::fixup::find_target;
+use committo::git::{get_staged_diff, DiffOptions};
+
+fn git(repo: &Path, args: &[&str]) -> String {
+    let output = Command::new("git").current_dir(repo).args(args).output().unwrap();
+    assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+fn commit_file(repo: &Path, content: &str, message: &str) -> String {
+    fs::write(repo.join("lib.txt"), content).unwrap();
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "-q", "-m", message]);
+    git(repo, &["rev-parse", "HEAD"])
+}
+
+fn target() -> Option<(String, String)> {
+    find_target(&get_staged_diff(&DiffOptions::default()).unwrap())
+        .unwrap()
+        .map(|target| {
+            let message = target.message();
+            (target.commit, message)
+        })
+}
+
+// A single test, since it changes the process working directory
+#[test]
+fn test_finds_the_branch_commit_to_fix_up() {
+    let temp_dir = tempdir().unwrap();
+    let repo = temp_dir.path();
+    git(repo, &["init", "-q", "-b", "main"]);
+    git(repo, &["config", "user.name", "Test User"]);
+    git(repo, &["config", "user.email", "test@example.com"]);
+    commit_file(repo, "one\ntwo\nthree\n", "initial");
+    git(repo, &["checkout", "-q", "-b", "feature"]);
+    let login = commit_file(repo, "one\ntwo\nthree\nlogin()\nsession()\n", "feat: add login");
+    commit_file(repo, "one\ntwo\nthree\nlogin()\nsession()\nlogout()\n", "fixup! feat: add login");
+    env::set_current_dir(repo).unwrap();
+
+    // Lines from the branch: the original commit is targeted, even through a fixup
+    fs::write(repo.join("lib.txt"), "one\ntwo\nthree\nlogin()\nsession()\nlogout(user)\n").unwrap();
+    git(repo, &["add", "."]);
+    let (commit, message) = target().unwrap();
+    assert_eq!(message, "fixup! feat: add login");
+    assert_ne!(commit, login);
+
+    fs::write(repo.join("lib.txt"), "one\ntwo\nthree\nlogin(user)\nsession(user)\nlogout()\n").unwrap();
+    git(repo, &["add", "."]);
+    assert_eq!(target().unwrap().0, login);
+
+    // A line from main as well: not obviously a fixup
+    fs::write(repo.join("lib.txt"), "ONE\ntwo\nthree\nlogin(user)\nsession()\nlogout()\n").unwrap();
+    git(repo, &["add", "."]);
+    assert_eq!(target(), None);
+
+    // Nothing to compare with on main itself
+    git(repo, &["stash", "-q"]);
+    git(repo, &["checkout", "-q", "main"]);
+    fs::write(repo.join("lib.txt"), "one\nTWO\nthree\n").unwrap();
+    git(repo, &["add", "."]);
+    assert_eq!(target(), None);
+}
@@ -0,0 +1,78 @@
+use assert_cmd::Command;
+use std::fs;
+use std::process::Command as StdCommand;
+use std::time::Duration;
+use tempfile::TempDir;
+use committo::api::LlmError;
+use committo::config::CONFIG_FILE_NAME;
+use committo::error::{RunError, EXIT_NO_STAGED_CHANGES, EXIT_PROVIDER_ERROR};
+
+fn parse_stdout(output: &std::process::Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).expect("stdout should be valid JSON")
+}
+
+#[test]
+fn test_json_error_for_no_staged_changes() {
+    let temp_dir = TempDir::new().unwrap();
+    StdCommand::new("git").current_dir(temp_dir.path()).args(["init", "-q"]).output().unwrap();
+    fs::write(temp_dir.path().join(CONFIG_FILE_NAME), "config-version = 1\napi-key = \"test_key\"\n").unwrap();
+
+    let output = Command::cargo_bin("committo").unwrap()
+        .current_dir(temp_dir.path())
+        .env("HOME", temp_dir.path())
+        .args(["generate", "--format", "json"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(EXIT_NO_STAGED_CHANGES));
+    let json = parse_stdout(&output);
+    assert_eq!(json["error"]["code"], "no_staged_changes");
+    assert_eq!(json["error"]["exit_code"], EXIT_NO_STAGED_CHANGES);
+}
+
+#[test]
+fn test_json_never_starts_setup() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let output = Command::cargo_bin("committo").unwrap()
+        .current_dir(temp_dir.path())
+        .env("HOME", temp_dir.path())
+        .args(["generate", "--format", "json"])
+        .output()
+        .unwrap();
+
+    let json = parse_stdout(&output);
+    assert_eq!(json["error"]["code"], "not_interactive");
+    assert!(json["error"]["message"].as_str().unwrap().contains("No configuration file found"));
+}
+
+#[test]
+fn test_provider_error_codes_are_stable() {
+    let err = RunError::Provider(LlmError::ConfigError("API key not found in config".to_string()));
+    let json = err.to_json();
+
+    assert_eq!(json["error"]["code"], "config_error");
+    assert_eq!(json["error"]["exit_code"], EXIT_PROVIDER_ERROR);
+    assert_eq!(RunError::Provider(LlmError::ApiError("x".to_string())).code(), "api_error");
+    assert_eq!(RunError::Aborted.code(), "aborted");
+}
+
+#[test]
+fn test_provider_errors_carry_hints() {
+    let rate_limited = LlmError::RateLimited { message: "Slow down".to_string(), retry_after: Some(Duration::from_secs(20)) };
+    let cases = [
+        (rate_limited, "rate_limited"),
+        (LlmError::Timeout(Duration::from_secs(60)), "timeout"),
+        (LlmError::Auth("Incorrect API key".to_string()), "auth_error"),
+        (LlmError::ContextLengthExceeded("Too many tokens".to_string()), "context_length_exceeded"),
+        (LlmError::Unavailable("Bad gateway (HTTP 502)".to_string()), "provider_unavailable"),
+    ];
+    for (err, code) in cases {
+        let json = RunError::Provider(err).to_json();
+        assert_eq!(json["error"]["code"], code);
+        assert!(json["error"]["hint"].is_string(), "{} has no hint", code);
+    }
+
+    let json = RunError::Provider(LlmError::ApiError("x".to_string())).to_json();
+    assert!(json["error"].get("hint").is_none());
+}
@@ -0,0 +1,61 @@
+//! `--format json` must leave nothing but the report on stdout. The test runs itself
+//! again as a child process with a mock provider and parses the child's stdout, so
+//! this file has no test harness capturing the output.
+
+use clap::Parser;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+use committo::config::Config;
+use committo::Cli;
+
+#[path = "common/mock.rs"]
+mod mock;
+use mock::MockProvider;
+
+/// Set in the child process, which runs `generate` instead of the test
+const CHILD: &str = "COMMITTO_JSON_SUCCESS_CHILD";
+
+fn git(repo: &Path, args: &[&str]) {
+    let output = Command::new("git").current_dir(repo).args(args).output().unwrap();
+    assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+}
+
+#[tokio::main]
+async fn main() {
+    if env::var_os(CHILD).is_some() {
+        let cli = Cli::parse_from(["committo", "--format", "json"]);
+        let config = Config { api_key: Some("test_key".to_string()), candidate_count: Some(2), ..Default::default() };
+        let provider = MockProvider::with_config(config.clone());
+        committo::generate_with_provider(&cli, &config, &provider).await.unwrap();
+        return;
+    }
+
+    test_json_candidates_are_the_only_output();
+    println!("test test_json_candidates_are_the_only_output ... ok");
+}
+
+fn test_json_candidates_are_the_only_output() {
+    let temp_dir = TempDir::new().unwrap();
+    let repo = temp_dir.path();
+    git(repo, &["init", "-q"]);
+    fs::write(repo.join("file.txt"), "content\n").unwrap();
+    git(repo, &["add", "file.txt"]);
+
+    let output = Command::new(env::current_exe().unwrap())
+        .current_dir(repo)
+        .env(CHILD, "1")
+        .env("HOME", repo)
+        .env("XDG_CONFIG_HOME", repo.join(".config"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "child failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .unwrap_or_else(|e| panic!("stdout should be only JSON ({}): {:?}", e, String::from_utf8_lossy(&output.stdout)));
+    assert_eq!(json["candidates"], serde_json::json!(["Mock commit message #1", "Mock commit message #2"]));
+    assert_eq!(json["model"], "mock-model");
+    assert_eq!(json["diff_stats"]["files_changed"], 1);
+}
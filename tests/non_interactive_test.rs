@@ -0,0 +1,90 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::process::Command as StdCommand;
+use tempfile::TempDir;
+use committo::config::CONFIG_FILE_NAME;
+use committo::error::{EXIT_NOT_INTERACTIVE, EXIT_NO_STAGED_CHANGES};
+
+/// Create a git repo with a config file in a fresh HOME
+fn setup_repo() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let repo = temp_dir.path();
+    StdCommand::new("git").current_dir(repo).args(["init", "-q"]).output().unwrap();
+    fs::write(repo.join(CONFIG_FILE_NAME), "config-version = 1\napi-key = \"test_key\"\n").unwrap();
+    temp_dir
+}
+
+fn committo(repo: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("committo").unwrap();
+    cmd.current_dir(repo).env("HOME", repo);
+    cmd
+}
+
+#[test]
+fn test_no_staged_changes_exit_code() {
+    let temp_dir = setup_repo();
+
+    committo(temp_dir.path())
+        .arg("--yes")
+        .assert()
+        .code(EXIT_NO_STAGED_CHANGES)
+        .stderr(predicate::str::contains("No staged changes to commit."));
+}
+
+#[test]
+fn test_non_tty_without_flags_fails_fast() {
+    let temp_dir = setup_repo();
+    fs::write(temp_dir.path().join("file.txt"), "content").unwrap();
+    StdCommand::new("git").current_dir(temp_dir.path()).args(["add", "file.txt"]).output().unwrap();
+
+    committo(temp_dir.path())
+        .write_stdin("")
+        .assert()
+        .code(EXIT_NOT_INTERACTIVE)
+        .stderr(predicate::str::contains("stdin is not a terminal"))
+        .stderr(predicate::str::contains("--pick <n>"));
+}
+
+#[test]
+fn test_yes_conflicts_with_print() {
+    let temp_dir = setup_repo();
+
+    committo(temp_dir.path())
+        .arg("--yes")
+        .arg("--print")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_pick_must_be_positive() {
+    let temp_dir = setup_repo();
+
+    committo(temp_dir.path())
+        .arg("generate")
+        .arg("--pick")
+        .arg("0")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value '0'"));
+}
+
+#[test]
+fn test_git_args_are_checked_before_committing() {
+    let temp_dir = setup_repo();
+
+    committo(temp_dir.path())
+        .args(["--yes", "--", "-m", "message"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("`-m` can't be passed to git commit"));
+
+    committo(temp_dir.path())
+        .args(["--yes", "--all", "--", "--no-verify", "--", "src/"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--all can't be combined with paths"));
+}
@@ -53,6 +53,7 @@ async fn test_provider_without_api_key() -> Result<(), Box<dyn std::error::Error
         llm_provider: Some("mock".to_string()),
         llm_model: Some("mock-model".to_string()),
         run_edit: Some(true),
+        ..Default::default()
     };
     let provider = MockProvider::with_config(config);
 
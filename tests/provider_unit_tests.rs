@@ -1,5 +1,5 @@
 use committo::providers::{OpenAiProvider, ProviderFactory};
-use committo::api::{LlmProvider, generate_commit_message_with_provider};
+use committo::api::{ChatMessage, LlmProvider, generate_commit_message_with_provider};
 use committo::config::{DEFAULT_OPENAI_MODEL, GPT4_MODEL, Config};
 
 #[path = "common/mock.rs"]
@@ -13,7 +13,7 @@ mod provider_tests {
     #[test]
     fn test_openai_provider_config() {
         let app_config = Config::default();
-        let provider = OpenAiProvider::new(app_config);
+        let provider = OpenAiProvider::new(app_config).unwrap();
         let config = provider.get_config();
         
         assert_eq!(config.model, DEFAULT_OPENAI_MODEL);
@@ -24,7 +24,7 @@ mod provider_tests {
     #[test]
     fn test_openai_provider_with_custom_model() {
         let app_config = Config::default();
-        let provider = OpenAiProvider::with_model(app_config, "gpt-4");
+        let provider = OpenAiProvider::with_model(app_config, "gpt-4").unwrap();
         let config = provider.get_config();
         
         assert_eq!(config.model, GPT4_MODEL);
@@ -49,6 +49,7 @@ mod provider_tests {
             llm_provider: Some("mock".to_string()),
             llm_model: Some("mock-model".to_string()),
             run_edit: Some(true),
+            ..Default::default()
         };
         
         // Test various key lengths
@@ -66,6 +67,7 @@ mod provider_tests {
             llm_provider: Some("openai".to_string()),
             llm_model: Some("gpt-4".to_string()),
             run_edit: Some(true),
+            ..Default::default()
         };
         
         let output = config.show_masking_config();
@@ -80,7 +82,7 @@ mod provider_tests {
     async fn test_mock_provider_success() {
         let provider = MockProvider::with_response("feat: add new feature");
         
-        let result = provider.generate_commit_message_impl("Test prompt", "Test diff").await;
+        let result = provider.generate_commit_message_impl(&[ChatMessage::system("Test prompt"), ChatMessage::user("Test diff")]).await;
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "feat: add new feature");
     }
@@ -89,7 +91,7 @@ mod provider_tests {
     async fn test_mock_provider_failure() {
         let provider = MockProvider::with_failure();
         
-        let result = provider.generate_commit_message_impl("Test prompt", "Test diff").await;
+        let result = provider.generate_commit_message_impl(&[ChatMessage::system("Test prompt"), ChatMessage::user("Test diff")]).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Mock API error"));
     }
@@ -121,19 +123,26 @@ mod provider_tests {
     fn test_provider_factory_default() {
         // Test default provider creation
         let config = Config::default();
-        let provider = ProviderFactory::create_provider(config);
+        let provider = ProviderFactory::create_provider(config).unwrap();
         assert_eq!(provider.get_provider_name(), "OpenAI");
     }
 
     #[test]
     fn test_provider_factory_specific_methods() {
         let config = Config::default();
-        let provider = ProviderFactory::create_openai(config.clone());
+        let provider = ProviderFactory::create_openai(config.clone()).unwrap();
         assert_eq!(provider.get_provider_name(), "OpenAI");
         assert_eq!(provider.get_config().model, DEFAULT_OPENAI_MODEL);
         
-        let provider = ProviderFactory::create_openai_with_model(config, "gpt-4-turbo");
+        let provider = ProviderFactory::create_openai_with_model(config, "gpt-4-turbo").unwrap();
         assert_eq!(provider.get_provider_name(), "OpenAI");
         assert_eq!(provider.get_config().model, "gpt-4-turbo");
     }
+
+    #[test]
+    fn test_provider_factory_rejects_invalid_connection_settings() {
+        let config = Config { client_cert: Some("/nonexistent/cert.pem".to_string()), ..Default::default() };
+        assert!(ProviderFactory::create_provider(config.clone()).is_err());
+        assert!(OpenAiProvider::new(config).is_err());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,112 @@
+use committo::api::{refine_candidate, regenerate_candidate, retry_with_feedback, Conversation, LlmProvider, Role};
+use committo::config::Config;
+use committo::prompt::Prompt;
+
+#[path = "common/mock.rs"]
+mod mock;
+use mock::MockProvider;
+
+#[tokio::test]
+async fn test_refine_sends_follow_up_turn() {
+    let provider = MockProvider::with_response("  fix: shorten login timeout  ");
+    let mut conversation = Conversation::new("system", "diff content");
+    conversation.push_response("fix: reduce the login timeout from 30s to 10s");
+
+    let refined = refine_candidate(&provider, &mut conversation, "fix: reduce the login timeout from 30s to 10s", "make it shorter")
+        .await
+        .unwrap();
+    assert_eq!(refined, "fix: shorten login timeout");
+
+    let messages = provider.last_messages();
+    let roles: Vec<Role> = messages.iter().map(|m| m.role).collect();
+    assert_eq!(roles, vec![Role::System, Role::User, Role::Assistant, Role::User]);
+    assert_eq!(messages[1].content, "diff content");
+    assert_eq!(messages[2].content, "fix: reduce the login timeout from 30s to 10s");
+    assert!(messages[3].content.contains("make it shorter"));
+
+    // Later turns see the refined message
+    assert_eq!(conversation.messages().len(), 5);
+    assert_eq!(conversation.messages()[4].content, refined);
+}
+
+#[tokio::test]
+async fn test_regenerate_returns_single_message() {
+    let config = Config {
+        api_key: Some("test_key".to_string()),
+        candidate_count: Some(3),
+        ..Default::default()
+    };
+    let provider = MockProvider::with_config(config);
+    let candidates = vec!["feat: a".to_string(), "feat: b".to_string(), "feat: c".to_string()];
+    let mut conversation = Conversation::new(provider.build_system_prompt(&Prompt::default()), "diff content");
+    conversation.push_response(candidates.join("\n"));
+
+    let replacement = regenerate_candidate(&provider, &mut conversation, &candidates, 1).await.unwrap();
+    assert_eq!(replacement, "Mock commit message");
+
+    let messages = provider.last_messages();
+    assert_eq!(messages[2].content, "feat: a\nfeat: b\nfeat: c");
+    assert!(messages[3].content.contains("\"feat: b\""));
+    assert_eq!(conversation.messages().last().unwrap().content, replacement);
+}
+
+#[tokio::test]
+async fn test_refine_propagates_provider_errors() {
+    let provider = MockProvider::with_failure();
+
+    let mut conversation = Conversation::new("system", "diff");
+    let result = refine_candidate(&provider, &mut conversation, "fix: x", "shorter").await;
+    assert!(result.unwrap_err().to_string().contains("Mock API error"));
+}
+
+#[tokio::test]
+async fn test_retry_sends_previous_candidates_and_feedback() {
+    let config = Config {
+        api_key: Some("test_key".to_string()),
+        candidate_count: Some(2),
+        ..Default::default()
+    };
+    let provider = MockProvider::with_config(config);
+    let mut conversation = Conversation::new(provider.build_system_prompt(&Prompt::default()), "diff content");
+    conversation.push_response("feat: a\nfeat: b");
+
+    let response = retry_with_feedback(&provider, &mut conversation, "mention the migration").await.unwrap();
+    assert_eq!(response, "Mock commit message #1\nMock commit message #2");
+
+    let sent = provider.last_messages();
+    let roles: Vec<Role> = sent.iter().map(|m| m.role).collect();
+    assert_eq!(roles, vec![Role::System, Role::User, Role::Assistant, Role::User]);
+    assert_eq!(sent[2].content, "feat: a\nfeat: b");
+    assert!(sent[3].content.contains("What should change: mention the migration"));
+
+    // The new response is kept so a second retry sees both rounds
+    assert_eq!(conversation.messages().len(), 5);
+    assert_eq!(conversation.messages()[4].content, response);
+}
+
+#[tokio::test]
+async fn test_retry_without_feedback_asks_for_different_wording() {
+    let provider = MockProvider::with_response("fix: something else");
+    let mut conversation = Conversation::new("system", "diff content");
+    conversation.push_response("fix: something");
+
+    let response = retry_with_feedback(&provider, &mut conversation, "  ").await.unwrap();
+    assert_eq!(response, "fix: something else");
+    assert!(provider.last_messages()[3].content.contains("different wording"));
+}
+
+#[tokio::test]
+async fn test_retry_after_edit_starts_from_the_edited_message() {
+    let provider = MockProvider::with_response("fix: something else");
+    let mut conversation = Conversation::new("system", "diff content");
+    conversation.push_response("fix: something");
+    conversation.push_edit("fix: something", "fix: handle empty passwords\n\nThey used to crash the login form.");
+
+    retry_with_feedback(&provider, &mut conversation, "").await.unwrap();
+
+    let sent = provider.last_messages();
+    let roles: Vec<Role> = sent.iter().map(|m| m.role).collect();
+    assert_eq!(roles, vec![Role::System, Role::User, Role::Assistant, Role::User, Role::Assistant, Role::User]);
+    assert!(sent[3].content.contains("fix: handle empty passwords"));
+    assert_eq!(sent[4].content, "fix: handle empty passwords\n\nThey used to crash the login form.");
+}
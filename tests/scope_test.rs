@@ -0,0 +1,75 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::process::Command as StdCommand;
+use tempfile::TempDir;
+use committo::config::CONFIG_FILE_NAME;
+
+fn git(repo: &Path, args: &[&str]) {
+    let output = StdCommand::new("git").current_dir(repo).args(args).output().unwrap();
+    assert!(output.status.success(), "git {:?} failed", args);
+}
+
+fn write(repo: &Path, path: &str, content: &str) {
+    let path = repo.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+/// Cargo workspace with two members and a config file in a fresh HOME
+fn setup_workspace() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let repo = temp_dir.path();
+    git(repo, &["init", "-q"]);
+    write(repo, CONFIG_FILE_NAME, "config-version = 1\napi-key = \"test_key\"\n");
+    write(repo, "Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+    write(repo, "crates/api/Cargo.toml", "[package]\nname = \"api\"\n");
+    write(repo, "crates/web/Cargo.toml", "[package]\nname = \"web\"\n");
+    temp_dir
+}
+
+fn committo(repo: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("committo").unwrap();
+    cmd.current_dir(repo).env("HOME", repo);
+    cmd
+}
+
+#[test]
+fn test_single_package_scope_is_suggested() {
+    let temp_dir = setup_workspace();
+    let repo = temp_dir.path();
+    write(repo, "crates/api/src/lib.rs", "pub fn handler() {}\n");
+    git(repo, &["add", "crates/api/src/lib.rs"]);
+
+    committo(repo)
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("belong to the package `api`"))
+        .stderr(predicate::str::contains("span").not());
+}
+
+#[test]
+fn test_several_packages_are_reported() {
+    let temp_dir = setup_workspace();
+    let repo = temp_dir.path();
+    write(repo, "crates/api/src/lib.rs", "pub fn handler() {}\n");
+    write(repo, "crates/web/src/lib.rs", "pub fn page() {}\n");
+    git(repo, &["add", "crates"]);
+
+    committo(repo)
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("span several packages (api, web)"))
+        .stderr(predicate::str::contains("these changes span 2 packages (api, web)"));
+
+    // Turned off, nothing is inferred
+    committo(repo).args(["set", "scope-mode", "off"]).assert().success();
+    committo(repo)
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Scope:").not());
+}
@@ -3,7 +3,13 @@ use predicates::prelude::*;
 use std::fs;
 use std::process::Command;
 use tempfile::tempdir;
-use committo::config::CONVENTION_FILE_NAME;
+use std::path::Path;
+use committo::config::{CONFIG_FILE_NAME, CONVENTION_FILE_NAME};
+
+/// Write a config with an API key so the interactive setup never runs
+fn write_test_config(home: &Path) -> std::io::Result<()> {
+    fs::write(home.join(CONFIG_FILE_NAME), "api-key = \"test_key_for_smoke_test\"\n")
+}
 
 #[test]
 fn smoke_test_full_workflow() -> Result<(), Box<dyn std::error::Error>> {
@@ -44,6 +50,8 @@ fn smoke_test_full_workflow() -> Result<(), Box<dyn std::error::Error>> {
     let mut cmd = Command::cargo_bin("committo")?;
     cmd.current_dir(&git_repo);
     cmd.env("OPENAI_API_KEY", "test_key_for_smoke_test");
+    write_test_config(temp_dir.path())?;
+    cmd.env("HOME", temp_dir.path());
     cmd.arg("generate").arg("--dry-run");
 
     cmd.assert()
@@ -95,6 +103,8 @@ fn smoke_test_multiple_files() -> Result<(), Box<dyn std::error::Error>> {
     let mut cmd = Command::cargo_bin("committo")?;
     cmd.current_dir(&git_repo);
     cmd.env("OPENAI_API_KEY", "test_key_for_smoke_test");
+    write_test_config(temp_dir.path())?;
+    cmd.env("HOME", temp_dir.path());
     cmd.arg("generate").arg("--dry-run");
 
     cmd.assert()
@@ -108,8 +118,8 @@ fn smoke_test_multiple_files() -> Result<(), Box<dyn std::error::Error>> {
 
 #[test]
 fn smoke_test_nested_convention_files() -> Result<(), Box<dyn std::error::Error>> {
-    let t1 = "1. For the entire project: Use the Conventional Commits format (feat, fix, docs).";
-    let t2 = "2. For the frontend: When modifying UI components, the component: prefix is required.";
+    let t1 = "2. For the entire project: Use the Conventional Commits format (feat, fix, docs).";
+    let t2 = "1. For the frontend: When modifying UI components, the component: prefix is required.";
     let guideline = "**IMPORTANT PRIORITY RULES:**";
     let temp_dir = tempdir()?;
     let git_repo = temp_dir.path().join("test_repo");
@@ -147,6 +157,8 @@ fn smoke_test_nested_convention_files() -> Result<(), Box<dyn std::error::Error>
     let mut cmd = Command::cargo_bin("committo")?;
     cmd.current_dir(&sub_dir);
     cmd.env("OPENAI_API_KEY", "test_key_for_smoke_test");
+    write_test_config(temp_dir.path())?;
+    cmd.env("HOME", temp_dir.path());
     cmd.arg("generate").arg("--dry-run");
 
     cmd.assert()
@@ -199,13 +211,15 @@ fn test_numbered_priority_convention_files() -> Result<(), Box<dyn std::error::E
     let mut cmd = Command::cargo_bin("committo")?;
     cmd.current_dir(&deep_dir);
     cmd.env("OPENAI_API_KEY", "test_key_for_smoke_test");
+    write_test_config(temp_dir.path())?;
+    cmd.env("HOME", temp_dir.path());
     cmd.arg("generate").arg("--dry-run");
 
     cmd.assert()
         .success()
-        .stdout(predicate::str::contains("1. Use Korean for commit messages"))
+        .stdout(predicate::str::contains("3. Use Korean for commit messages"))
         .stdout(predicate::str::contains("2. Frontend: Use component prefixes"))
-        .stdout(predicate::str::contains("3. Components: Describe UI changes in detail"))
+        .stdout(predicate::str::contains("1. Components: Describe UI changes in detail"))
         .stdout(predicate::str::contains("**IMPORTANT PRIORITY RULES:**"))
         .stdout(predicate::str::contains("Button.js"));
 
@@ -0,0 +1,71 @@
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+use committo::commit::CommitOptions;
+use committo::split::run_split;
+
+#[path = "common/mock.rs"]
+mod mock;
+use mock::MockProvider;
+
+fn git(repo: &Path, args: &[&str]) -> String {
+    let output = Command::new("git").current_dir(repo).args(args).output().unwrap();
+    assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Repo with one commit, then three staged changes: two hunks in one file and a new file
+fn setup_repo(repo: &Path) {
+    git(repo, &["init", "-q"]);
+    git(repo, &["config", "user.name", "Test User"]);
+    git(repo, &["config", "user.email", "test@example.com"]);
+
+    let original: Vec<String> = (1..=30).map(|i| format!("line {}", i)).collect();
+    fs::write(repo.join("a.txt"), original.join("\n") + "\n").unwrap();
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "-q", "-m", "initial"]);
+
+    let mut changed = original.clone();
+    changed[1] = "line 2 changed".to_string();
+    changed[27] = "line 28 changed".to_string();
+    fs::write(repo.join("a.txt"), changed.join("\n") + "\n").unwrap();
+    fs::write(repo.join("b.txt"), "new file\n").unwrap();
+    git(repo, &["add", "."]);
+}
+
+// A single test, since it changes the process working directory
+#[tokio::test]
+async fn test_split_commits_groups_and_restores_on_failure() {
+    let temp_dir = tempdir().unwrap();
+    let repo = temp_dir.path();
+    setup_repo(repo);
+    env::set_current_dir(repo).unwrap();
+
+    let grouping = r#"[{"message": "fix: change line 2", "hunks": [1]}, {"message": "feat: add b and line 28", "hunks": [2, 3]}]"#;
+
+    // A hook that rejects the second commit forces a rollback
+    let hook = repo.join(".git/hooks/pre-commit");
+    fs::write(&hook, "#!/bin/sh\ntest \"$(git rev-list --count HEAD)\" -lt 2\n").unwrap();
+    fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let head_before = git(repo, &["rev-parse", "HEAD"]);
+    let tree_before = git(repo, &["write-tree"]);
+
+    let provider = MockProvider::with_response(grouping);
+    assert!(run_split(&provider, false, true, false, &CommitOptions::default()).await.is_err());
+    assert_eq!(git(repo, &["rev-parse", "HEAD"]), head_before);
+    assert_eq!(git(repo, &["write-tree"]), tree_before);
+
+    // Without the hook the same plan goes through
+    fs::remove_file(&hook).unwrap();
+    run_split(&provider, false, true, false, &CommitOptions::default()).await.unwrap();
+
+    let log = git(repo, &["log", "--format=%s"]);
+    assert_eq!(log, "feat: add b and line 28\nfix: change line 2\ninitial");
+    assert_eq!(git(repo, &["show", "--name-only", "--format=", "HEAD~1"]), "a.txt");
+    assert_eq!(git(repo, &["diff", "--staged", "--name-only"]), "");
+    assert_eq!(git(repo, &["rev-parse", "HEAD^{tree}"]), tree_before);
+}
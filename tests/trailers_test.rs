@@ -0,0 +1,48 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+use committo::commit::{execute_git_commit, known_co_authors, CommitOptions, Trailers, CO_AUTHORED_BY, SIGNED_OFF_BY};
+use committo::git::committer_identity;
+
+fn git(repo: &Path, args: &[&str]) -> String {
+    let output = Command::new("git").current_dir(repo).args(args).output().unwrap();
+    assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+// A single test, since it changes the process working directory
+#[test]
+fn test_trailers_are_added_on_commit() {
+    let temp_dir = tempdir().unwrap();
+    let repo = temp_dir.path();
+    git(repo, &["init", "-q"]);
+    git(repo, &["config", "user.name", "Test User"]);
+    git(repo, &["config", "user.email", "test@example.com"]);
+    fs::write(repo.join("a.txt"), "a\n").unwrap();
+    git(repo, &["add", "."]);
+    git(repo, &["-c", "user.name=Ann Lee", "-c", "user.email=ann@example.com", "commit", "-q", "-m", "initial"]);
+    env::set_current_dir(repo).unwrap();
+
+    assert_eq!(committer_identity().unwrap(), "Test User <test@example.com>");
+    // The committer is never offered as a co-author
+    let known = known_co_authors(&["Bob Kim <bob@example.org>".to_string()]).unwrap();
+    assert_eq!(known, vec!["Bob Kim <bob@example.org>", "Ann Lee <ann@example.com>"]);
+
+    let mut trailers = Trailers::default();
+    trailers.push("Refs", "#12");
+    trailers.push(CO_AUTHORED_BY, "Ann Lee <ann@example.com>");
+    trailers.push(SIGNED_OFF_BY, &committer_identity().unwrap());
+
+    fs::write(repo.join("a.txt"), "a changed\n").unwrap();
+    git(repo, &["add", "."]);
+    // An existing identical trailer isn't repeated
+    let message = "feat: change a\n\nMore detail.\n\nRefs: #12";
+    execute_git_commit(message, &CommitOptions { trailers, ..Default::default() }).unwrap();
+
+    assert_eq!(
+        git(repo, &["log", "-1", "--format=%B"]),
+        "feat: change a\n\nMore detail.\n\nRefs: #12\nCo-authored-by: Ann Lee <ann@example.com>\nSigned-off-by: Test User <test@example.com>"
+    );
+}